@@ -1,25 +1,120 @@
-use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::Utc;
+use futures::stream::BoxStream;
+use futures::FutureExt;
 use serde_json::json;
-use tokio::fs;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::deepseek::{ChatMessage, DeepSeekClient};
-use crate::types::{SolutionV1, TaskSpec, ValidationV1};
+use crate::align::align_checks;
+use crate::deepseek::ChatMessage;
+use crate::llm::LlmClient;
+use crate::scoring::ScoringStrategy;
+use crate::storage::ArtifactStore;
+use crate::types::{SolutionV1, TaskSpec, ValidationRecord, ValidationV1, DELIVERABLE_TYPE_CRITERION};
 
-use super::{Agent, AgentError};
+use super::parse::{parse_model_json, strip_think_blocks};
+use super::{actual_model_used, event_stream, localized_prompt, AgentEvent, EventSink, save_raw_response, Agent, AgentError};
 
 #[derive(Clone)]
 pub struct AuditorAgent {
-    client: DeepSeekClient,
-    out_path: PathBuf,
+    client: Arc<dyn LlmClient>,
+    store: Arc<dyn ArtifactStore>,
+    key: String,
+    save_raw_on_parse_error: bool,
+    scoring: Option<Arc<dyn ScoringStrategy>>,
+    align_threshold: Option<f32>,
+    cite_lines: bool,
+    execute_timeout: Option<Duration>,
+    history_key: Option<String>,
+    keep_reasoning: bool,
 }
 
 impl AuditorAgent {
-    pub fn new(client: DeepSeekClient, out_path: PathBuf) -> Self {
-        Self { client, out_path }
+    /// Create an agent that persists its artifact under `key` in `store`
+    pub fn new(client: impl LlmClient + 'static, store: Arc<dyn ArtifactStore>, key: impl Into<String>) -> Self {
+        Self {
+            client: Arc::new(client),
+            store,
+            key: key.into(),
+            save_raw_on_parse_error: false,
+            scoring: None,
+            align_threshold: None,
+            cite_lines: false,
+            execute_timeout: None,
+            history_key: None,
+            keep_reasoning: false,
+        }
+    }
+
+    /// Recompute score and verdict from the checks with `strategy` instead of trusting the model
+    pub fn with_scoring(mut self, strategy: Option<Arc<dyn ScoringStrategy>>) -> Self {
+        self.scoring = strategy;
+        self
+    }
+
+    /// Match each returned check to the most similar acceptance criterion (similarity at
+    /// least `threshold`) and adopt its exact wording; unmatched checks are flagged
+    pub fn with_criterion_alignment(mut self, threshold: Option<f32>) -> Self {
+        self.align_threshold = threshold;
+        self
+    }
+
+    /// Number the lines of a text/code deliverable in the prompt and ask for the lines
+    /// supporting each check in `evidence_lines`
+    pub fn with_cite_lines(mut self, enabled: bool) -> Self {
+        self.cite_lines = enabled;
+        self
+    }
+
+    /// Bound the whole run, retries included, to `timeout`; exceeding it fails the run
+    /// with `AgentError::Unexpected("agent timeout")`
+    pub fn with_execute_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.execute_timeout = timeout;
+        self
+    }
+
+    /// Store the model's reasoning in `ValidationV1::reasoning`: the response's separate
+    /// `reasoning_content`, else its inline `<think>` blocks (stripped before parsing either way)
+    pub fn with_keep_reasoning(mut self, enabled: bool) -> Self {
+        self.keep_reasoning = enabled;
+        self
+    }
+
+    /// Also append every validation, timestamped, to the JSON array stored under `key`, so
+    /// re-audits of a solution keep the earlier verdicts
+    pub fn with_history_key(mut self, key: Option<String>) -> Self {
+        self.history_key = key;
+        self
+    }
+
+    /// Append `validation` to the history under `key`. An unreadable history is an error
+    /// rather than being replaced, so the audit trail is never lost.
+    async fn append_to_history(&self, key: &str, validation: &ValidationV1) -> Result<(), AgentError> {
+        let mut history: Vec<ValidationRecord> = match self.store.load(key).await {
+            Some(existing) if !existing.trim().is_empty() => serde_json::from_str(&existing)?,
+            _ => Vec::new(),
+        };
+        history.push(ValidationRecord {
+            appended_at: Utc::now().to_rfc3339(),
+            validation: validation.clone(),
+        });
+        self.store.save(key, &self.client.config().artifact_json(&history)?).await?;
+        info!(
+            "AuditorAgent: appended validation {} of {} to {}",
+            history.len(),
+            validation.solution_id,
+            self.store.locate(key)
+        );
+        Ok(())
+    }
+
+    /// When enabled, an unparseable model response is saved next to the artifact before the error is returned
+    pub fn with_save_raw_on_parse_error(mut self, enabled: bool) -> Self {
+        self.save_raw_on_parse_error = enabled;
+        self
     }
 }
 
@@ -28,23 +123,14 @@ pub struct AuditInput {
     pub solution: SolutionV1,
 }
 
-#[async_trait]
-impl Agent for AuditorAgent {
-    type Input = AuditInput;
-    type Output = ValidationV1;
-
-    async fn execute(&self, input: &Self::Input) -> Result<Self::Output, AgentError> {
-        info!(
-            "AuditorAgent: preparing output directory at {}",
-            self.out_path.display()
-        );
-        fs::create_dir_all(
-            self.out_path
-                .parent()
-                .ok_or_else(|| AgentError::Unexpected("invalid output path".into()))?,
-        )
-        .await?;
-
+impl AuditorAgent {
+    /// One run: audit `input.solution` against `input.task`, reporting progress to `events`
+    async fn run(
+        &self,
+        input: &AuditInput,
+        events: EventSink<ValidationV1>,
+    ) -> Result<ValidationV1, AgentError> {
+        events.emit(AgentEvent::PreparingOutput);
         let system_prompt = r#"
             You are Agent 2. Given TaskSpec and a SolutionV1, grade it strictly against acceptance_criteria. Output ONLY JSON matching ValidationV1.
 
@@ -62,8 +148,9 @@ impl Agent for AuditorAgent {
                 "criterion": "Acceptance criterion being assessed (string)",
                 "pass": "Whether this criterion passed (boolean)",
                 "reason": "Explanation for the outcome (string)",
-                "severity": "Impact level if failing: 'minor' | 'major' (string)",
-                "suggested_fix": "Optional suggestion to remediate a failure (string or null)"
+                "severity": "Impact level if failing: 'minor' | 'major' | 'critical' (string); use 'critical' only for violations that must block the deliverable, e.g. security issues",
+                "suggested_fix": "Optional suggestion to remediate a failure (string or null)",
+                "partial_score": "Optional degree to which the criterion is met, in [0.0, 1.0], for criteria that are partly met (number or null)"
                 }
             ],
             "suggested_rewrite": "Optional repaired content or structured fix (any JSON value or null)",
@@ -75,15 +162,36 @@ impl Agent for AuditorAgent {
             }
         "#;
 
-        let user_payload = json!({
+        let type_check = input.task.deliverable_type_check(&input.solution);
+
+        let mut user_payload = json!({
             "task_spec": input.task,
             "solution": input.solution,
-            "instructions": "Include one check per acceptance_criteria item. Set verdict and a score in [0.0, 1.0]."
+            "instructions": "Include one check per acceptance_criteria item. Set partial_score when a criterion is partly met. Set verdict and a score in [0.0, 1.0] that accounts for partial scores."
         });
+        let numbered = self.cite_lines.then(|| numbered_deliverable(&input.solution)).flatten();
+        if let Some(numbered) = numbered {
+            user_payload["numbered_deliverable"] = json!(numbered);
+            user_payload["instructions"] = json!(format!(
+                "{} {}",
+                user_payload["instructions"].as_str().unwrap_or_default(),
+                CITE_LINES_INSTRUCTION
+            ));
+        }
+        if let Some(guidance) = input.task.scoring_guidance.as_deref().map(str::trim)
+            && !guidance.is_empty()
+        {
+            user_payload["instructions"] = json!(format!(
+                "{} {} {}",
+                user_payload["instructions"].as_str().unwrap_or_default(),
+                SCORING_GUIDANCE_INSTRUCTION,
+                guidance
+            ));
+        }
 
         let messages = vec![
-            ChatMessage { role: "system".to_string(), content: system_prompt.to_string() },
-            ChatMessage { role: "user".to_string(), content: user_payload.to_string() },
+            ChatMessage::system(localized_prompt(system_prompt, self.client.config())),
+            ChatMessage::user(user_payload.to_string()),
         ];
 
         info!(
@@ -91,25 +199,466 @@ impl Agent for AuditorAgent {
             input.solution.solution_id,
             input.task.task_id
         );
-        let raw = self.client.send_messages_raw(messages).await?;
+        events.emit(AgentEvent::SendingRequest);
+        let completion = self.client.send_messages_with_reasoning(messages).await?;
+        let raw = completion.content;
+        events.emit(AgentEvent::ReceivedResponse);
         info!("AuditorAgent: received model response, parsing JSON");
-        let mut validation: ValidationV1 = serde_json::from_str(&raw)?;
+        events.emit(AgentEvent::Parsing);
+        let (answer, inline_reasoning) = strip_think_blocks(&raw);
+        let mut validation: ValidationV1 = match parse_model_json(answer) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                if self.save_raw_on_parse_error {
+                    save_raw_response(self.store.as_ref(), &self.key, &raw).await?;
+                }
+                return Err(e);
+            }
+        };
         if validation.schema_version.is_empty() {
             validation.schema_version = "validation_v1".to_string();
         }
         if validation.created_at.trim().is_empty() {
             validation.created_at = Utc::now().to_rfc3339();
         }
+        validation.model_used = actual_model_used(self.client.config());
+        if inline_reasoning.is_some() {
+            info!("AuditorAgent: stripped inline <think> reasoning from the response");
+        }
+        // deepseek-reasoner returns it in `reasoning_content`; other models may inline it
+        validation.reasoning =
+            completion.reasoning.or(inline_reasoning).filter(|_| self.keep_reasoning);
+        if let Some(original) = validation.normalize_score() {
+            warn!(
+                "AuditorAgent: model-reported score {} is outside [0.0, 1.0]; corrected to {}",
+                original,
+                validation.score
+            );
+        }
+        // The deterministic check replaces any model-written one for the same criterion
+        validation
+            .checks
+            .retain(|c| !c.criterion.eq_ignore_ascii_case(DELIVERABLE_TYPE_CRITERION));
+        if let Some(threshold) = self.align_threshold {
+            let renamed = align_checks(&mut validation.checks, &input.task.acceptance_criteria, threshold);
+            if renamed > 0 {
+                info!("AuditorAgent: aligned {} check(s) with the task's criterion wording", renamed);
+            }
+            for check in validation.checks.iter().filter(|c| c.unmatched) {
+                warn!("AuditorAgent: check '{}' matches no acceptance criterion", check.criterion);
+            }
+        }
+        if !type_check.pass_ {
+            warn!(
+                "AuditorAgent: solution {} fails the deliverable type check: {}",
+                input.solution.solution_id,
+                type_check.reason
+            );
+        }
+        validation.checks.insert(0, type_check);
+        let dropped = validation.discard_invalid_partial_scores();
+        if dropped > 0 {
+            warn!(
+                "AuditorAgent: ignored {} partial_score value(s) outside [0.0, 1.0]",
+                dropped
+            );
+        }
+        if let Some(strategy) = &self.scoring {
+            let (score, verdict) = strategy.score(&validation.checks);
+            info!(
+                "AuditorAgent: deterministic scoring {} ({:.2}) replaces model-reported {} ({:.2})",
+                verdict,
+                score,
+                validation.verdict,
+                validation.score
+            );
+            validation.score = score;
+            validation.verdict = verdict;
+        }
+        if validation.enforce_critical_failures() {
+            warn!(
+                "AuditorAgent: critical check failed; verdict forced to fail for solution {}",
+                validation.solution_id
+            );
+        }
 
-        let pretty = serde_json::to_string_pretty(&validation)?;
-        fs::write(&self.out_path, pretty).await?;
+        let pretty = self.client.config().artifact_json(&validation)?;
+        self.store.save(&self.key, &pretty).await?;
         info!(
             "AuditorAgent: saved validation for solution {} to {}",
             validation.solution_id,
-            self.out_path.display()
+            self.store.locate(&self.key)
         );
+        if let Some(history_key) = &self.history_key {
+            self.append_to_history(history_key, &validation).await?;
+        }
         Ok(validation)
     }
 }
 
+/// Introduces the task's `scoring_guidance` in the audit instructions
+const SCORING_GUIDANCE_INSTRUCTION: &str = "Weigh the criteria in the verdict and score as the task author asks:";
+
+/// Appended to the audit instructions with `with_cite_lines`
+const CITE_LINES_INSTRUCTION: &str = "For each check, set evidence_lines to the numbers of the numbered_deliverable lines that support its outcome (array of integers, or null when no line applies).";
+
+/// Text or code deliverable with each line prefixed by its 1-based number; `None` for JSON
+fn numbered_deliverable(solution: &SolutionV1) -> Option<String> {
+    let text = match (&solution.deliverable.text, &solution.deliverable.code) {
+        (Some(text), _) => text,
+        (None, Some(code)) => &code.content,
+        (None, None) => return None,
+    };
+    Some(
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| format!("{}: {}", i + 1, line))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+#[async_trait]
+impl Agent for AuditorAgent {
+    type Input = AuditInput;
+    type Output = ValidationV1;
+
+    fn execute_with_events<'a>(
+        &'a self,
+        input: &'a Self::Input,
+    ) -> BoxStream<'a, AgentEvent<Self::Output>> {
+        event_stream(self.store.locate(&self.key), self.execute_timeout, move |events| self.run(input, events).boxed())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStore;
+    use crate::test_support::{
+        mock_client, mount_completion, sample_solution_json, sample_task, sample_validation_json,
+    };
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn execute_saves_validation_to_store() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_validation_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store.clone(), "validation.json");
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let validation = agent.execute(&input).await.unwrap();
+
+        let saved = store.load("validation.json").await.expect("validation saved");
+        let parsed: ValidationV1 = serde_json::from_str(&saved).unwrap();
+        assert_eq!(parsed.solution_id, validation.solution_id);
+    }
+
+    #[tokio::test]
+    async fn inline_reasoning_is_stripped_and_kept_on_request() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &format!("<think>Both criteria hold.</think>\n{}", sample_validation_json())).await;
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store.clone(), "validation.json");
+        assert!(agent.execute(&input).await.unwrap().reasoning.is_none());
+        let kept = agent.with_keep_reasoning(true).execute(&input).await.unwrap();
+        assert_eq!(kept.reasoning.as_deref(), Some("Both criteria hold."));
+        let saved: ValidationV1 = serde_json::from_str(&store.load("validation.json").await.unwrap()).unwrap();
+        assert_eq!(saved.reasoning.as_deref(), Some("Both criteria hold."));
+    }
+
+    #[tokio::test]
+    async fn separate_reasoning_content_is_kept_on_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": sample_validation_json(),
+                        "reasoning_content": "Checked each criterion."
+                    }
+                }]
+            })))
+            .mount(&server)
+            .await;
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store, "validation.json");
+        assert!(agent.execute(&input).await.unwrap().reasoning.is_none());
+        let kept = agent.with_keep_reasoning(true).execute(&input).await.unwrap();
+        assert_eq!(kept.reasoning.as_deref(), Some("Checked each criterion."));
+    }
+
+    #[tokio::test]
+    async fn validations_are_appended_to_the_history_in_order() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_validation_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store.clone(), "validation.json")
+            .with_history_key(Some("validations.json".to_string()));
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let first = agent.execute(&input).await.unwrap();
+        server.reset().await;
+        let mut failing: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        failing["verdict"] = json!("fail");
+        mount_completion(&server, &failing.to_string()).await;
+        let second = agent.execute(&input).await.unwrap();
+
+        let history: Vec<ValidationRecord> =
+            serde_json::from_str(&store.load("validations.json").await.unwrap()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].validation.created_at, first.created_at);
+        assert!(matches!(history[0].validation.verdict, crate::types::Verdict::Pass));
+        assert!(matches!(second.verdict, crate::types::Verdict::Fail));
+        assert!(matches!(history[1].validation.verdict, crate::types::Verdict::Fail));
+        assert!(history[0].appended_at <= history[1].appended_at);
+        // validation.json still holds only the latest audit
+        let latest: ValidationV1 = serde_json::from_str(&store.load("validation.json").await.unwrap()).unwrap();
+        assert!(matches!(latest.verdict, crate::types::Verdict::Fail));
+    }
+
+    #[tokio::test]
+    async fn saved_validation_uses_the_configured_indentation() {
+        use crate::config::{Config, JsonIndent};
+        use crate::deepseek::DeepSeekClient;
+        use crate::test_support::mock_config;
+
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_validation_json()).await;
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+
+        for (indent, prefix) in [(JsonIndent::Spaces(4), "\n    \""), (JsonIndent::Tab, "\n\t\"")] {
+            let config = Config { json_indent: indent, ..mock_config(&server) };
+            let client = DeepSeekClient::new(config).unwrap();
+            let store = Arc::new(MemoryStore::new());
+            let agent = AuditorAgent::new(client, store.clone(), "validation.json");
+            agent.execute(&input).await.unwrap();
+
+            let saved = store.load("validation.json").await.unwrap();
+            assert!(saved.starts_with(&format!("{{{}", prefix)), "{}", saved);
+            serde_json::from_str::<ValidationV1>(&saved).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn model_used_reflects_the_client_config_not_the_model_output() {
+        let server = MockServer::start().await;
+        // The model claims deepseek-reasoner at 0.7
+        mount_completion(&server, &sample_validation_json()).await;
+
+        let client = mock_client(&server).with_temperature(0.0);
+        let agent = AuditorAgent::new(client, Arc::new(MemoryStore::new()), "validation.json");
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let validation = agent.execute(&input).await.unwrap();
+
+        assert_eq!(validation.model_used.name, "deepseek-chat");
+        assert_eq!(validation.model_used.temperature, 0.0);
+    }
+
+    #[tokio::test]
+    async fn scoring_strategy_overrides_model_score() {
+        let server = MockServer::start().await;
+        let mut reported: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        reported["checks"][0]["pass"] = json!(false);
+        mount_completion(&server, &reported.to_string()).await;
+
+        let agent = AuditorAgent::new(mock_client(&server), Arc::new(MemoryStore::new()), "validation.json")
+            .with_scoring(Some(crate::scoring::ScoringKind::Strict.strategy()));
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let validation = agent.execute(&input).await.unwrap();
+        // The failed criterion and the passing deterministic deliverable-type check
+        assert_eq!(validation.score, 0.5);
+        assert!(matches!(validation.verdict, crate::types::Verdict::Fail));
+    }
+
+    #[tokio::test]
+    async fn out_of_range_model_score_is_clamped() {
+        let server = MockServer::start().await;
+        let mut reported: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        reported["score"] = json!(1.5);
+        mount_completion(&server, &reported.to_string()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store, "validation.json");
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let validation = agent.execute(&input).await.unwrap();
+        assert_eq!(validation.score, 1.0);
+    }
 
+    #[tokio::test]
+    async fn criterion_alignment_adopts_task_wording() {
+        let server = MockServer::start().await;
+        let mut reported: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        reported["checks"][0]["criterion"] = json!("Exactly three bullet points");
+        reported["checks"].as_array_mut().unwrap().push(json!({
+            "criterion": "Uses formal tone",
+            "pass": true,
+            "reason": "",
+            "severity": "minor"
+        }));
+        mount_completion(&server, &reported.to_string()).await;
+
+        let agent = AuditorAgent::new(mock_client(&server), Arc::new(MemoryStore::new()), "validation.json")
+            .with_criterion_alignment(Some(crate::align::DEFAULT_MATCH_THRESHOLD));
+        let input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let validation = agent.execute(&input).await.unwrap();
+
+        // The deterministic type check comes first and is never aligned
+        assert_eq!(validation.checks[0].criterion, DELIVERABLE_TYPE_CRITERION);
+        assert!(!validation.checks[0].unmatched);
+        assert_eq!(validation.checks[1].criterion, "exactly 3 bullets");
+        assert!(!validation.checks[1].unmatched);
+        assert_eq!(validation.checks[2].criterion, "Uses formal tone");
+        assert!(validation.checks[2].unmatched);
+    }
+
+    #[tokio::test]
+    async fn cite_lines_numbers_the_deliverable_and_keeps_evidence_lines() {
+        let server = MockServer::start().await;
+        let mut reported: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        reported["checks"][0]["evidence_lines"] = json!([1, 3]);
+        mount_completion(&server, &reported.to_string()).await;
+
+        let mut solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        solution.deliverable.text = Some("- one\n- two\n- three".to_string());
+        let agent = AuditorAgent::new(mock_client(&server), Arc::new(MemoryStore::new()), "validation.json")
+            .with_cite_lines(true);
+        let validation = agent
+            .execute(&AuditInput { task: sample_task(), solution })
+            .await
+            .unwrap();
+        assert_eq!(validation.checks[1].evidence_lines, Some(vec![1, 3]));
+        assert_eq!(validation.checks[0].evidence_lines, None);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let payload: serde_json::Value =
+            serde_json::from_str(body["messages"][1]["content"].as_str().unwrap()).unwrap();
+        assert_eq!(payload["numbered_deliverable"], "1: - one\n2: - two\n3: - three");
+        assert!(payload["instructions"].as_str().unwrap().contains("evidence_lines"));
+    }
+
+    #[tokio::test]
+    async fn output_language_is_requested_in_the_system_prompt() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_validation_json()).await;
+
+        let config = crate::config::Config {
+            output_language: Some("Japanese".to_string()),
+            ..crate::test_support::mock_config(&server)
+        };
+        let client = crate::deepseek::DeepSeekClient::new(config).unwrap();
+        let agent = AuditorAgent::new(client, Arc::new(MemoryStore::new()), "validation.json");
+        agent
+            .execute(&AuditInput {
+                task: sample_task(),
+                solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+            })
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let system = body["messages"][0]["content"].as_str().unwrap();
+        assert!(system.contains("Schema (ValidationV1)"));
+        assert!(system.contains("Respond in Japanese."), "{}", system);
+    }
+
+    #[tokio::test]
+    async fn scoring_guidance_is_added_to_the_audit_instructions() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_validation_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store, "validation.json");
+        let mut input = AuditInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        agent.execute(&input).await.unwrap();
+        input.task.scoring_guidance = Some("Treat correctness as twice as important as style.".to_string());
+        agent.execute(&input).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let instructions = |i: usize| {
+            let body: serde_json::Value = serde_json::from_slice(&requests[i].body).unwrap();
+            let user: serde_json::Value =
+                serde_json::from_str(body["messages"][1]["content"].as_str().unwrap()).unwrap();
+            user["instructions"].as_str().unwrap().to_string()
+        };
+        assert!(!instructions(0).contains(SCORING_GUIDANCE_INSTRUCTION));
+        assert!(instructions(1).ends_with(&format!(
+            "{} Treat correctness as twice as important as style.",
+            SCORING_GUIDANCE_INSTRUCTION
+        )));
+    }
+
+    #[tokio::test]
+    async fn deliverable_type_mismatch_adds_a_failing_check() {
+        let server = MockServer::start().await;
+        // The model itself reports a pass and even claims the type check passed
+        let mut reported: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        reported["checks"].as_array_mut().unwrap().push(json!({
+            "criterion": DELIVERABLE_TYPE_CRITERION,
+            "pass": true,
+            "reason": "looks fine",
+            "severity": "minor"
+        }));
+        mount_completion(&server, &reported.to_string()).await;
+
+        let mut task = sample_task();
+        task.deliverable_type = crate::types::DeliverableType::Code;
+        let store = Arc::new(MemoryStore::new());
+        let agent = AuditorAgent::new(mock_client(&server), store, "validation.json");
+        let input = AuditInput {
+            task,
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+        };
+        let validation = agent.execute(&input).await.unwrap();
+
+        let type_checks: Vec<_> = validation
+            .checks
+            .iter()
+            .filter(|c| c.criterion == DELIVERABLE_TYPE_CRITERION)
+            .collect();
+        assert_eq!(type_checks.len(), 1);
+        assert!(!type_checks[0].pass_);
+        assert!(type_checks[0].reason.contains("'code'"), "{}", type_checks[0].reason);
+        assert!(matches!(validation.verdict, crate::types::Verdict::Fail));
+    }
+}
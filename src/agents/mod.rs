@@ -1,4 +1,10 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::future::{self, BoxFuture};
+use futures::stream::{self, BoxStream};
+use futures::{FutureExt, StreamExt};
 
 #[derive(Debug, thiserror::Error)]
 pub enum AgentError {
@@ -8,21 +14,156 @@ pub enum AgentError {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
+    #[error("Failed to parse model response: {source}; near: {snippet}")]
+    Parse {
+        source: serde_json::Error,
+        snippet: String,
+    },
+    #[allow(dead_code)]
     #[error("Unexpected: {0}")]
     Unexpected(String),
 }
 
+/// Progress of a single agent run, in the order emitted. A run ends with exactly one
+/// `Saved` or `Failed`.
+#[derive(Debug)]
+pub enum AgentEvent<T> {
+    PreparingOutput,
+    SendingRequest,
+    ReceivedResponse,
+    Parsing,
+    Saved { location: String, output: T },
+    Failed(AgentError),
+}
+
 #[async_trait]
-pub trait Agent {
+pub trait Agent: Sync {
     type Input: Send + Sync;
     type Output: Send + Sync;
-    async fn execute(&self, input: &Self::Input) -> Result<Self::Output, AgentError>;
+
+    /// Run the agent, streaming its progress; the stream ends after `Saved` or `Failed`
+    fn execute_with_events<'a>(
+        &'a self,
+        input: &'a Self::Input,
+    ) -> BoxStream<'a, AgentEvent<Self::Output>>;
+
+    /// Run the agent and return its output, discarding the progress events
+    async fn execute(&self, input: &Self::Input) -> Result<Self::Output, AgentError> {
+        drain_events(self.execute_with_events(input)).await
+    }
+}
+
+/// Sender half handed to an agent run for reporting progress
+pub struct EventSink<T>(mpsc::UnboundedSender<AgentEvent<T>>);
+
+impl<T> EventSink<T> {
+    pub fn emit(&self, event: AgentEvent<T>) {
+        // The receiver is only gone when the caller dropped the stream; nothing to report to
+        let _ = self.0.unbounded_send(event);
+    }
+}
+
+/// Error message of a run cut off by its `execute_timeout`
+pub const AGENT_TIMEOUT: &str = "agent timeout";
+
+/// Drive `run` as part of the returned stream, then emit `Saved` (at `location`) or `Failed`.
+/// With a `timeout`, a run still going when it elapses is dropped and reported as failed.
+fn event_stream<'a, T: Send + 'a>(
+    location: String,
+    timeout: Option<Duration>,
+    run: impl FnOnce(EventSink<T>) -> BoxFuture<'a, Result<T, AgentError>> + Send + 'a,
+) -> BoxStream<'a, AgentEvent<T>> {
+    let (tx, rx) = mpsc::unbounded();
+    let driver = async move {
+        let sink = EventSink(tx);
+        let run = run(EventSink(sink.0.clone()));
+        let outcome = match timeout {
+            Some(limit) => tokio::time::timeout(limit, run)
+                .await
+                .unwrap_or_else(|_| Err(AgentError::Unexpected(AGENT_TIMEOUT.to_string()))),
+            None => run.await,
+        };
+        sink.emit(match outcome {
+            Ok(output) => AgentEvent::Saved { location, output },
+            Err(e) => AgentEvent::Failed(e),
+        });
+    };
+    // The driver yields no items; `rx` ends once the driver (and with it every sender) is done
+    let driver = driver.into_stream().filter_map(|()| future::ready(None));
+    stream::select(driver, rx).boxed()
+}
+
+/// Consume an event stream down to its terminal `Saved` or `Failed`
+async fn drain_events<T>(mut events: BoxStream<'_, AgentEvent<T>>) -> Result<T, AgentError> {
+    while let Some(event) = events.next().await {
+        match event {
+            AgentEvent::Saved { output, .. } => return Ok(output),
+            AgentEvent::Failed(e) => return Err(e),
+            _ => {}
+        }
+    }
+    Err(AgentError::Unexpected("agent stopped without a result".to_string()))
+}
+
+/// Key under which the raw model response for artifact `key` is saved
+/// (`solution.json` → `solution.raw.txt`)
+pub fn raw_response_key(key: &str) -> String {
+    format!("{}.raw.txt", key.strip_suffix(".json").unwrap_or(key))
+}
+
+/// Persist an unparseable model response next to the artifact so it can be salvaged manually
+async fn save_raw_response(
+    store: &dyn crate::storage::ArtifactStore,
+    key: &str,
+    raw: &str,
+) -> Result<(), AgentError> {
+    let raw_key = raw_response_key(key);
+    store.save(&raw_key, raw).await?;
+    tracing::warn!(
+        "Model response could not be parsed; raw response saved to {}",
+        store.locate(&raw_key)
+    );
+    Ok(())
+}
+
+/// Validate a `Json` deliverable against the task's schema and record the violations on the solution
+fn record_schema_violations(task: &crate::types::TaskSpec, solution: &mut crate::types::SolutionV1) {
+    solution.evidence.schema_violations = task.json_schema_violations(&solution.deliverable);
+    if !solution.evidence.schema_violations.is_empty() {
+        tracing::warn!(
+            "Solution {} violates the task's json_schema: {}",
+            solution.solution_id,
+            solution.evidence.schema_violations.join("; ")
+        );
+    }
+}
+
+/// The model and temperature the request was actually sent with; replaces whatever the
+/// model wrote into `model_used`
+fn actual_model_used(config: &crate::config::Config) -> crate::types::ModelUsed {
+    crate::types::ModelUsed { name: config.model.clone(), temperature: config.temperature }
+}
+
+/// `prompt` followed by an instruction to write in `config.output_language`, when set
+fn localized_prompt(prompt: &str, config: &crate::config::Config) -> String {
+    match &config.output_language {
+        Some(language) => format!(
+            "{}\n\nRespond in {}. Keep JSON keys and enum values such as verdict and severity in English; write all other string values in {}.",
+            prompt.trim_end(),
+            language,
+            language
+        ),
+        None => prompt.to_string(),
+    }
 }
 
 pub mod producer;
 pub mod auditor;
+pub mod parse;
+pub mod revision;
 
-pub use producer::ProducerAgent;
+pub use producer::{seed_draft_from, ProducerAgent, StreamTarget};
 pub use auditor::{AuditorAgent, AuditInput};
+pub use revision::{RevisionAgent, RevisionInput};
 
 
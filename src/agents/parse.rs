@@ -0,0 +1,280 @@
+use serde::de::DeserializeOwned;
+
+use super::AgentError;
+
+/// Characters of context shown on each side of a parse error position
+const SNIPPET_RADIUS: usize = 40;
+
+/// Byte order mark some models emit before their output
+const UTF8_BOM: char = '\u{feff}';
+
+/// Tags around the inline reasoning some reasoner deployments emit before the answer
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// The JSON document of a model response: without a leading BOM, leading `<think>` blocks
+/// and surrounding whitespace
+pub fn extract_json(raw: &str) -> &str {
+    strip_think_blocks(raw.trim().trim_start_matches(UTF8_BOM)).0.trim()
+}
+
+/// Split inline reasoning off a model response: the `<think>...</think>` blocks before the
+/// answer are removed, as is text ending in a bare `</think>` (the opening tag is sometimes
+/// left out). Returns the answer and the reasoning, blocks joined by a blank line. Only
+/// reasoning ahead of the JSON counts, so tags inside the answer's strings are kept.
+pub fn strip_think_blocks(raw: &str) -> (&str, Option<String>) {
+    let mut rest = raw.trim_start();
+    let mut blocks = Vec::new();
+    if let Some(close) = rest.find(THINK_CLOSE)
+        && !rest[..close].contains(THINK_OPEN)
+        && !rest[..close].contains('{')
+    {
+        blocks.push(&rest[..close]);
+        rest = rest[close + THINK_CLOSE.len()..].trim_start();
+    }
+    while let Some(after_open) = rest.strip_prefix(THINK_OPEN) {
+        // An unclosed block has no answer after it; leave it for the parse error to show
+        let Some(close) = after_open.find(THINK_CLOSE) else {
+            break;
+        };
+        blocks.push(&after_open[..close]);
+        rest = after_open[close + THINK_CLOSE.len()..].trim_start();
+    }
+    let reasoning: Vec<&str> = blocks
+        .into_iter()
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .collect();
+    (rest, (!reasoning.is_empty()).then(|| reasoning.join("\n\n")))
+}
+
+/// Deserialize a model response, attaching the surrounding raw text to parse failures.
+/// When the bare document does not deserialize, a response wrapping the expected object
+/// under a single key (`{"solution": {...}}`) is unwrapped, and failing that the last
+/// complete JSON object in the text is used (prose or drafts before the final answer).
+pub fn parse_model_json<T: DeserializeOwned>(raw: &str) -> Result<T, AgentError> {
+    let json = extract_json(raw);
+    serde_json::from_str(json).or_else(|source| {
+        unwrap_single_key(json)
+            .or_else(|| parse_last_object(json))
+            .ok_or_else(|| AgentError::Parse {
+                snippet: error_snippet(json, source.line(), source.column()),
+                source,
+            })
+    })
+}
+
+/// The last complete top-level `{...}` in `text`, found by matching braces outside strings
+pub fn last_json_object(text: &str) -> Option<&str> {
+    let (mut depth, mut start, mut last) = (0usize, 0usize, None);
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, byte) in text.bytes().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            // Quotes in prose between objects are not JSON strings
+            b'"' if depth > 0 => in_string = true,
+            b'{' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            b'}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    last = Some(&text[start..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    last
+}
+
+/// `T` from the last JSON object in `json`, when that is not the whole text
+fn parse_last_object<T: DeserializeOwned>(json: &str) -> Option<T> {
+    let object = last_json_object(json).filter(|object| object.len() < json.len())?;
+    let parsed = serde_json::from_str(object)
+        .ok()
+        .or_else(|| unwrap_single_key(object))?;
+    tracing::warn!("Model response holds more than one JSON value or extra text; used the last object");
+    Some(parsed)
+}
+
+/// The value of a one-key JSON object, if it deserializes as `T`
+fn unwrap_single_key<T: DeserializeOwned>(json: &str) -> Option<T> {
+    let serde_json::Value::Object(object) = serde_json::from_str(json).ok()? else {
+        return None;
+    };
+    if object.len() != 1 {
+        return None;
+    }
+    let (key, inner) = object.into_iter().next()?;
+    let parsed = serde_json::from_value(inner).ok()?;
+    tracing::warn!("Model wrapped its answer in a '{}' object; unwrapped it", key);
+    Some(parsed)
+}
+
+/// Extract the text around a 1-based `line`/`column` position, marking the
+/// position with `⟨here⟩`. Falls back to the start of the input when the
+/// position is unknown (serde reports line 0 for non-syntax errors).
+pub fn error_snippet(raw: &str, line: usize, column: usize) -> String {
+    let target = raw.lines().nth(line.saturating_sub(1)).unwrap_or(raw);
+    let chars: Vec<char> = target.chars().collect();
+
+    // serde_json reports byte columns; translate to a char index
+    let byte_col = column.saturating_sub(1).min(target.len());
+    let pos = target
+        .char_indices()
+        .take_while(|(idx, _)| *idx < byte_col)
+        .count();
+
+    let start = pos.saturating_sub(SNIPPET_RADIUS);
+    let end = (pos + SNIPPET_RADIUS).min(chars.len());
+    let before: String = chars[start..pos].iter().collect();
+    let after: String = chars[pos..end].iter().collect();
+
+    format!(
+        "{}{}⟨here⟩{}{}",
+        if start > 0 { "…" } else { "" },
+        before,
+        after,
+        if end < chars.len() { "…" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample_solution_json, sample_validation_json};
+    use crate::types::{SolutionV1, ValidationV1};
+
+    #[test]
+    fn parse_error_includes_snippet_around_position() {
+        let raw = "{\n  \"schema_version\": \"solution_v1\",\n  \"task_id\": oops\n}";
+        let err = parse_model_json::<SolutionV1>(raw).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("line 3"), "unexpected error: {}", msg);
+        assert!(msg.contains("\"task_id\": ⟨here⟩oops"), "unexpected error: {}", msg);
+    }
+
+    #[test]
+    fn object_wrapped_under_a_single_key_is_unwrapped() {
+        let raw = format!("{{\"solution\": {}}}", sample_solution_json());
+        let solution: SolutionV1 = parse_model_json(&raw).unwrap();
+        assert_eq!(solution.solution_id, "sol-1");
+
+        let raw = format!("\n{{\"validation_v1\": {}}}\n", sample_validation_json());
+        let validation: ValidationV1 = parse_model_json(&raw).unwrap();
+        assert_eq!(validation.solution_id, "sol-1");
+    }
+
+    #[test]
+    fn ambiguous_or_mismatched_wrappers_still_fail() {
+        let two_keys = format!(
+            "{{\"solution\": {}, \"note\": \"hi\"}}",
+            sample_solution_json()
+        );
+        assert!(matches!(
+            parse_model_json::<SolutionV1>(&two_keys),
+            Err(AgentError::Parse { .. })
+        ));
+
+        let wrong_type = format!("{{\"validation\": {}}}", sample_validation_json());
+        assert!(parse_model_json::<SolutionV1>(&wrong_type).is_err());
+    }
+
+    #[test]
+    fn last_object_is_found_past_drafts_prose_and_string_braces() {
+        assert_eq!(
+            last_json_object("Draft: {\"a\": 1}\nFinal: {\"a\": {\"b\": \"}{\"}} done"),
+            Some("{\"a\": {\"b\": \"}{\"}}")
+        );
+        assert_eq!(last_json_object("say \"hi\" then {\"q\": \"\\\"}\"}"), Some("{\"q\": \"\\\"}\"}"));
+        assert_eq!(last_json_object("{\"open\": 1"), None);
+        assert_eq!(last_json_object("no json"), None);
+    }
+
+    #[test]
+    fn the_last_of_several_objects_is_parsed() {
+        let mut draft: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        draft["solution_id"] = serde_json::json!("draft");
+        let raw = format!(
+            "Here is my reasoning and a draft:\n{}\nAnd the final answer:\n{}",
+            draft,
+            sample_solution_json()
+        );
+        let solution: SolutionV1 = parse_model_json(&raw).unwrap();
+        assert_eq!(solution.solution_id, "sol-1");
+
+        let raw = format!("{}{}", sample_validation_json(), sample_validation_json());
+        let validation: ValidationV1 = parse_model_json(&raw).unwrap();
+        assert_eq!(validation.solution_id, "sol-1");
+    }
+
+    #[test]
+    fn think_blocks_before_the_answer_are_split_off() {
+        let raw = format!(
+            "<think>Check each criterion.</think>\n<think> Both pass. </think>{}",
+            sample_validation_json()
+        );
+        let (answer, reasoning) = strip_think_blocks(&raw);
+        assert_eq!(answer, sample_validation_json());
+        assert_eq!(reasoning.as_deref(), Some("Check each criterion.\n\nBoth pass."));
+        let validation: ValidationV1 = parse_model_json(&raw).unwrap();
+        assert_eq!(validation.solution_id, "sol-1");
+
+        // Reasoning missing its opening tag
+        let (answer, reasoning) = strip_think_blocks("Looks fine.</think>{\"ok\": true}");
+        assert_eq!((answer, reasoning.as_deref()), ("{\"ok\": true}", Some("Looks fine.")));
+
+        // Without think tags, or with tags inside the answer, nothing is stripped
+        let raw = "{\"text\": \"<think>x</think>\"}";
+        assert_eq!(strip_think_blocks(raw), (raw, None));
+        assert_eq!(strip_think_blocks(&sample_solution_json()), (sample_solution_json().as_str(), None));
+        let value: serde_json::Value = parse_model_json(raw).unwrap();
+        assert_eq!(value["text"], "<think>x</think>");
+    }
+
+    #[test]
+    fn leading_bom_is_stripped() {
+        let raw = "\u{feff}{\"ok\": true}";
+        assert!(serde_json::from_str::<serde_json::Value>(raw).is_err());
+        let value: serde_json::Value = parse_model_json(raw).unwrap();
+        assert_eq!(value["ok"], true);
+    }
+
+    #[test]
+    fn surrounding_whitespace_and_blank_lines_are_stripped() {
+        for raw in ["\n\n  {\"ok\": true}\n\n", "\r\n\u{feff}\n{\"ok\": true}\t"] {
+            let value: serde_json::Value = parse_model_json(raw).unwrap();
+            assert_eq!(value["ok"], true, "{:?}", raw);
+        }
+        assert_eq!(extract_json(" \u{feff} [1] \n"), "[1]");
+    }
+
+    #[test]
+    fn snippet_truncates_long_lines() {
+        let raw = format!("{}X{}", "a".repeat(100), "b".repeat(100));
+        let snippet = error_snippet(&raw, 1, 101);
+        assert_eq!(
+            snippet,
+            format!("…{}⟨here⟩X{}…", "a".repeat(40), "b".repeat(39))
+        );
+    }
+
+    #[test]
+    fn snippet_handles_multibyte_text() {
+        let raw = "{\"text\": \"héllo\" ,,}";
+        let err = parse_model_json::<serde_json::Value>(raw).unwrap_err();
+        assert!(err.to_string().contains("héllo"));
+    }
+}
@@ -1,46 +1,30 @@
-use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::Utc;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
 use serde_json::json;
-use tokio::fs;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::deepseek::{ChatMessage, DeepSeekClient};
-use crate::types::{SolutionV1, TaskSpec};
+use crate::deepseek::{ChatMessage, DeepSeekError};
+use crate::format::format_solution_code;
+use crate::llm::LlmClient;
+use crate::storage::ArtifactStore;
+use crate::summary::attach_text_summary;
+use crate::types::{
+    CodeArtifact, Deliverable, DeliverableType, Evidence, SolutionV1, TaskSpec, Usage,
+};
 
-use super::{Agent, AgentError};
-
-#[derive(Clone)]
-pub struct ProducerAgent {
-    client: DeepSeekClient,
-    out_path: PathBuf,
-}
-
-impl ProducerAgent {
-    pub fn new(client: DeepSeekClient, out_path: PathBuf) -> Self {
-        Self { client, out_path }
-    }
-}
-
-#[async_trait]
-impl Agent for ProducerAgent {
-    type Input = TaskSpec;
-    type Output = SolutionV1;
-
-    async fn execute(&self, task: &Self::Input) -> Result<Self::Output, AgentError> {
-        info!("ProducerAgent: preparing output directory at {}", self.out_path.display());
-        fs::create_dir_all(
-            self.out_path
-                .parent()
-                .ok_or_else(|| AgentError::Unexpected("invalid output path".into()))?,
-        )
-        .await?;
-
-        // System prompt: strict JSON SolutionV1
-        let system_prompt = r#"
-            You are Agent 1. Produce a solution strictly as JSON matching the schema below. Do not add commentary or markdown. Output ONLY a JSON object.
+use super::parse::parse_model_json;
+use super::{
+    actual_model_used, event_stream, localized_prompt, record_schema_violations, save_raw_response,
+    Agent, AgentError, AgentEvent, EventSink,
+};
 
+/// Schema description of `SolutionV1`, shared by every agent that emits a solution
+pub(super) const SOLUTION_SCHEMA: &str = r#"
             Descriptions in the schema indicate expected data and type; replace them with actual values in your output.
 
             Schema (SolutionV1):
@@ -73,21 +57,273 @@ impl Agent for ProducerAgent {
             }
         "#;
 
-        let user_payload = json!({
+/// System prompt: strict JSON SolutionV1
+const SYSTEM_PROMPT: &str = r#"
+            You are Agent 1. Produce a solution strictly as JSON matching the schema below. Do not add commentary or markdown. Output ONLY a JSON object.
+"#;
+
+/// Guidance sent with the task for `DeliverableType::Text`
+const TEXT_GUIDANCE: &str = "Write plain prose that follows the requested format exactly (bullets, length, tone); do not pad it with introductions or summaries that were not asked for.";
+/// Guidance sent with the task for `DeliverableType::Json`
+const JSON_GUIDANCE: &str = "Put the requested data in deliverable.json as a JSON value, not as a string of JSON; follow the task's json_schema when it has one.";
+/// Guidance sent with the task for `DeliverableType::Code`
+const CODE_GUIDANCE: &str = "Ensure the code compiles and includes no placeholders, TODOs or elided sections; set deliverable.code.language to the file extension of the language.";
+
+/// Prompt fragment with advice specific to the requested kind of deliverable
+fn type_guidance(deliverable_type: &DeliverableType) -> &'static str {
+    match deliverable_type {
+        DeliverableType::Text => TEXT_GUIDANCE,
+        DeliverableType::Json => JSON_GUIDANCE,
+        DeliverableType::Code => CODE_GUIDANCE,
+    }
+}
+
+#[derive(Clone)]
+pub struct ProducerAgent {
+    client: Arc<dyn LlmClient>,
+    store: Arc<dyn ArtifactStore>,
+    key: String,
+    save_raw_on_parse_error: bool,
+    seed_draft: Option<String>,
+    examples: Vec<(TaskSpec, SolutionV1)>,
+    embed_task: bool,
+    stream_to: Option<StreamTarget>,
+    input_note: Option<String>,
+    format_code: bool,
+    with_summary: bool,
+    execute_timeout: Option<Duration>,
+}
+
+/// Where `--stream-deliverable` writes a deliverable as it is generated
+#[derive(Clone)]
+pub struct StreamTarget {
+    pub store: Arc<dyn ArtifactStore>,
+    pub key: String,
+}
+
+/// Streamed deltas are appended to the stream target in chunks of about this many bytes
+const STREAM_CHUNK_BYTES: usize = 8 * 1024;
+/// Most bytes of a streamed deliverable kept in the solution; the whole deliverable is
+/// only in the stream target
+pub const MAX_STREAM_EXCERPT_BYTES: usize = 64 * 1024;
+
+/// Upper bound on few-shot examples sent with each request, to stay within the token budget
+pub const MAX_FEW_SHOT_EXAMPLES: usize = 3;
+
+const INSTRUCTIONS: &str = "Use the deliverable_type from TaskSpec. Populate created_at with current time. Ensure only one of deliverable.text/json/code is present as per deliverable_type.";
+
+/// System prompt of the streaming mode: the bare deliverable, no JSON envelope
+const STREAM_SYSTEM_PROMPT: &str = "You are Agent 1. Output ONLY the deliverable itself, exactly as it should be saved: no JSON, no markdown fences, no commentary.";
+
+const STREAM_INSTRUCTIONS: &str = "Return only the deliverable content for the TaskSpec's deliverable_type.";
+
+/// Note recorded in `Evidence.usage_note` when the solution refines a seed draft
+const SEED_USAGE_NOTE: &str = "Refined from a user-provided seed draft";
+
+/// Turn the contents of a seed file into a draft: the deliverable of a `SolutionV1`
+/// document, otherwise the contents as plain text
+pub fn seed_draft_from(contents: &str) -> String {
+    match serde_json::from_str::<SolutionV1>(contents) {
+        Ok(solution) => deliverable_text(&solution.deliverable).unwrap_or_default(),
+        Err(_) => contents.trim().to_string(),
+    }
+}
+
+fn deliverable_text(deliverable: &Deliverable) -> Option<String> {
+    if let Some(text) = &deliverable.text {
+        return Some(text.clone());
+    }
+    if let Some(json) = &deliverable.json {
+        return serde_json::to_string_pretty(json).ok();
+    }
+    deliverable.code.as_ref().map(|code| code.content.clone())
+}
+
+impl ProducerAgent {
+    /// Create an agent that persists its artifact under `key` in `store`
+    pub fn new(client: impl LlmClient + 'static, store: Arc<dyn ArtifactStore>, key: impl Into<String>) -> Self {
+        Self {
+            client: Arc::new(client),
+            store,
+            key: key.into(),
+            save_raw_on_parse_error: false,
+            seed_draft: None,
+            examples: Vec::new(),
+            embed_task: false,
+            stream_to: None,
+            input_note: None,
+            format_code: false,
+            with_summary: false,
+            execute_timeout: None,
+        }
+    }
+
+    /// Few-shot examples sent as user/assistant turns before the real task;
+    /// only the first `MAX_FEW_SHOT_EXAMPLES` are kept
+    pub fn with_examples(mut self, mut examples: Vec<(TaskSpec, SolutionV1)>) -> Self {
+        if examples.len() > MAX_FEW_SHOT_EXAMPLES {
+            warn!(
+                "Using {} of {} few-shot examples",
+                MAX_FEW_SHOT_EXAMPLES,
+                examples.len()
+            );
+            examples.truncate(MAX_FEW_SHOT_EXAMPLES);
+        }
+        self.examples = examples;
+        self
+    }
+
+    /// Refine `draft` instead of generating the deliverable from scratch
+    pub fn with_seed_draft(mut self, draft: Option<String>) -> Self {
+        self.seed_draft = draft.filter(|d| !d.trim().is_empty());
+        self
+    }
+
+    /// Note about how the task input was prepared (e.g. truncation), recorded in `Evidence.usage_note`
+    pub fn with_input_note(mut self, note: Option<String>) -> Self {
+        self.input_note = note;
+        self
+    }
+
+    /// Run the language's formatter (rustfmt, black, or JSON pretty-printing) over a code
+    /// deliverable before saving; skipped when the formatter is missing or fails
+    pub fn with_format_code(mut self, enabled: bool) -> Self {
+        self.format_code = enabled;
+        self
+    }
+
+    /// Store a summary of a text deliverable (key points, word count) in `Evidence.summary`
+    pub fn with_summary(mut self, enabled: bool) -> Self {
+        self.with_summary = enabled;
+        self
+    }
+
+    /// Embed the originating `TaskSpec` in the saved solution
+    pub fn with_embed_task(mut self, enabled: bool) -> Self {
+        self.embed_task = enabled;
+        self
+    }
+
+    /// Stream `text`/`code` deliverables to `target` as they are generated instead of
+    /// buffering a JSON response; `json` deliverables still use the buffered, validated path.
+    /// The solution holds the first `MAX_STREAM_EXCERPT_BYTES` of the deliverable. A code
+    /// deliverable's language is taken from the key's extension.
+    pub fn with_stream_to(mut self, target: Option<StreamTarget>) -> Self {
+        self.stream_to = target;
+        self
+    }
+
+    /// Bound the whole run, retries included, to `timeout`; exceeding it fails the run
+    /// with `AgentError::Unexpected("agent timeout")`
+    pub fn with_execute_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.execute_timeout = timeout;
+        self
+    }
+
+    /// When enabled, an unparseable model response is saved next to the artifact before the error is returned
+    pub fn with_save_raw_on_parse_error(mut self, enabled: bool) -> Self {
+        self.save_raw_on_parse_error = enabled;
+        self
+    }
+
+    /// Build the messages sent to the model for `task`: system prompt, few-shot
+    /// user/assistant pairs, then the task itself with its deliverable-type guidance.
+    /// Everything before the task is the same for every task, so DeepSeek's automatic
+    /// context cache can serve that prefix across the tasks of a batch.
+    fn build_messages(&self, task: &TaskSpec) -> Vec<ChatMessage> {
+        let system_prompt = localized_prompt(
+            &format!("{}{}", SYSTEM_PROMPT, SOLUTION_SCHEMA),
+            self.client.config(),
+        );
+
+        let mut messages = vec![ChatMessage::system(system_prompt)];
+        messages.extend(self.example_turns(INSTRUCTIONS, |solution| {
+            serde_json::to_string(solution).unwrap_or_default()
+        }));
+
+        let mut user_payload = json!({
             "task_spec": task,
-            "instructions": "Use the deliverable_type from TaskSpec. Populate created_at with current time. Ensure only one of deliverable.text/json/code is present as per deliverable_type."
+            "instructions": INSTRUCTIONS,
+            "deliverable_guidance": type_guidance(&task.deliverable_type)
         });
+        if let Some(draft) = &self.seed_draft {
+            user_payload["seed_draft"] = json!(draft);
+            user_payload["instructions"] = json!(format!(
+                "Start from seed_draft and refine it to satisfy the TaskSpec rather than writing from scratch. {}",
+                INSTRUCTIONS
+            ));
+        }
+        messages.push(ChatMessage::user(user_payload.to_string()));
+        messages
+    }
 
-        let messages = vec![
-            ChatMessage { role: "system".to_string(), content: system_prompt.to_string() },
-            ChatMessage { role: "user".to_string(), content: user_payload.to_string() },
-        ];
+    /// The few-shot examples as user/assistant turns, each answered with `answer(solution)`
+    fn example_turns(
+        &self,
+        instructions: &str,
+        answer: impl Fn(&SolutionV1) -> String,
+    ) -> Vec<ChatMessage> {
+        self.examples
+            .iter()
+            .flat_map(|(example_task, example_solution)| {
+                [
+                    ChatMessage::user(
+                        json!({ "task_spec": example_task, "instructions": instructions }).to_string(),
+                    ),
+                    ChatMessage::assistant(answer(example_solution)),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl ProducerAgent {
+    /// One run: produce a solution for `task`, reporting progress to `events`
+    async fn run(
+        &self,
+        task: &TaskSpec,
+        events: EventSink<SolutionV1>,
+    ) -> Result<SolutionV1, AgentError> {
+        events.emit(AgentEvent::PreparingOutput);
+        if let Some(target) = &self.stream_to
+            && matches!(task.deliverable_type, DeliverableType::Text | DeliverableType::Code)
+        {
+            let solution = self.stream_deliverable(task, target, &events).await?;
+            return self.finish(task, solution).await;
+        }
+        let messages = self.build_messages(task);
+        if let Some(n) = self.client.config().n.filter(|&n| n > 1) {
+            info!("ProducerAgent: requesting {} candidates for task {}", n, task.task_id);
+            events.emit(AgentEvent::SendingRequest);
+            let completions = self.client.send_messages_candidates(messages, n).await?;
+            events.emit(AgentEvent::ReceivedResponse);
+            events.emit(AgentEvent::Parsing);
+            let raws: Vec<String> = completions.into_iter().map(|c| c.content).collect();
+            let solution = self.select_candidate(task, &raws).await?;
+            return self.finish(task, solution).await;
+        }
 
         info!("ProducerAgent: sending task {} to LLM", task.task_id);
+        events.emit(AgentEvent::SendingRequest);
         let raw = self.client.send_messages_raw(messages).await?;
+        events.emit(AgentEvent::ReceivedResponse);
         info!("ProducerAgent: received model response, parsing JSON");
-        let mut solution: SolutionV1 = serde_json::from_str(&raw)?;
+        events.emit(AgentEvent::Parsing);
+        let solution: SolutionV1 = match parse_model_json(&raw) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                if self.save_raw_on_parse_error {
+                    save_raw_response(self.store.as_ref(), &self.key, &raw).await?;
+                }
+                return Err(e);
+            }
+        };
 
+        self.finish(task, solution).await
+    }
+
+    /// Fill in what the model may have left out, then persist `solution`
+    async fn finish(&self, task: &TaskSpec, mut solution: SolutionV1) -> Result<SolutionV1, AgentError> {
         // Ensure schema_version and timestamps if model forgot
         if solution.schema_version.is_empty() {
             solution.schema_version = "solution_v1".to_string();
@@ -95,17 +331,707 @@ impl Agent for ProducerAgent {
         if solution.created_at.trim().is_empty() {
             solution.created_at = Utc::now().to_rfc3339();
         }
+        if self.seed_draft.is_some() {
+            solution.evidence.prepend_note(SEED_USAGE_NOTE);
+        }
+        if let Some(note) = &self.input_note {
+            solution.evidence.prepend_note(note);
+        }
+        solution.model_used = actual_model_used(self.client.config());
+        if self.format_code && format_solution_code(&mut solution).await {
+            info!("ProducerAgent: formatted the code deliverable of solution {}", solution.solution_id);
+        }
+        record_schema_violations(task, &mut solution);
+        if self.with_summary
+            && matches!(solution.deliverable_type, DeliverableType::Text)
+            && attach_text_summary(&mut solution)
+        {
+            info!("ProducerAgent: added a text summary to solution {}", solution.solution_id);
+        }
+        solution.task_spec = self.embed_task.then(|| task.clone());
 
         // Persist
-        let pretty = serde_json::to_string_pretty(&solution)?;
-        fs::write(&self.out_path, pretty).await?;
+        let pretty = self.client.config().artifact_json(&solution)?;
+        self.store.save(&self.key, &pretty).await?;
         info!(
             "ProducerAgent: saved solution {} to {}",
             solution.solution_id,
-            self.out_path.display()
+            self.store.locate(&self.key)
         );
         Ok(solution)
     }
 }
 
+/// Parse each raw candidate into a `SolutionV1`, keeping the index of the ones that parse
+pub fn parse_candidates(raws: &[String]) -> Vec<(usize, SolutionV1)> {
+    raws.iter()
+        .enumerate()
+        .filter_map(|(index, raw)| match parse_model_json::<SolutionV1>(raw) {
+            Ok(solution) => Some((index, solution)),
+            Err(e) => {
+                warn!("ProducerAgent: candidate {} could not be parsed: {}", index + 1, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Quick model-free grade of a candidate: the deliverable-type check counts double,
+/// conformance to the task's `json_schema` once
+fn candidate_score(task: &TaskSpec, solution: &SolutionV1) -> u8 {
+    let type_ok = task.deliverable_type_check(solution).pass_;
+    let schema_ok = task.json_schema_violations(&solution.deliverable).is_empty();
+    2 * u8::from(type_ok) + u8::from(schema_ok)
+}
+
+impl ProducerAgent {
+    /// Best-of-N: pick the highest-graded parseable candidate, the earliest on ties
+    async fn select_candidate(&self, task: &TaskSpec, raws: &[String]) -> Result<SolutionV1, AgentError> {
+        let candidates = parse_candidates(raws);
+        let Some((index, mut solution)) = candidates
+            .into_iter()
+            .max_by_key(|(index, solution)| (candidate_score(task, solution), std::cmp::Reverse(*index)))
+        else {
+            // None parsed: report (and optionally save) the first one's error
+            let raw = raws.first().map(String::as_str).unwrap_or_default();
+            if self.save_raw_on_parse_error {
+                save_raw_response(self.store.as_ref(), &self.key, raw).await?;
+            }
+            return parse_model_json(raw);
+        };
+        info!("ProducerAgent: selected candidate {} of {}", index + 1, raws.len());
+        solution
+            .evidence
+            .prepend_note(&format!("Best of {} candidates (candidate {})", raws.len(), index + 1));
+        Ok(solution)
+    }
+
+    /// Streaming mode: append the deliverable to `target` chunk by chunk and wrap its first
+    /// `MAX_STREAM_EXCERPT_BYTES` in a `SolutionV1`. A stream failing for any reason other
+    /// than a timeout removes what was written.
+    async fn stream_deliverable(
+        &self,
+        task: &TaskSpec,
+        target: &StreamTarget,
+        events: &EventSink<SolutionV1>,
+    ) -> Result<SolutionV1, AgentError> {
+        let mut user_payload = json!({
+            "task_spec": task,
+            "instructions": STREAM_INSTRUCTIONS,
+            "deliverable_guidance": type_guidance(&task.deliverable_type)
+        });
+        if let Some(draft) = &self.seed_draft {
+            user_payload["seed_draft"] = json!(draft);
+        }
+        // Task-independent, like the prefix of `build_messages`
+        let system_prompt = localized_prompt(STREAM_SYSTEM_PROMPT, self.client.config());
+        let mut messages = vec![ChatMessage::system(system_prompt.clone())];
+        messages.extend(self.example_turns(STREAM_INSTRUCTIONS, |solution| {
+            deliverable_text(&solution.deliverable).unwrap_or_default()
+        }));
+        messages.push(ChatMessage::user(user_payload.to_string()));
+
+        let location = target.store.locate(&target.key);
+        info!("ProducerAgent: streaming task {} to {}", task.task_id, location);
+        events.emit(AgentEvent::SendingRequest);
+        let streamed = self.stream_to_target(messages, target).await;
+        let (excerpt, written, timed_out) = match streamed {
+            Ok(streamed) => streamed,
+            Err(e) => {
+                if let Err(remove_err) = target.store.remove(&target.key).await {
+                    warn!("ProducerAgent: could not remove partial {}: {}", location, remove_err);
+                }
+                return Err(e);
+            }
+        };
+        events.emit(AgentEvent::ReceivedResponse);
+        info!("ProducerAgent: streamed {} bytes to {}", written, location);
+
+        events.emit(AgentEvent::Parsing);
+        let mut usage_note = format!("Streamed to {}", location);
+        if let Some(seconds) = timed_out {
+            usage_note = format!("{}; partial: the stream timed out after {} s", usage_note, seconds);
+        }
+        if excerpt.len() < written {
+            usage_note = format!(
+                "{}; the deliverable holds the first {} of {} bytes",
+                usage_note,
+                excerpt.len(),
+                written
+            );
+        }
+        let deliverable = match task.deliverable_type {
+            DeliverableType::Code => Deliverable {
+                text: None,
+                json: None,
+                code: Some(CodeArtifact {
+                    language: std::path::Path::new(&target.key)
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .unwrap_or("text")
+                        .to_string(),
+                    content: excerpt,
+                }),
+            },
+            _ => Deliverable { text: Some(excerpt), json: None, code: None },
+        };
+        Ok(SolutionV1 {
+            schema_version: "solution_v1".to_string(),
+            task_id: task.task_id.clone(),
+            solution_id: uuid::Uuid::new_v4().to_string(),
+            model_used: actual_model_used(self.client.config()),
+            deliverable_type: task.deliverable_type.clone(),
+            deliverable,
+            evidence: Evidence {
+                system_prompt,
+                usage_note: Some(usage_note),
+                schema_violations: Vec::new(),
+                summary: None,
+            },
+            usage: Usage::default(),
+            created_at: Utc::now().to_rfc3339(),
+            task_spec: None,
+        })
+    }
+
+    /// Append the streamed deltas to `target`, replacing what it held. Returns the first
+    /// `MAX_STREAM_EXCERPT_BYTES` of the content, the bytes written, and the timeout in
+    /// seconds when the stream timed out after some content (which is kept).
+    async fn stream_to_target(
+        &self,
+        messages: Vec<ChatMessage>,
+        target: &StreamTarget,
+    ) -> Result<(String, usize, Option<u64>), AgentError> {
+        let mut deltas = self.client.stream_messages(messages).await?;
+        target.store.save(&target.key, "").await?;
+        let (mut excerpt, mut chunk, mut written) = (String::new(), String::new(), 0usize);
+        let mut timed_out = None;
+        while let Some(delta) = deltas.next().await {
+            let delta = match delta {
+                Ok(delta) => delta,
+                // Everything received so far is already in the target; keep it for the audit
+                Err(DeepSeekError::Timeout { seconds, .. }) if written + chunk.len() > 0 => {
+                    warn!(
+                        "ProducerAgent: stream timed out after {} s; keeping the {} bytes received",
+                        seconds,
+                        written + chunk.len()
+                    );
+                    timed_out = Some(seconds);
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if excerpt.len() < MAX_STREAM_EXCERPT_BYTES {
+                let room = MAX_STREAM_EXCERPT_BYTES - excerpt.len();
+                let mut end = room.min(delta.len());
+                while !delta.is_char_boundary(end) {
+                    end -= 1;
+                }
+                excerpt.push_str(&delta[..end]);
+            }
+            chunk.push_str(&delta);
+            if chunk.len() >= STREAM_CHUNK_BYTES {
+                target.store.append(&target.key, &chunk).await?;
+                written += chunk.len();
+                chunk.clear();
+            }
+        }
+        target.store.append(&target.key, &chunk).await?;
+        written += chunk.len();
+        Ok((excerpt, written, timed_out))
+    }
+}
+
+#[async_trait]
+impl Agent for ProducerAgent {
+    type Input = TaskSpec;
+    type Output = SolutionV1;
+
+    fn execute_with_events<'a>(
+        &'a self,
+        task: &'a Self::Input,
+    ) -> BoxStream<'a, AgentEvent<Self::Output>> {
+        event_stream(self.store.locate(&self.key), self.execute_timeout, move |events| self.run(task, events).boxed())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deepseek::DeepSeekClient;
+    use crate::deepseek::Role;
+    use crate::storage::MemoryStore;
+    use futures::StreamExt;
+    use crate::test_support::{
+        mock_client, mock_config, mount_completion, sample_solution_json, sample_task,
+    };
+    use wiremock::MockServer;
+
+    #[tokio::test]
+    async fn execute_saves_solution_to_store() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_solution_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store.clone(), "solution.json");
+        let solution = agent.execute(&sample_task()).await.unwrap();
+
+        let saved = store.load("solution.json").await.expect("solution saved");
+        let parsed: SolutionV1 = serde_json::from_str(&saved).unwrap();
+        assert_eq!(parsed.solution_id, solution.solution_id);
+        assert_eq!(store.keys(), vec!["solution.json".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn model_used_reflects_the_client_config_not_the_model_output() {
+        let server = MockServer::start().await;
+        // The model claims deepseek-chat at 0.7
+        mount_completion(&server, &sample_solution_json()).await;
+
+        let config = crate::config::Config {
+            model: "deepseek-reasoner".to_string(),
+            temperature: 0.2,
+            ..mock_config(&server)
+        };
+        let client = DeepSeekClient::new(config).unwrap();
+        assert_eq!((client.model_name(), client.temperature()), ("deepseek-reasoner", 0.2));
+        let agent = ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json");
+        let solution = agent.execute(&sample_task()).await.unwrap();
+
+        assert_eq!(solution.model_used.name, "deepseek-reasoner");
+        assert_eq!(solution.model_used.temperature, 0.2);
+    }
+
+    #[tokio::test]
+    async fn seed_draft_is_sent_and_noted() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_solution_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store, "solution.json")
+            .with_seed_draft(Some("- draft bullet one".to_string()));
+        let messages = agent.build_messages(&sample_task());
+        assert!(messages[1].content.contains("\"seed_draft\":\"- draft bullet one\""));
+        assert!(messages[1].content.contains("refine it"));
+
+        let solution = agent.execute(&sample_task()).await.unwrap();
+        assert_eq!(solution.evidence.usage_note.as_deref(), Some(SEED_USAGE_NOTE));
+    }
+
+    #[tokio::test]
+    async fn with_summary_stores_a_parseable_summary_in_the_evidence() {
+        use crate::summary::TextSummary;
+
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_solution_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store.clone(), "solution.json")
+            .with_seed_draft(Some("- draft".to_string()))
+            .with_summary(true);
+        agent.execute(&sample_task()).await.unwrap();
+
+        let saved: SolutionV1 =
+            serde_json::from_str(&store.load("solution.json").await.unwrap()).unwrap();
+        let summary: TextSummary = saved.evidence.summary.expect("summary in the evidence");
+        assert_eq!(summary.key_points, vec!["a", "b", "c"]);
+        assert_eq!(summary.word_count, 3);
+        // Notes stay free text beside the summary
+        assert_eq!(saved.evidence.usage_note.as_deref(), Some(SEED_USAGE_NOTE));
+    }
+
+    #[test]
+    fn examples_precede_the_task_as_user_assistant_pairs() {
+        let client = DeepSeekClient::new(crate::config::Config {
+            api_key: "k".to_string(),
+            ..crate::config::Config::default()
+        })
+        .unwrap();
+        let examples: Vec<(TaskSpec, SolutionV1)> = (1..=MAX_FEW_SHOT_EXAMPLES + 1)
+            .map(|i| {
+                let mut task = sample_task();
+                task.task_id = format!("example-task-{}", i);
+                let mut solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+                solution.solution_id = format!("example-sol-{}", i);
+                (task, solution)
+            })
+            .collect();
+        let agent = ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json")
+            .with_examples(examples);
+
+        let messages = agent.build_messages(&sample_task());
+        let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            vec![
+                Role::System,
+                Role::User,
+                Role::Assistant,
+                Role::User,
+                Role::Assistant,
+                Role::User,
+                Role::Assistant,
+                Role::User,
+            ]
+        );
+        for i in 1..=MAX_FEW_SHOT_EXAMPLES {
+            assert!(messages[2 * i - 1].content.contains(&format!("example-task-{}", i)));
+            assert!(messages[2 * i].content.contains(&format!("\"solution_id\":\"example-sol-{}\"", i)));
+        }
+        assert!(messages.last().unwrap().content.contains("\"task_id\":\"task-1\""));
+    }
+
+    #[test]
+    fn output_language_is_requested_in_the_system_prompt() {
+        let agent = |output_language: Option<&str>| {
+            let client = DeepSeekClient::new(crate::config::Config {
+                api_key: "k".to_string(),
+                output_language: output_language.map(str::to_string),
+                ..crate::config::Config::default()
+            })
+            .unwrap();
+            ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json")
+        };
+
+        let messages = agent(Some("German")).build_messages(&sample_task());
+        assert!(messages[0].content.ends_with("write all other string values in German."));
+        assert!(messages[0].content.contains("Respond in German."));
+
+        let messages = agent(None).build_messages(&sample_task());
+        assert!(!messages[0].content.contains("Respond in"));
+    }
+
+    #[test]
+    fn task_message_carries_the_guidance_of_the_deliverable_type() {
+        let client = DeepSeekClient::new(crate::config::Config {
+            api_key: "k".to_string(),
+            ..crate::config::Config::default()
+        })
+        .unwrap();
+        let agent = ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json");
+        let guidance = [
+            (DeliverableType::Text, TEXT_GUIDANCE),
+            (DeliverableType::Json, JSON_GUIDANCE),
+            (DeliverableType::Code, CODE_GUIDANCE),
+        ];
+        for (deliverable_type, expected) in &guidance {
+            let mut task = sample_task();
+            task.deliverable_type = deliverable_type.clone();
+            let messages = agent.build_messages(&task);
+            let task_message = &messages.last().unwrap().content;
+            for (_, fragment) in &guidance {
+                let included = task_message.contains(fragment);
+                assert_eq!(included, fragment == expected, "{:?}", deliverable_type);
+            }
+        }
+        assert!(CODE_GUIDANCE.contains("compiles and includes no placeholders"));
+    }
+
+    #[test]
+    fn every_task_shares_the_prompt_prefix_before_its_own_message() {
+        let client = DeepSeekClient::new(crate::config::Config {
+            api_key: "k".to_string(),
+            output_language: Some("German".to_string()),
+            ..crate::config::Config::default()
+        })
+        .unwrap();
+        let example: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        let agent = ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json")
+            .with_examples(vec![(sample_task(), example)]);
+
+        let types = [DeliverableType::Text, DeliverableType::Json, DeliverableType::Code];
+        let prefixes: Vec<Vec<String>> = types
+            .into_iter()
+            .enumerate()
+            .map(|(i, deliverable_type)| {
+                let mut task = sample_task();
+                task.task_id = format!("task-{}", i);
+                task.deliverable_type = deliverable_type;
+                let mut messages = agent.build_messages(&task);
+                let task_message = messages.pop().unwrap().content;
+                assert!(task_message.contains(&format!("\"task_id\":\"task-{}\"", i)));
+                messages.into_iter().map(|m| m.content).collect()
+            })
+            .collect();
+        assert_eq!(prefixes[0].len(), 3);
+        assert!(prefixes.iter().all(|prefix| prefix == &prefixes[0]));
+    }
+
+    #[test]
+    fn seed_draft_from_solution_or_plain_text() {
+        assert_eq!(seed_draft_from(&sample_solution_json()), "- a\n- b\n- c");
+        assert_eq!(seed_draft_from("  my rough notes\n"), "my rough notes");
+    }
+
+    #[tokio::test]
+    async fn execute_records_json_schema_violations() {
+        let server = MockServer::start().await;
+        let mut solution: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        solution["deliverable_type"] = json!("json");
+        solution["deliverable"] = json!({ "json": { "count": "three" } });
+        mount_completion(&server, &solution.to_string()).await;
 
+        let mut task = sample_task();
+        task.deliverable_type = crate::types::DeliverableType::Json;
+        task.json_schema = Some(json!({
+            "type": "object",
+            "properties": { "count": { "type": "integer" } }
+        }));
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store.clone(), "solution.json");
+        let solution = agent.execute(&task).await.unwrap();
+        assert_eq!(solution.evidence.schema_violations.len(), 1);
+        assert!(solution.evidence.schema_violations[0].starts_with("/count:"));
+
+        let saved: SolutionV1 =
+            serde_json::from_str(&store.load("solution.json").await.unwrap()).unwrap();
+        assert_eq!(saved.evidence.schema_violations, solution.evidence.schema_violations);
+    }
+
+    /// Event names in emission order, so failures print a readable sequence
+    fn event_names<T>(events: &[AgentEvent<T>]) -> Vec<&'static str> {
+        events
+            .iter()
+            .map(|e| match e {
+                AgentEvent::PreparingOutput => "preparing_output",
+                AgentEvent::SendingRequest => "sending_request",
+                AgentEvent::ReceivedResponse => "received_response",
+                AgentEvent::Parsing => "parsing",
+                AgentEvent::Saved { .. } => "saved",
+                AgentEvent::Failed(_) => "failed",
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn execute_with_events_emits_progress_in_order() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_solution_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store, "solution.json");
+        let task = sample_task();
+        let events: Vec<_> = agent.execute_with_events(&task).collect().await;
+
+        assert_eq!(
+            event_names(&events),
+            vec!["preparing_output", "sending_request", "received_response", "parsing", "saved"]
+        );
+        match events.last() {
+            Some(AgentEvent::Saved { location, output }) => {
+                assert_eq!(location, "solution.json");
+                assert_eq!(output.solution_id, "sol-1");
+            }
+            other => panic!("expected Saved, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_with_events_ends_with_failed_on_parse_error() {
+        let server = MockServer::start().await;
+        mount_completion(&server, "not json").await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store, "solution.json");
+        let task = sample_task();
+        let events: Vec<_> = agent.execute_with_events(&task).collect().await;
+
+        assert_eq!(
+            event_names(&events),
+            vec!["preparing_output", "sending_request", "received_response", "parsing", "failed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_failure_saves_raw_response_when_enabled() {
+        let server = MockServer::start().await;
+        mount_completion(&server, "Here is your solution: {not json").await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store.clone(), "solution.json")
+            .with_save_raw_on_parse_error(true);
+        let err = agent.execute(&sample_task()).await.unwrap_err();
+
+        assert!(matches!(err, AgentError::Parse { .. }), "unexpected error: {}", err);
+        assert_eq!(
+            store.load("solution.raw.txt").await.as_deref(),
+            Some("Here is your solution: {not json")
+        );
+        assert!(store.load("solution.json").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn parse_failure_saves_nothing_by_default() {
+        let server = MockServer::start().await;
+        mount_completion(&server, "not json").await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = ProducerAgent::new(mock_client(&server), store.clone(), "solution.json");
+        assert!(agent.execute(&sample_task()).await.is_err());
+        assert!(store.keys().is_empty());
+    }
+
+    #[tokio::test]
+    async fn text_deliverable_streams_to_file() {
+        use wiremock::matchers::body_partial_json;
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let line = "streamed line of a large deliverable\n";
+        let deltas = vec![line; 5_000];
+        Mock::given(body_partial_json(json!({ "stream": true })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(crate::test_support::sse_body(&deltas)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let store = Arc::new(MemoryStore::new());
+        let target = StreamTarget { store: store.clone(), key: "deliverable.txt".to_string() };
+        let agent = ProducerAgent::new(mock_client(&server), store.clone(), "solution.json")
+            .with_stream_to(Some(target));
+        let solution = agent.execute(&sample_task()).await.unwrap();
+
+        let written = store.load("deliverable.txt").await.unwrap();
+        assert_eq!(written, line.repeat(5_000));
+        // The solution keeps a bounded excerpt and points at the full deliverable
+        let text = solution.deliverable.text.as_deref().unwrap();
+        assert!(text.len() <= MAX_STREAM_EXCERPT_BYTES && written.starts_with(text));
+        let note = solution.evidence.usage_note.as_deref().unwrap();
+        assert!(note.contains(&format!("of {} bytes", written.len())), "{}", note);
+        assert_eq!(solution.task_id, "task-1");
+        assert!(store.load("solution.json").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_stream_removes_the_partial_deliverable_and_sends_examples() {
+        use wiremock::matchers::body_partial_json;
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let mut body = crate::test_support::sse_body(&["first part\n"]);
+        body = body.replace("data: [DONE]", "data: {not json");
+        Mock::given(body_partial_json(json!({ "stream": true })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+
+        let root = std::env::temp_dir().join(format!("stream_{}", uuid::Uuid::new_v4()));
+        let store = Arc::new(crate::storage::FsStore::new(&root));
+        let target = StreamTarget { store: store.clone(), key: "deliverable.txt".to_string() };
+        let example: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        let agent = ProducerAgent::new(mock_client(&server), Arc::new(MemoryStore::new()), "solution.json")
+            .with_examples(vec![(sample_task(), example)])
+            .with_stream_to(Some(target));
+        assert!(agent.execute(&sample_task()).await.is_err());
+        assert!(!store.path_for("deliverable.txt").exists());
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        // Examples answer in the streaming format: the bare deliverable
+        assert_eq!(messages[2]["content"], "- a\n- b\n- c");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn json_deliverable_ignores_stream_to_and_parses_buffered() {
+        let server = MockServer::start().await;
+        let mut reported: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        reported["deliverable_type"] = json!("json");
+        reported["deliverable"] = json!({ "json": { "ok": true } });
+        mount_completion(&server, &reported.to_string()).await;
+
+        let stream_store = Arc::new(MemoryStore::new());
+        let target = StreamTarget { store: stream_store.clone(), key: "deliverable.json".to_string() };
+        let agent = ProducerAgent::new(mock_client(&server), Arc::new(MemoryStore::new()), "solution.json")
+            .with_stream_to(Some(target));
+        let mut task = sample_task();
+        task.deliverable_type = DeliverableType::Json;
+        let solution = agent.execute(&task).await.unwrap();
+
+        assert_eq!(solution.deliverable.json, Some(json!({ "ok": true })));
+        assert!(stream_store.keys().is_empty());
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("stream").is_none());
+    }
+
+    #[tokio::test]
+    async fn candidates_mode_parses_every_choice_and_keeps_the_best() {
+        use wiremock::matchers::body_partial_json;
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let good: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        let mut wrong_type = good.clone();
+        wrong_type["solution_id"] = json!("sol-code");
+        wrong_type["deliverable_type"] = json!("code");
+        let mut second = good.clone();
+        second["solution_id"] = json!("sol-good");
+        let body = json!({
+            "choices": [
+                { "message": { "content": wrong_type.to_string() } },
+                { "message": { "content": "not json" } },
+                { "message": { "content": second.to_string() } }
+            ]
+        });
+        Mock::given(body_partial_json(json!({ "n": 3 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let raws: Vec<String> = vec![wrong_type.to_string(), "not json".to_string(), second.to_string()];
+        let parsed = parse_candidates(&raws);
+        assert_eq!(parsed.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 2]);
+
+        let config = crate::config::Config {
+            n: Some(3),
+            ..crate::test_support::mock_config(&server)
+        };
+        let client = DeepSeekClient::new(config).unwrap();
+        let agent = ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json");
+        let solution = agent.execute(&sample_task()).await.unwrap();
+
+        assert_eq!(solution.solution_id, "sol-good");
+        assert_eq!(
+            solution.evidence.usage_note.as_deref(),
+            Some("Best of 3 candidates (candidate 3)")
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_timeout_bounds_retries() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_delay(std::time::Duration::from_millis(100)))
+            .mount(&server)
+            .await;
+
+        let budget = Arc::new(crate::rate_limit::RetryBudget::new(10));
+        let client = mock_client(&server).with_retry_budget(budget);
+        let agent = ProducerAgent::new(client, Arc::new(MemoryStore::new()), "solution.json")
+            .with_execute_timeout(Some(std::time::Duration::from_millis(300)));
+        let started = std::time::Instant::now();
+        let err = agent.execute(&sample_task()).await.unwrap_err();
+
+        assert!(matches!(&err, AgentError::Unexpected(msg) if msg == crate::agents::AGENT_TIMEOUT), "{}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        // The first attempt failed and a retry was pending when the timeout hit
+        assert!(!server.received_requests().await.unwrap().is_empty());
+    }
+}
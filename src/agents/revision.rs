@@ -0,0 +1,240 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::stream::BoxStream;
+use futures::FutureExt;
+use serde_json::json;
+use tracing::info;
+
+use crate::deepseek::ChatMessage;
+use crate::llm::LlmClient;
+use crate::storage::ArtifactStore;
+use crate::summary::attach_text_summary;
+use crate::types::{DeliverableType, SolutionV1, TaskSpec, ValidationV1};
+
+use super::parse::parse_model_json;
+use super::producer::SOLUTION_SCHEMA;
+use super::{
+    actual_model_used, event_stream, localized_prompt, record_schema_violations, save_raw_response, Agent, AgentError,
+    AgentEvent, EventSink,
+};
+
+/// System prompt: revise a solution using the auditor's feedback, strict JSON SolutionV1
+const SYSTEM_PROMPT: &str = r#"
+            You are Agent 1, revising a solution of yours that was graded by an auditor. Fix every failing check while keeping what already passes. Produce the revised solution strictly as JSON matching the schema below. Do not add commentary or markdown. Output ONLY a JSON object.
+"#;
+
+/// `name` of the revision conversation's turns written by each agent
+const PRODUCER_NAME: &str = "producer";
+const AUDITOR_NAME: &str = "auditor";
+
+/// Reworks a `SolutionV1` based on the failing checks of its `ValidationV1`
+#[derive(Clone)]
+pub struct RevisionAgent {
+    client: Arc<dyn LlmClient>,
+    store: Arc<dyn ArtifactStore>,
+    key: String,
+    save_raw_on_parse_error: bool,
+    embed_task: bool,
+    with_summary: bool,
+    execute_timeout: Option<Duration>,
+}
+
+impl RevisionAgent {
+    /// Create an agent that persists the revised solution under `key` in `store`
+    pub fn new(client: impl LlmClient + 'static, store: Arc<dyn ArtifactStore>, key: impl Into<String>) -> Self {
+        Self {
+            client: Arc::new(client),
+            store,
+            key: key.into(),
+            save_raw_on_parse_error: false,
+            embed_task: false,
+            with_summary: false,
+            execute_timeout: None,
+        }
+    }
+
+    /// Bound the whole run, retries included, to `timeout`; exceeding it fails the run
+    /// with `AgentError::Unexpected("agent timeout")`
+    pub fn with_execute_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.execute_timeout = timeout;
+        self
+    }
+
+    /// When enabled, an unparseable model response is saved next to the artifact before the error is returned
+    pub fn with_save_raw_on_parse_error(mut self, enabled: bool) -> Self {
+        self.save_raw_on_parse_error = enabled;
+        self
+    }
+
+    /// Embed the originating `TaskSpec` in the saved solution
+    pub fn with_embed_task(mut self, enabled: bool) -> Self {
+        self.embed_task = enabled;
+        self
+    }
+
+    /// Summarize a revised text deliverable in `Evidence.summary`, as the producer does
+    pub fn with_summary(mut self, enabled: bool) -> Self {
+        self.with_summary = enabled;
+        self
+    }
+
+    /// Build the messages sent to the model for `input`: the system prompt, the task, the
+    /// previous solution attributed to the producer and the audit feedback to the auditor
+    fn build_messages(&self, input: &RevisionInput) -> Vec<ChatMessage> {
+        let system_prompt = localized_prompt(
+            &format!("{}{}", SYSTEM_PROMPT, SOLUTION_SCHEMA),
+            self.client.config(),
+        );
+
+        let mut feedback = json!({
+            "validation": input.validation,
+            "instructions": "Address every check with pass=false, using suggested_fix and suggested_rewrite when present. Keep task_id, use a new solution_id and populate created_at with current time."
+        });
+        if let Some(prior) = &input.prior_attempts {
+            feedback["prior_attempts"] = json!(prior);
+        }
+
+        vec![
+            ChatMessage::system(system_prompt),
+            ChatMessage::user(json!({ "task_spec": input.task }).to_string()),
+            ChatMessage::assistant(serde_json::to_string(&input.solution).unwrap_or_default())
+                .with_name(PRODUCER_NAME),
+            ChatMessage::user(feedback.to_string()).with_name(AUDITOR_NAME),
+        ]
+    }
+}
+
+pub struct RevisionInput {
+    pub task: TaskSpec,
+    pub solution: SolutionV1,
+    pub validation: ValidationV1,
+    /// Compact summary of the attempts before `solution` (see `PipelineMemory::summary`)
+    pub prior_attempts: Option<String>,
+}
+
+impl RevisionAgent {
+    /// One run: revise `input.solution` using the audit feedback, reporting progress to `events`
+    async fn run(
+        &self,
+        input: &RevisionInput,
+        events: EventSink<SolutionV1>,
+    ) -> Result<SolutionV1, AgentError> {
+        events.emit(AgentEvent::PreparingOutput);
+        let messages = self.build_messages(input);
+
+        info!(
+            "RevisionAgent: revising solution {} for task {}",
+            input.solution.solution_id,
+            input.task.task_id
+        );
+        events.emit(AgentEvent::SendingRequest);
+        let raw = self.client.send_messages_raw(messages).await?;
+        events.emit(AgentEvent::ReceivedResponse);
+        info!("RevisionAgent: received model response, parsing JSON");
+        events.emit(AgentEvent::Parsing);
+        let mut solution: SolutionV1 = match parse_model_json(&raw) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                if self.save_raw_on_parse_error {
+                    save_raw_response(self.store.as_ref(), &self.key, &raw).await?;
+                }
+                return Err(e);
+            }
+        };
+
+        if solution.schema_version.is_empty() {
+            solution.schema_version = "solution_v1".to_string();
+        }
+        if solution.created_at.trim().is_empty() {
+            solution.created_at = Utc::now().to_rfc3339();
+        }
+        solution.model_used = actual_model_used(self.client.config());
+        record_schema_violations(&input.task, &mut solution);
+        if self.with_summary && matches!(solution.deliverable_type, DeliverableType::Text) {
+            attach_text_summary(&mut solution);
+        }
+        solution.task_spec = self.embed_task.then(|| input.task.clone());
+
+        let pretty = self.client.config().artifact_json(&solution)?;
+        self.store.save(&self.key, &pretty).await?;
+        info!(
+            "RevisionAgent: saved revised solution {} to {}",
+            solution.solution_id,
+            self.store.locate(&self.key)
+        );
+        Ok(solution)
+    }
+}
+
+#[async_trait]
+impl Agent for RevisionAgent {
+    type Input = RevisionInput;
+    type Output = SolutionV1;
+
+    fn execute_with_events<'a>(
+        &'a self,
+        input: &'a Self::Input,
+    ) -> BoxStream<'a, AgentEvent<Self::Output>> {
+        event_stream(self.store.locate(&self.key), self.execute_timeout, move |events| self.run(input, events).boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStore;
+    use crate::test_support::{
+        mock_client, mount_completion, sample_solution_json, sample_task, sample_validation_json,
+    };
+    use wiremock::MockServer;
+
+    #[tokio::test]
+    async fn execute_sends_feedback_and_saves_revision() {
+        let server = MockServer::start().await;
+        mount_completion(&server, &sample_solution_json()).await;
+
+        let store = Arc::new(MemoryStore::new());
+        let agent = RevisionAgent::new(mock_client(&server), store.clone(), "solution.json");
+        let input = RevisionInput {
+            task: sample_task(),
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+            validation: serde_json::from_str(&sample_validation_json()).unwrap(),
+            prior_attempts: None,
+        };
+
+        let messages = agent.build_messages(&input);
+        assert!(messages[0].content.contains("revising a solution"));
+        assert!(messages[1].content.contains("\"task_spec\""));
+        let solution_id = format!("\"solution_id\":\"{}\"", input.solution.solution_id);
+        assert!(messages[2].content.contains(&solution_id));
+        assert!(messages[3].content.contains("three bullets present"));
+        assert!(!messages[3].content.contains("prior_attempts"));
+
+        let with_memory = RevisionInput {
+            prior_attempts: Some("Attempt 1 (sol-0): fail, score 0.20; failed: no fluff".to_string()),
+            ..input
+        };
+        let messages = agent.build_messages(&with_memory);
+        assert!(messages[3].content.contains("\"prior_attempts\":\"Attempt 1 (sol-0)"));
+        let input = with_memory;
+
+        agent.execute(&input).await.unwrap();
+        assert!(store.load("solution.json").await.is_some());
+        // The previous solution and the feedback are attributed to their agents on the wire
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let names: Vec<serde_json::Value> = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["name"].clone())
+            .collect();
+        assert_eq!(names, [json!(null), json!(null), json!("producer"), json!("auditor")]);
+
+        let revised = agent.with_summary(true).execute(&input).await.unwrap();
+        assert_eq!(revised.evidence.summary.unwrap().key_points, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Alignment of auditor checks with the task's acceptance criteria: the model often rewords
+//! a criterion, so each check is matched to the most similar one and takes its exact wording.
+
+use crate::types::CheckResult;
+
+/// Similarity a check needs to be matched when `--align-criteria` is given without a value
+pub const DEFAULT_MATCH_THRESHOLD: f32 = 0.5;
+
+/// Lowercase alphanumeric words separated by single spaces
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Dice coefficient of the character bigrams of the normalized texts, in [0.0, 1.0]
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+    let (left, mut right) = (bigrams(&a), bigrams(&b));
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    let total = left.len() + right.len();
+    let mut shared = 0;
+    for pair in left {
+        if let Some(pos) = right.iter().position(|p| *p == pair) {
+            right.swap_remove(pos);
+            shared += 1;
+        }
+    }
+    2.0 * shared as f32 / total as f32
+}
+
+/// Rename each check's `criterion` to the most similar of `criteria` when the similarity
+/// reaches `threshold`; checks matching none are flagged `unmatched`. Returns the number
+/// of checks whose wording changed.
+pub fn align_checks(checks: &mut [CheckResult], criteria: &[String], threshold: f32) -> usize {
+    let mut renamed = 0;
+    for check in checks.iter_mut() {
+        let best = criteria
+            .iter()
+            .map(|criterion| (criterion, similarity(&check.criterion, criterion)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((criterion, score)) if score >= threshold => {
+                check.unmatched = false;
+                if check.criterion != *criterion {
+                    check.criterion = criterion.clone();
+                    renamed += 1;
+                }
+            }
+            _ => check.unmatched = true,
+        }
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+
+    fn check(criterion: &str) -> CheckResult {
+        CheckResult {
+            criterion: criterion.to_string(),
+            pass_: true,
+            reason: String::new(),
+            severity: Severity::Minor,
+            suggested_fix: None,
+            partial_score: None,
+            unmatched: false,
+            evidence_lines: None,
+        }
+    }
+
+    fn criteria() -> Vec<String> {
+        vec![
+            "exactly 3 bullets".to_string(),
+            "<= 80 words total".to_string(),
+            "no marketing fluff".to_string(),
+        ]
+    }
+
+    #[test]
+    fn similarity_ignores_case_and_punctuation() {
+        assert_eq!(similarity("No marketing fluff.", "no marketing fluff"), 1.0);
+        assert!(similarity("exactly three bullets", "exactly 3 bullets") > 0.6);
+        assert!(similarity("exactly 3 bullets", "no marketing fluff") < 0.3);
+        assert_eq!(similarity("", "anything"), 0.0);
+    }
+
+    #[test]
+    fn reworded_checks_take_the_task_wording() {
+        let mut checks = vec![
+            check("Contains exactly 3 bullet points"),
+            check("Total words <= 80"),
+            check("No marketing fluff."),
+        ];
+        let renamed = align_checks(&mut checks, &criteria(), DEFAULT_MATCH_THRESHOLD);
+
+        let names: Vec<&str> = checks.iter().map(|c| c.criterion.as_str()).collect();
+        assert_eq!(names, vec!["exactly 3 bullets", "<= 80 words total", "no marketing fluff"]);
+        assert_eq!(renamed, 3);
+        assert!(checks.iter().all(|c| !c.unmatched));
+    }
+
+    #[test]
+    fn unrelated_checks_are_flagged_and_keep_their_wording() {
+        let mut checks = vec![check("exactly 3 bullets"), check("uses British spelling")];
+        let renamed = align_checks(&mut checks, &criteria(), DEFAULT_MATCH_THRESHOLD);
+
+        assert_eq!(renamed, 0);
+        assert!(!checks[0].unmatched);
+        assert!(checks[1].unmatched);
+        assert_eq!(checks[1].criterion, "uses British spelling");
+    }
+}
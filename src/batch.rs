@@ -0,0 +1,330 @@
+//! `--batch`: run the pipeline over every task file in a directory, recording finished
+//! tasks in a checkpoint so an interrupted batch can be resumed with `--resume-batch`.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::report::PipelineResult;
+use crate::storage::sanitize_filename;
+use crate::types::{TaskSpec, Verdict};
+
+/// Checkpoint file in the batch output directory: one completed task_id per line
+pub const CHECKPOINT_FILE: &str = "batch_checkpoint.txt";
+
+/// Load every `*.json` file in `dir` as a `TaskSpec`, ordered by file name. Fails when two
+/// tasks share a task_id (or ids that map to the same output directory).
+pub async fn load_batch_tasks(dir: &Path) -> Result<Vec<TaskSpec>> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .with_context(|| format!("Failed to read batch directory {}", dir.display()))?;
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut tasks = Vec::with_capacity(paths.len());
+    let mut files_by_dir: HashMap<String, PathBuf> = HashMap::new();
+    for path in paths {
+        let bytes = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let task: TaskSpec = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Invalid TaskSpec in {}", path.display()))?;
+        // Duplicates would share an output directory and a checkpoint entry
+        if let Some(first) = files_by_dir.insert(sanitize_filename(&task.task_id), path.clone()) {
+            anyhow::bail!(
+                "Duplicate task_id '{}' in {} (already used by {})",
+                task.task_id,
+                path.display(),
+                first.display()
+            );
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Task ids completed by a batch, persisted as they finish. Appends are serialized, so
+/// concurrently finishing tasks never interleave their lines.
+#[derive(Debug)]
+pub struct BatchCheckpoint {
+    /// Checkpoint file; `None` keeps the completed ids in memory only
+    path: Option<PathBuf>,
+    completed: Mutex<HashSet<String>>,
+}
+
+impl BatchCheckpoint {
+    /// Checkpoint in `out_dir`. With `resume`, the task ids already recorded there are kept;
+    /// otherwise the batch starts over with an empty checkpoint.
+    pub async fn open(out_dir: &Path, resume: bool) -> Result<Self> {
+        let path = out_dir.join(CHECKPOINT_FILE);
+        let completed = if resume {
+            match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+                }
+            }
+        } else {
+            tokio::fs::write(&path, "")
+                .await
+                .with_context(|| format!("Failed to reset {}", path.display()))?;
+            HashSet::new()
+        };
+        Ok(Self { path: Some(path), completed: Mutex::new(completed) })
+    }
+
+    /// Checkpoint that writes no file, for runs that are never resumed (`--temperature-sweep`)
+    pub fn in_memory() -> Self {
+        Self { path: None, completed: Mutex::new(HashSet::new()) }
+    }
+
+    pub async fn is_completed(&self, task_id: &str) -> bool {
+        self.completed.lock().await.contains(task_id)
+    }
+
+    /// Record `task_id` as completed, on disk before returning
+    pub async fn mark_completed(&self, task_id: &str) -> Result<()> {
+        let mut completed = self.completed.lock().await;
+        if let Some(path) = &self.path {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            file.write_all(format!("{}\n", task_id).as_bytes()).await?;
+            file.sync_data().await?;
+        }
+        completed.insert(task_id.to_string());
+        Ok(())
+    }
+}
+
+/// Outcome of one task of a batch
+#[derive(Debug)]
+pub enum BatchOutcome {
+    /// Completed by an earlier run (`--resume-batch`)
+    Skipped,
+    Completed(Box<PipelineResult>),
+    Failed(anyhow::Error),
+    /// Not run, or cancelled while running, after an earlier task failed (`--fail-fast`)
+    Aborted,
+}
+
+impl BatchOutcome {
+    /// An error or a `Fail` verdict, either of which stops a `--fail-fast` batch
+    pub fn is_failure(&self) -> bool {
+        match self {
+            BatchOutcome::Failed(_) => true,
+            BatchOutcome::Completed(result) => matches!(result.verdict(), Verdict::Fail),
+            BatchOutcome::Skipped | BatchOutcome::Aborted => false,
+        }
+    }
+}
+
+/// Run `run` on each task not yet in `checkpoint`, up to `concurrency` at a time, and
+/// checkpoint every task that completes. Failed tasks are not checkpointed, so a resumed
+/// batch retries them. With `fail_fast`, the first failure (see `BatchOutcome::is_failure`)
+/// cancels the tasks still running and the rest are not started; they are `Aborted`.
+/// Outcomes are returned in task order.
+pub async fn run_batch<F, Fut>(
+    tasks: Vec<TaskSpec>,
+    checkpoint: &BatchCheckpoint,
+    concurrency: usize,
+    fail_fast: bool,
+    run: F,
+) -> Vec<(String, BatchOutcome)>
+where
+    F: Fn(TaskSpec) -> Fut,
+    Fut: Future<Output = Result<PipelineResult>>,
+{
+    let run = &run;
+    let task_ids: Vec<String> = tasks.iter().map(|task| task.task_id.clone()).collect();
+    let mut outcomes: Vec<Option<BatchOutcome>> = task_ids.iter().map(|_| None).collect();
+    let mut finished = stream::iter(tasks.into_iter().enumerate())
+        .map(|(index, task)| async move {
+            let task_id = task.task_id.clone();
+            if checkpoint.is_completed(&task_id).await {
+                tracing::info!("Skipping task {}: completed in an earlier run", task_id);
+                return (index, BatchOutcome::Skipped);
+            }
+            let outcome = match run(task).await {
+                Ok(result) => match checkpoint.mark_completed(&task_id).await {
+                    Ok(()) => BatchOutcome::Completed(Box::new(result)),
+                    Err(e) => BatchOutcome::Failed(e),
+                },
+                Err(e) => {
+                    tracing::error!("Task {} failed: {:#}", task_id, e);
+                    BatchOutcome::Failed(e)
+                }
+            };
+            (index, outcome)
+        })
+        .buffer_unordered(concurrency.max(1));
+    while let Some((index, outcome)) = finished.next().await {
+        let abort = fail_fast && outcome.is_failure();
+        outcomes[index] = Some(outcome);
+        if abort {
+            tracing::warn!("Task {} failed; aborting the batch (--fail-fast)", task_ids[index]);
+            break;
+        }
+    }
+    // Dropping the stream cancels the tasks still in flight
+    drop(finished);
+    task_ids
+        .into_iter()
+        .zip(outcomes)
+        .map(|(task_id, outcome)| (task_id, outcome.unwrap_or(BatchOutcome::Aborted)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::RunReport;
+    use crate::test_support::{sample_solution_json, sample_task, sample_validation_json};
+    use std::sync::Mutex as StdMutex;
+
+    fn task(id: &str) -> TaskSpec {
+        TaskSpec { task_id: id.to_string(), ..sample_task() }
+    }
+
+    fn result() -> PipelineResult {
+        PipelineResult {
+            solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+            validation: serde_json::from_str(&sample_validation_json()).unwrap(),
+            report: RunReport {
+                started_at: String::new(),
+                finished_at: String::new(),
+                producer_ms: 0,
+                auditor_ms: 0,
+                total_ms: 0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn resumed_batch_skips_completed_tasks_and_retries_failed_ones() {
+        let out_dir = std::env::temp_dir().join(format!("batch_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let tasks = || vec![task("a"), task("b"), task("c"), task("d")];
+
+        // First run: "c" fails, as if the batch had crashed there
+        let ran = StdMutex::new(Vec::new());
+        let checkpoint = BatchCheckpoint::open(&out_dir, false).await.unwrap();
+        let outcomes = run_batch(tasks(), &checkpoint, 2, false, |task| {
+            ran.lock().unwrap().push(task.task_id.clone());
+            async move {
+                if task.task_id == "c" {
+                    anyhow::bail!("interrupted");
+                }
+                Ok(result())
+            }
+        })
+        .await;
+        assert!(matches!(outcomes[2].1, BatchOutcome::Failed(_)));
+        assert_eq!(ran.lock().unwrap().len(), 4);
+
+        // Resume: only the failed task runs again
+        let ran = StdMutex::new(Vec::new());
+        let checkpoint = BatchCheckpoint::open(&out_dir, true).await.unwrap();
+        let outcomes = run_batch(tasks(), &checkpoint, 2, false, |task| {
+            ran.lock().unwrap().push(task.task_id.clone());
+            async { Ok(result()) }
+        })
+        .await;
+        assert_eq!(*ran.lock().unwrap(), vec!["c"]);
+        let ids: Vec<&str> = outcomes.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c", "d"]);
+        assert!(matches!(outcomes[0].1, BatchOutcome::Skipped));
+        assert!(matches!(outcomes[2].1, BatchOutcome::Completed(_)));
+
+        let mut recorded: Vec<String> = std::fs::read_to_string(out_dir.join(CHECKPOINT_FILE))
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        recorded.sort();
+        assert_eq!(recorded, vec!["a", "b", "c", "d"]);
+
+        // An in-memory checkpoint leaves the batch's file alone
+        let in_memory = BatchCheckpoint::in_memory();
+        in_memory.mark_completed("e").await.unwrap();
+        assert!(in_memory.is_completed("e").await);
+        assert_eq!(std::fs::read_to_string(out_dir.join(CHECKPOINT_FILE)).unwrap().lines().count(), 4);
+
+        // Without --resume-batch the checkpoint starts over
+        let fresh = BatchCheckpoint::open(&out_dir, false).await.unwrap();
+        assert!(!fresh.is_completed("a").await);
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn fail_fast_stops_the_batch_at_the_first_failing_task() {
+        let out_dir = std::env::temp_dir().join(format!("batch_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let tasks = vec![task("a"), task("b"), task("c"), task("d")];
+
+        let ran = StdMutex::new(Vec::new());
+        let checkpoint = BatchCheckpoint::open(&out_dir, false).await.unwrap();
+        let outcomes = run_batch(tasks, &checkpoint, 1, true, |task| {
+            ran.lock().unwrap().push(task.task_id.clone());
+            async move {
+                if task.task_id == "b" {
+                    anyhow::bail!("model unavailable");
+                }
+                Ok(result())
+            }
+        })
+        .await;
+
+        assert_eq!(*ran.lock().unwrap(), vec!["a", "b"]);
+        assert!(matches!(outcomes[0].1, BatchOutcome::Completed(_)));
+        assert!(outcomes[1].1.is_failure());
+        assert!(matches!(outcomes[2].1, BatchOutcome::Aborted));
+        assert!(matches!(outcomes[3].1, BatchOutcome::Aborted));
+        // What completed before the abort stays recorded
+        assert!(checkpoint.is_completed("a").await);
+
+        let mut failing = result();
+        failing.validation.verdict = Verdict::Fail;
+        assert!(BatchOutcome::Completed(Box::new(failing)).is_failure());
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn batch_tasks_load_in_file_name_order() {
+        let dir = std::env::temp_dir().join(format!("batch_tasks_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, id) in [("2.json", "second"), ("1.json", "first")] {
+            std::fs::write(dir.join(file), serde_json::to_string(&task(id)).unwrap()).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let tasks = load_batch_tasks(&dir).await.unwrap();
+        let ids: Vec<&str> = tasks.iter().map(|t| t.task_id.as_str()).collect();
+        assert_eq!(ids, vec!["first", "second"]);
+
+        std::fs::write(dir.join("3.json"), serde_json::to_string(&task("first")).unwrap()).unwrap();
+        let err = load_batch_tasks(&dir).await.unwrap_err();
+        assert!(err.to_string().contains("Duplicate task_id 'first'"), "unexpected error: {:#}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::types::{ValidationV1, Verdict};
+
+/// How a single criterion changed between two validation runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CriterionChange {
+    Unchanged,
+    /// fail → pass
+    Fixed,
+    /// pass → fail
+    Regressed,
+    /// only present in the second run
+    Added,
+    /// only present in the first run
+    Removed,
+}
+
+/// Per-criterion pass/fail state across two runs
+#[derive(Debug, Clone)]
+pub struct CriterionDelta {
+    pub criterion: String,
+    pub before: Option<bool>,
+    pub after: Option<bool>,
+    pub change: CriterionChange,
+}
+
+/// Side-by-side comparison of two `ValidationV1` artifacts
+#[derive(Debug, Clone)]
+pub struct ValidationComparison {
+    pub verdicts: (Verdict, Verdict),
+    pub scores: (f32, f32),
+    pub criteria: Vec<CriterionDelta>,
+}
+
+impl ValidationComparison {
+    /// Match checks by criterion text (trimmed, case-insensitive) and record pass/fail deltas
+    pub fn new(before: &ValidationV1, after: &ValidationV1) -> Self {
+        let normalize = |s: &str| s.trim().to_lowercase();
+        let mut criteria: Vec<CriterionDelta> = Vec::new();
+
+        for chk in &before.checks {
+            let after_pass = after
+                .checks
+                .iter()
+                .find(|c| normalize(&c.criterion) == normalize(&chk.criterion))
+                .map(|c| c.pass_);
+            let change = match (chk.pass_, after_pass) {
+                (_, None) => CriterionChange::Removed,
+                (true, Some(false)) => CriterionChange::Regressed,
+                (false, Some(true)) => CriterionChange::Fixed,
+                _ => CriterionChange::Unchanged,
+            };
+            criteria.push(CriterionDelta {
+                criterion: chk.criterion.clone(),
+                before: Some(chk.pass_),
+                after: after_pass,
+                change,
+            });
+        }
+
+        for chk in &after.checks {
+            let known = before
+                .checks
+                .iter()
+                .any(|c| normalize(&c.criterion) == normalize(&chk.criterion));
+            if !known {
+                criteria.push(CriterionDelta {
+                    criterion: chk.criterion.clone(),
+                    before: None,
+                    after: Some(chk.pass_),
+                    change: CriterionChange::Added,
+                });
+            }
+        }
+
+        Self {
+            verdicts: (before.verdict.clone(), after.verdict.clone()),
+            scores: (before.score, after.score),
+            criteria,
+        }
+    }
+
+    /// Criteria that passed in the first run and fail in the second
+    pub fn regressions(&self) -> impl Iterator<Item = &CriterionDelta> {
+        self.criteria
+            .iter()
+            .filter(|d| d.change == CriterionChange::Regressed)
+    }
+}
+
+/// Load `validation.json` from a run output directory
+pub async fn load_validation(dir: &Path) -> Result<ValidationV1> {
+    let path = dir.join(crate::orchestrator::VALIDATION_KEY);
+    let bytes = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("Invalid ValidationV1 in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CheckResult, ModelUsed, Severity};
+
+    fn check(criterion: &str, pass: bool) -> CheckResult {
+        CheckResult {
+            criterion: criterion.to_string(),
+            pass_: pass,
+            reason: String::new(),
+            severity: Severity::Minor,
+            suggested_fix: None,
+            partial_score: None,
+            unmatched: false,
+            evidence_lines: None,
+        }
+    }
+
+    fn validation(verdict: Verdict, score: f32, checks: Vec<CheckResult>) -> ValidationV1 {
+        ValidationV1 {
+            schema_version: "validation_v1".to_string(),
+            task_id: "t".to_string(),
+            solution_id: "s".to_string(),
+            verdict,
+            score,
+            checks,
+            suggested_rewrite: None,
+            model_used: ModelUsed { name: "deepseek-reasoner".to_string(), temperature: 0.0 },
+            created_at: String::new(),
+            reasoning: None,
+        }
+    }
+
+    #[test]
+    fn detects_per_criterion_deltas() {
+        let before = validation(
+            Verdict::Pass,
+            0.9,
+            vec![
+                check("exactly 3 bullets", true),
+                check("no fluff", false),
+                check("<= 80 words", true),
+                check("dropped", true),
+            ],
+        );
+        let after = validation(
+            Verdict::Fail,
+            0.4,
+            vec![
+                check("Exactly 3 bullets ", false),
+                check("no fluff", true),
+                check("<= 80 words", true),
+                check("new criterion", false),
+            ],
+        );
+
+        let cmp = ValidationComparison::new(&before, &after);
+        assert!(matches!(cmp.verdicts, (Verdict::Pass, Verdict::Fail)));
+        assert_eq!(cmp.scores, (0.9, 0.4));
+
+        let changes: Vec<(&str, CriterionChange)> = cmp
+            .criteria
+            .iter()
+            .map(|d| (d.criterion.as_str(), d.change))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("exactly 3 bullets", CriterionChange::Regressed),
+                ("no fluff", CriterionChange::Fixed),
+                ("<= 80 words", CriterionChange::Unchanged),
+                ("dropped", CriterionChange::Removed),
+                ("new criterion", CriterionChange::Added),
+            ]
+        );
+        assert_eq!(cmp.regressions().count(), 1);
+    }
+}
@@ -1,14 +1,154 @@
 use anyhow::{Context, Result};
 use std::env;
+use std::fmt;
+use std::path::Path;
+use thiserror::Error;
 
 const DEFAULT_BASE_URL: &str = "https://api.deepseek.com/v1";
 const DEFAULT_MODEL: &str = "deepseek-chat";
 const DEFAULT_MAX_TOKENS: u32 = 4096;
 const DEFAULT_TEMPERATURE: f32 = 0.7;
 const DEFAULT_TIMEOUT: u64 = 180;
+/// Ceiling on a single retry back-off, so retries stay predictable
+const DEFAULT_MAX_BACKOFF_MS: u64 = 30_000;
+/// Grading should be deterministic, so the auditor samples greedily unless overridden
+const DEFAULT_AUDITOR_TEMPERATURE: f32 = 0.0;
+const DEFAULT_USER_AGENT: &str = concat!("deepseek_agents/", env!("CARGO_PKG_VERSION"));
+/// Models the API is known to serve; extended with `DEEPSEEK_KNOWN_MODELS`
+pub const KNOWN_MODELS: &[&str] = &["deepseek-chat", "deepseek-reasoner"];
+/// Widest `JsonIndent::Spaces` accepted
+const MAX_JSON_INDENT: u8 = 16;
 
-/// Configuration structure for the DeepSeek client
-#[derive(Debug, Clone)]
+/// Reasons a `Config` is rejected by `Config::validate`
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    #[error("API key cannot be empty")]
+    MissingApiKey,
+
+    #[error("Base URL must be an absolute http(s) URL, got '{0}'")]
+    InvalidBaseUrl(String),
+
+    #[error("Temperature must be between 0.0 and 2.0, got {0}")]
+    InvalidTemperature(f32),
+
+    #[error("Auditor temperature must be between 0.0 and 2.0, got {0}")]
+    InvalidAuditorTemperature(f32),
+
+    #[error("Max tokens must be greater than 0")]
+    InvalidMaxTokens,
+
+    #[error("Timeout must be greater than 0")]
+    InvalidTimeout,
+
+    #[error("Requests per minute must be greater than 0")]
+    InvalidRequestsPerMinute,
+
+    #[error("Max concurrent requests must be greater than 0")]
+    InvalidMaxConcurrentRequests,
+
+    #[error("Max backoff must be greater than 0")]
+    InvalidMaxBackoff,
+
+    #[error("Candidates (n) must be greater than 0")]
+    InvalidCandidates,
+
+    #[error("Artifact mode must be a permission mode no greater than 0o7777, got {0:#o}")]
+    InvalidArtifactMode(u32),
+
+    #[error("Unknown model '{model}'{}; add it to DEEPSEEK_KNOWN_MODELS if it is intended", suggestion.as_ref().map(|s| format!(" (did you mean '{}'?)", s)).unwrap_or_default())]
+    UnknownModel { model: String, suggestion: Option<String> },
+}
+
+/// Load environment variables from the dotenv file at `path` (`--env-file`), or from `.env`
+/// in the current directory when `path` is `None`. Variables already set in the environment
+/// win. A missing `.env` is fine; a missing or malformed `path` is an error.
+pub fn load_env_file(path: Option<&Path>) -> Result<()> {
+    let Some(path) = path else {
+        dotenv::dotenv().ok();
+        return Ok(());
+    };
+    if !path.is_file() {
+        anyhow::bail!("env file {} does not exist", path.display());
+    }
+    dotenv::from_path(path).with_context(|| format!("Failed to load env file {}", path.display()))
+}
+
+/// Parse a boolean env var (`1/true/yes/on` or `0/false/no/off`); `default` when unset
+fn env_flag(key: &str, default: bool) -> Result<bool> {
+    match env::var(key) {
+        Err(_) => Ok(default),
+        Ok(v) => match v.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "" | "0" | "false" | "no" | "off" => Ok(false),
+            _ => anyhow::bail!("{} must be a boolean (true/false, 1/0)", key),
+        },
+    }
+}
+
+/// Mask an API key for logs: `sk-...` plus the last 4 characters, or `***` when too short to hint at
+fn mask_api_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "***".to_string();
+    }
+    let prefix: String = chars[..3].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", prefix, suffix)
+}
+
+/// Indentation of the pretty-printed JSON artifacts (`--indent`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonIndent {
+    Spaces(u8),
+    Tab,
+}
+
+impl Default for JsonIndent {
+    fn default() -> Self {
+        JsonIndent::Spaces(2)
+    }
+}
+
+impl std::str::FromStr for JsonIndent {
+    type Err = String;
+
+    /// A number of spaces (0 to `MAX_JSON_INDENT`) or `tab`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("tab") {
+            return Ok(JsonIndent::Tab);
+        }
+        match s.parse::<u8>() {
+            Ok(n) if n <= MAX_JSON_INDENT => Ok(JsonIndent::Spaces(n)),
+            _ => Err(format!(
+                "expected a number of spaces between 0 and {} or 'tab', got '{}'",
+                MAX_JSON_INDENT, s
+            )),
+        }
+    }
+}
+
+impl JsonIndent {
+    /// `value` as pretty-printed JSON indented with this style
+    pub fn to_string_pretty<T: serde::Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> serde_json::Result<String> {
+        let indent = match self {
+            JsonIndent::Spaces(n) => " ".repeat(usize::from(n)),
+            JsonIndent::Tab => "\t".to_string(),
+        };
+        let mut out = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        value.serialize(&mut serializer)?;
+        // The serializer only writes valid UTF-8
+        Ok(String::from_utf8(out).expect("serde_json output is UTF-8"))
+    }
+}
+
+/// Configuration structure for the DeepSeek client. `Debug` masks the API key.
+#[derive(Clone)]
 pub struct Config {
     pub api_key: String,
     pub base_url: String,
@@ -16,9 +156,88 @@ pub struct Config {
     pub max_tokens: u32,
     pub temperature: f32,
     pub timeout: u64,
+    pub user_agent: Option<String>,
+    /// Client-side cap on requests sent per rolling minute; unlimited when `None`
+    pub requests_per_minute: Option<u32>,
+    /// Client-side cap on requests in flight at once, shared by every client derived from
+    /// the same one (chat and reasoner); unlimited when `None`
+    pub max_concurrent_requests: Option<usize>,
+    /// Auditor sampling temperature; `DEFAULT_AUDITOR_TEMPERATURE` when `None`
+    pub auditor_temperature: Option<f32>,
+    /// System prompt of the interactive chat (`Console::run`); built-in prompt when `None`
+    pub chat_system_prompt: Option<String>,
+    /// JSON schema the interactive chat asks for; it must still describe the
+    /// `title`/`description`/`content` fields of `DeepSeekResponse`
+    pub chat_json_schema: Option<String>,
+    /// Send an `Idempotency-Key` header, reused across retries of the same request
+    pub use_idempotency_key: bool,
+    /// Send `response_format: json_object`; when false, JSON output relies on the prompt alone.
+    /// Also switched off at runtime if the backend rejects the field with a 400.
+    pub response_format_supported: bool,
+    /// Unix permission bits for written artifacts (e.g. `0o600`); umask default when `None`
+    pub artifact_mode: Option<u32>,
+    /// Upper bound, in milliseconds, on each exponential retry back-off
+    pub max_backoff_ms: u64,
+    /// Always use the internal HTTP client, even where the `deepseek_api` backend would apply
+    pub force_internal: bool,
+    /// Completions requested per producer call (the API's `n`); above 1 the producer picks the best
+    pub n: Option<u8>,
+    /// Send `response_format` to `deepseek-reasoner` too; when false its JSON is taken from the
+    /// prompt and extracted from the reply
+    pub json_format_for_reasoner: bool,
+    /// Language the agents write deliverables and audit reasons in; JSON keys stay English
+    pub output_language: Option<String>,
+    /// Model names accepted in addition to `KNOWN_MODELS`
+    pub extra_known_models: Vec<String>,
+    /// Indentation of the JSON artifacts the agents write
+    pub json_indent: JsonIndent,
+    /// Write the JSON artifacts with every object's keys sorted, for stable diffs across runs
+    pub sort_keys: bool,
+    /// Organization the requests are billed to, sent as `OpenAI-Organization` and recorded
+    /// in the run summary
+    pub organization: Option<String>,
+    /// Project the requests are billed to, sent as `OpenAI-Project` and recorded in the run summary
+    pub project: Option<String>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("api_key", &mask_api_key(&self.api_key))
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .field("max_tokens", &self.max_tokens)
+            .field("temperature", &self.temperature)
+            .field("timeout", &self.timeout)
+            .field("user_agent", &self.user_agent)
+            .field("requests_per_minute", &self.requests_per_minute)
+            .field("max_concurrent_requests", &self.max_concurrent_requests)
+            .field("auditor_temperature", &self.auditor_temperature)
+            .field("chat_system_prompt", &self.chat_system_prompt)
+            .field("chat_json_schema", &self.chat_json_schema)
+            .field("use_idempotency_key", &self.use_idempotency_key)
+            .field("response_format_supported", &self.response_format_supported)
+            .field("artifact_mode", &self.artifact_mode.map(|m| format!("{:#o}", m)))
+            .field("max_backoff_ms", &self.max_backoff_ms)
+            .field("force_internal", &self.force_internal)
+            .field("n", &self.n)
+            .field("json_format_for_reasoner", &self.json_format_for_reasoner)
+            .field("output_language", &self.output_language)
+            .field("extra_known_models", &self.extra_known_models)
+            .field("json_indent", &self.json_indent)
+            .field("sort_keys", &self.sort_keys)
+            .field("organization", &self.organization)
+            .field("project", &self.project)
+            .finish()
+    }
 }
 
 impl Config {
+    /// All fields rendered for logging, with the API key masked (`sk-...last4`)
+    pub fn redacted_debug(&self) -> String {
+        format!("{:?}", self)
+    }
+
     /// Load configuration from environment variables
     pub fn load() -> Result<Self> {
         let api_key = env::var("DEEPSEEK_API_KEY")
@@ -44,6 +263,97 @@ impl Config {
             .parse::<u64>()
             .context("DEEPSEEK_TIMEOUT must be a valid number")?;
 
+        let user_agent = env::var("DEEPSEEK_USER_AGENT")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        let requests_per_minute = env::var("DEEPSEEK_REQUESTS_PER_MINUTE")
+            .ok()
+            .map(|v| v.parse::<u32>())
+            .transpose()
+            .context("DEEPSEEK_REQUESTS_PER_MINUTE must be a valid number")?;
+
+        let max_concurrent_requests = env::var("DEEPSEEK_MAX_CONCURRENT_REQUESTS")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .context("DEEPSEEK_MAX_CONCURRENT_REQUESTS must be a valid number")?;
+
+        let auditor_temperature = env::var("DEEPSEEK_AUDITOR_TEMPERATURE")
+            .ok()
+            .map(|v| v.parse::<f32>())
+            .transpose()
+            .context("DEEPSEEK_AUDITOR_TEMPERATURE must be a valid number")?;
+
+        let chat_system_prompt = env::var("DEEPSEEK_CHAT_SYSTEM_PROMPT")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        let chat_json_schema = env::var("DEEPSEEK_CHAT_JSON_SCHEMA")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        let use_idempotency_key = env_flag("DEEPSEEK_IDEMPOTENCY_KEY", false)?;
+
+        let response_format_supported = env_flag("DEEPSEEK_RESPONSE_FORMAT_SUPPORTED", true)?;
+
+        let artifact_mode = env::var("DEEPSEEK_ARTIFACT_MODE")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| u32::from_str_radix(v.trim().trim_start_matches("0o"), 8))
+            .transpose()
+            .context("DEEPSEEK_ARTIFACT_MODE must be an octal mode such as 600 or 0o600")?;
+
+        let max_backoff_ms = env::var("DEEPSEEK_MAX_BACKOFF_MS")
+            .unwrap_or_else(|_| DEFAULT_MAX_BACKOFF_MS.to_string())
+            .parse::<u64>()
+            .context("DEEPSEEK_MAX_BACKOFF_MS must be a valid number")?;
+
+        let force_internal = env_flag("DEEPSEEK_FORCE_INTERNAL", false)?;
+
+        let n = env::var("DEEPSEEK_CANDIDATES")
+            .ok()
+            .map(|v| v.parse::<u8>())
+            .transpose()
+            .context("DEEPSEEK_CANDIDATES must be a number between 1 and 255")?;
+
+        let json_format_for_reasoner = env_flag("DEEPSEEK_JSON_FORMAT_FOR_REASONER", true)?;
+
+        let output_language = env::var("DEEPSEEK_LANG")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        let extra_known_models = env::var("DEEPSEEK_KNOWN_MODELS")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|m| !m.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let json_indent = env::var("DEEPSEEK_JSON_INDENT")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| v.parse::<JsonIndent>())
+            .transpose()
+            .map_err(anyhow::Error::msg)
+            .context("DEEPSEEK_JSON_INDENT must be a number of spaces or 'tab'")?
+            .unwrap_or_default();
+
+        let sort_keys = env_flag("DEEPSEEK_SORT_KEYS", false)?;
+
+        let organization = env::var("DEEPSEEK_ORGANIZATION")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let project = env::var("DEEPSEEK_PROJECT")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
         Ok(Self {
             api_key,
             base_url,
@@ -51,25 +361,123 @@ impl Config {
             max_tokens,
             temperature,
             timeout,
+            user_agent,
+            requests_per_minute,
+            max_concurrent_requests,
+            auditor_temperature,
+            chat_system_prompt,
+            chat_json_schema,
+            use_idempotency_key,
+            response_format_supported,
+            artifact_mode,
+            max_backoff_ms,
+            force_internal,
+            n,
+            json_format_for_reasoner,
+            output_language,
+            extra_known_models,
+            json_indent,
+            sort_keys,
+            organization,
+            project,
         })
     }
 
+    /// `value` as the text of an artifact file: pretty-printed with `json_indent`, with the keys
+    /// of every object (struct fields included) in sorted order when `sort_keys` is set
+    pub fn artifact_json<T: serde::Serialize + ?Sized>(&self, value: &T) -> serde_json::Result<String> {
+        if !self.sort_keys {
+            return self.json_indent.to_string_pretty(value);
+        }
+        let mut value = serde_json::to_value(value)?;
+        // Already sorted unless serde_json's `preserve_order` is enabled
+        value.sort_all_objects();
+        self.json_indent.to_string_pretty(&value)
+    }
+
+    /// User-agent sent with every request; defaults to the crate name and version
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+
+    /// Temperature used by the auditor; lower than the producer's by default
+    pub fn auditor_temperature(&self) -> f32 {
+        self.auditor_temperature.unwrap_or(DEFAULT_AUDITOR_TEMPERATURE)
+    }
+
+    /// Whether `model` is in `KNOWN_MODELS` or `extra_known_models`
+    pub fn is_known_model(&self) -> bool {
+        KNOWN_MODELS.contains(&self.model.as_str())
+            || self.extra_known_models.contains(&self.model)
+    }
+
+    /// `ConfigError::UnknownModel`, naming the most similar known model, when `model` is not
+    /// recognized; catches typos such as `deepseek-resoner` before any request is sent
+    pub fn check_model(&self) -> Result<(), ConfigError> {
+        if self.is_known_model() {
+            return Ok(());
+        }
+        let suggestion = KNOWN_MODELS
+            .iter()
+            .copied()
+            .chain(self.extra_known_models.iter().map(String::as_str))
+            .map(|known| (known, crate::align::similarity(&self.model, known)))
+            .filter(|(_, score)| *score >= crate::align::DEFAULT_MATCH_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(known, _)| known.to_string());
+        Err(ConfigError::UnknownModel { model: self.model.clone(), suggestion })
+    }
+
     /// Validate the configuration
-    pub fn validate(&self) -> Result<()> {
+    pub fn validate(&self) -> Result<(), ConfigError> {
         if self.api_key.is_empty() {
-            anyhow::bail!("API key cannot be empty");
+            return Err(ConfigError::MissingApiKey);
         }
 
-        if self.temperature < 0.0 || self.temperature > 2.0 {
-            anyhow::bail!("Temperature must be between 0.0 and 2.0");
+        let base_url_ok = reqwest::Url::parse(&self.base_url)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host());
+        if !base_url_ok {
+            return Err(ConfigError::InvalidBaseUrl(self.base_url.clone()));
+        }
+
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err(ConfigError::InvalidTemperature(self.temperature));
+        }
+
+        if let Some(t) = self.auditor_temperature
+            && !(0.0..=2.0).contains(&t)
+        {
+            return Err(ConfigError::InvalidAuditorTemperature(t));
         }
 
         if self.max_tokens == 0 {
-            anyhow::bail!("Max tokens must be greater than 0");
+            return Err(ConfigError::InvalidMaxTokens);
         }
 
         if self.timeout == 0 {
-            anyhow::bail!("Timeout must be greater than 0");
+            return Err(ConfigError::InvalidTimeout);
+        }
+
+        if self.requests_per_minute == Some(0) {
+            return Err(ConfigError::InvalidRequestsPerMinute);
+        }
+
+        if self.max_concurrent_requests == Some(0) {
+            return Err(ConfigError::InvalidMaxConcurrentRequests);
+        }
+
+        if self.max_backoff_ms == 0 {
+            return Err(ConfigError::InvalidMaxBackoff);
+        }
+
+        if self.n == Some(0) {
+            return Err(ConfigError::InvalidCandidates);
+        }
+
+        if let Some(mode) = self.artifact_mode
+            && mode > 0o7777
+        {
+            return Err(ConfigError::InvalidArtifactMode(mode));
         }
 
         Ok(())
@@ -85,6 +493,25 @@ impl Default for Config {
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
             timeout: DEFAULT_TIMEOUT,
+            user_agent: None,
+            requests_per_minute: None,
+            max_concurrent_requests: None,
+            auditor_temperature: None,
+            chat_system_prompt: None,
+            chat_json_schema: None,
+            use_idempotency_key: false,
+            response_format_supported: true,
+            artifact_mode: None,
+            max_backoff_ms: DEFAULT_MAX_BACKOFF_MS,
+            force_internal: false,
+            n: None,
+            json_format_for_reasoner: true,
+            output_language: None,
+            extra_known_models: Vec::new(),
+            json_indent: JsonIndent::default(),
+            sort_keys: false,
+            organization: None,
+            project: None,
         }
     }
 }
@@ -112,6 +539,25 @@ mod tests {
         "DEEPSEEK_MAX_TOKENS",
         "DEEPSEEK_TEMPERATURE",
         "DEEPSEEK_TIMEOUT",
+        "DEEPSEEK_USER_AGENT",
+        "DEEPSEEK_REQUESTS_PER_MINUTE",
+        "DEEPSEEK_MAX_CONCURRENT_REQUESTS",
+        "DEEPSEEK_AUDITOR_TEMPERATURE",
+        "DEEPSEEK_CHAT_SYSTEM_PROMPT",
+        "DEEPSEEK_CHAT_JSON_SCHEMA",
+        "DEEPSEEK_IDEMPOTENCY_KEY",
+        "DEEPSEEK_RESPONSE_FORMAT_SUPPORTED",
+        "DEEPSEEK_ARTIFACT_MODE",
+        "DEEPSEEK_MAX_BACKOFF_MS",
+        "DEEPSEEK_FORCE_INTERNAL",
+        "DEEPSEEK_CANDIDATES",
+        "DEEPSEEK_JSON_FORMAT_FOR_REASONER",
+        "DEEPSEEK_LANG",
+        "DEEPSEEK_KNOWN_MODELS",
+        "DEEPSEEK_JSON_INDENT",
+        "DEEPSEEK_SORT_KEYS",
+        "DEEPSEEK_ORGANIZATION",
+        "DEEPSEEK_PROJECT",
     ];
 
     fn clear_env() {
@@ -122,6 +568,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn env_file_overrides_are_loaded_and_a_missing_file_is_an_error() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        let path = env::temp_dir().join(format!("env_{}.test", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "DEEPSEEK_API_KEY=file_key\nDEEPSEEK_MODEL=deepseek-reasoner\nDEEPSEEK_TIMEOUT=42\n",
+        )?;
+
+        load_env_file(Some(&path))?;
+        let config = Config::load()?;
+        assert_eq!(config.api_key, "file_key");
+        assert_eq!(config.model, "deepseek-reasoner");
+        assert_eq!(config.timeout, 42);
+
+        let _ = std::fs::remove_file(&path);
+        let err = load_env_file(Some(&path)).unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+        clear_env();
+        Ok(())
+    }
+
     #[test]
     fn load_missing_api_key_errors() {
         let _guard = lock_env();
@@ -151,6 +620,28 @@ mod tests {
         assert_eq!(config.max_tokens, DEFAULT_MAX_TOKENS);
         assert!((config.temperature - DEFAULT_TEMPERATURE).abs() < f32::EPSILON);
         assert_eq!(config.timeout, DEFAULT_TIMEOUT);
+        assert_eq!(config.user_agent, None);
+        assert_eq!(config.requests_per_minute, None);
+        assert_eq!(config.max_concurrent_requests, None);
+        assert_eq!(config.auditor_temperature(), DEFAULT_AUDITOR_TEMPERATURE);
+        assert!(!config.use_idempotency_key);
+        assert!(config.response_format_supported);
+        assert_eq!(config.artifact_mode, None);
+        assert_eq!(config.max_backoff_ms, DEFAULT_MAX_BACKOFF_MS);
+        assert!(!config.force_internal);
+        assert_eq!(config.n, None);
+        assert!(config.json_format_for_reasoner);
+        assert_eq!(config.output_language, None);
+        assert!(config.extra_known_models.is_empty());
+        assert_eq!(config.json_indent, JsonIndent::Spaces(2));
+        assert!(!config.sort_keys);
+        assert_eq!(config.organization, None);
+        assert_eq!(config.project, None);
+        assert!(config.is_known_model());
+        assert_eq!(
+            config.user_agent(),
+            format!("deepseek_agents/{}", env!("CARGO_PKG_VERSION"))
+        );
 
         // Also ensure validate passes on defaults
         config.validate()?;
@@ -168,6 +659,24 @@ mod tests {
             env::set_var("DEEPSEEK_MAX_TOKENS", "1234");
             env::set_var("DEEPSEEK_TEMPERATURE", "1.25");
             env::set_var("DEEPSEEK_TIMEOUT", "33");
+            env::set_var("DEEPSEEK_USER_AGENT", "team-a/1.0");
+            env::set_var("DEEPSEEK_REQUESTS_PER_MINUTE", "30");
+            env::set_var("DEEPSEEK_MAX_CONCURRENT_REQUESTS", "4");
+            env::set_var("DEEPSEEK_AUDITOR_TEMPERATURE", "0.2");
+            env::set_var("DEEPSEEK_CHAT_SYSTEM_PROMPT", "You are a bot.");
+            env::set_var("DEEPSEEK_IDEMPOTENCY_KEY", "true");
+            env::set_var("DEEPSEEK_RESPONSE_FORMAT_SUPPORTED", "false");
+            env::set_var("DEEPSEEK_ARTIFACT_MODE", "0o640");
+            env::set_var("DEEPSEEK_MAX_BACKOFF_MS", "5000");
+            env::set_var("DEEPSEEK_FORCE_INTERNAL", "1");
+            env::set_var("DEEPSEEK_CANDIDATES", "3");
+            env::set_var("DEEPSEEK_JSON_FORMAT_FOR_REASONER", "false");
+            env::set_var("DEEPSEEK_LANG", " German ");
+            env::set_var("DEEPSEEK_KNOWN_MODELS", "custom-model, other-model,");
+            env::set_var("DEEPSEEK_JSON_INDENT", "tab");
+            env::set_var("DEEPSEEK_SORT_KEYS", "true");
+            env::set_var("DEEPSEEK_ORGANIZATION", "org-billing");
+            env::set_var("DEEPSEEK_PROJECT", " search ");
         }
 
         let config = Config::load()?;
@@ -177,9 +686,66 @@ mod tests {
         assert_eq!(config.max_tokens, 1234);
         assert!((config.temperature - 1.25).abs() < f32::EPSILON);
         assert_eq!(config.timeout, 33);
+        assert_eq!(config.user_agent(), "team-a/1.0");
+        assert_eq!(config.requests_per_minute, Some(30));
+        assert_eq!(config.max_concurrent_requests, Some(4));
+        assert!((config.auditor_temperature() - 0.2).abs() < f32::EPSILON);
+        assert_eq!(config.chat_system_prompt.as_deref(), Some("You are a bot."));
+        assert_eq!(config.chat_json_schema, None);
+        assert!(config.use_idempotency_key);
+        assert!(!config.response_format_supported);
+        assert_eq!(config.artifact_mode, Some(0o640));
+        assert_eq!(config.max_backoff_ms, 5000);
+        assert!(config.force_internal);
+        assert_eq!(config.n, Some(3));
+        assert!(!config.json_format_for_reasoner);
+        assert_eq!(config.output_language.as_deref(), Some("German"));
+        assert_eq!(config.extra_known_models, vec!["custom-model", "other-model"]);
+        assert_eq!(config.json_indent, JsonIndent::Tab);
+        assert!(config.sort_keys);
+        assert_eq!(config.organization.as_deref(), Some("org-billing"));
+        assert_eq!(config.project.as_deref(), Some("search"));
+        assert!(config.is_known_model());
         Ok(())
     }
 
+    #[test]
+    fn json_indent_parses_spaces_and_tab() {
+        assert_eq!("4".parse::<JsonIndent>(), Ok(JsonIndent::Spaces(4)));
+        assert_eq!(" TAB ".parse::<JsonIndent>(), Ok(JsonIndent::Tab));
+        assert!("17".parse::<JsonIndent>().is_err());
+        assert!("wide".parse::<JsonIndent>().is_err());
+        assert_eq!(
+            JsonIndent::Tab.to_string_pretty(&serde_json::json!({"a": [1]})).unwrap(),
+            "{\n\t\"a\": [\n\t\t1\n\t]\n}"
+        );
+    }
+
+    #[test]
+    fn sorted_artifacts_are_byte_identical_for_identical_json() {
+        let config = Config { sort_keys: true, ..Config::default() };
+        let solution = |deliverable: &str| {
+            let mut solution: serde_json::Value =
+                serde_json::from_str(&crate::test_support::sample_solution_json()).unwrap();
+            solution["deliverable"] = serde_json::from_str(deliverable).unwrap();
+            serde_json::from_value::<crate::types::SolutionV1>(solution).unwrap()
+        };
+        let first = config
+            .artifact_json(&solution(r#"{"json": {"zeta": 1, "alpha": {"y": 2, "x": 3}}}"#))
+            .unwrap();
+        let second = config
+            .artifact_json(&solution(r#"{"json": {"alpha": {"x": 3, "y": 2}, "zeta": 1}}"#))
+            .unwrap();
+        assert_eq!(first, second);
+        let position = |key: &str| first.find(&format!("\"{}\"", key)).unwrap();
+        assert!(position("alpha") < position("zeta"));
+        // Struct fields are sorted too
+        assert!(position("created_at") < position("schema_version"));
+
+        let unsorted = Config::default().artifact_json(&solution(r#"{"text": "t"}"#)).unwrap();
+        assert!(unsorted.starts_with("{\n  \"schema_version\""));
+    }
+
     #[test]
     fn load_invalid_max_tokens_errors() {
         let _guard = lock_env();
@@ -234,6 +800,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn load_invalid_flag_errors() {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+            env::set_var("DEEPSEEK_IDEMPOTENCY_KEY", "maybe");
+        }
+
+        let err = Config::load().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("DEEPSEEK_IDEMPOTENCY_KEY must be a boolean"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
     #[test]
     fn validate_rejects_empty_api_key() {
         let config = Config {
@@ -243,6 +827,7 @@ mod tests {
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
             timeout: DEFAULT_TIMEOUT,
+            ..Config::default()
         };
         let err = config.validate().unwrap_err();
         assert!(
@@ -261,6 +846,7 @@ mod tests {
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: -0.1,
             timeout: DEFAULT_TIMEOUT,
+            ..Config::default()
         };
         let err = config.validate().unwrap_err();
         assert!(
@@ -278,6 +864,102 @@ mod tests {
             "unexpected error: {}",
             err
         );
+
+        config.temperature = DEFAULT_TEMPERATURE;
+        config.auditor_temperature = Some(2.5);
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Auditor temperature must be between 0.0 and 2.0"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn redacted_debug_masks_api_key() {
+        let config = Config {
+            api_key: "sk-0123456789abcdef".to_string(),
+            ..Config::default()
+        };
+        for rendered in [config.redacted_debug(), format!("{:?}", config)] {
+            assert!(!rendered.contains("0123456789"), "key leaked: {}", rendered);
+            assert!(rendered.contains("api_key: \"sk-...cdef\""), "{}", rendered);
+            assert!(rendered.contains("model: \"deepseek-chat\""), "{}", rendered);
+        }
+
+        let short = Config {
+            api_key: "short".to_string(),
+            ..Config::default()
+        };
+        assert!(short.redacted_debug().contains("api_key: \"***\""));
+    }
+
+    #[test]
+    fn validate_reports_typed_errors() {
+        let valid = Config {
+            api_key: "k".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(valid.validate(), Ok(()));
+
+        let cases = [
+            (Config { api_key: String::new(), ..valid.clone() }, ConfigError::MissingApiKey),
+            (
+                Config { base_url: "api.deepseek.com".to_string(), ..valid.clone() },
+                ConfigError::InvalidBaseUrl("api.deepseek.com".to_string()),
+            ),
+            (
+                Config { base_url: "ftp://api.deepseek.com".to_string(), ..valid.clone() },
+                ConfigError::InvalidBaseUrl("ftp://api.deepseek.com".to_string()),
+            ),
+            (Config { temperature: 3.0, ..valid.clone() }, ConfigError::InvalidTemperature(3.0)),
+            (
+                Config { auditor_temperature: Some(-1.0), ..valid.clone() },
+                ConfigError::InvalidAuditorTemperature(-1.0),
+            ),
+            (Config { max_tokens: 0, ..valid.clone() }, ConfigError::InvalidMaxTokens),
+            (Config { timeout: 0, ..valid.clone() }, ConfigError::InvalidTimeout),
+            (
+                Config { requests_per_minute: Some(0), ..valid.clone() },
+                ConfigError::InvalidRequestsPerMinute,
+            ),
+            (
+                Config { max_concurrent_requests: Some(0), ..valid.clone() },
+                ConfigError::InvalidMaxConcurrentRequests,
+            ),
+            (Config { max_backoff_ms: 0, ..valid.clone() }, ConfigError::InvalidMaxBackoff),
+            (Config { n: Some(0), ..valid.clone() }, ConfigError::InvalidCandidates),
+            (
+                Config { artifact_mode: Some(0o10000), ..valid.clone() },
+                ConfigError::InvalidArtifactMode(0o10000),
+            ),
+        ];
+        for (config, expected) in cases {
+            assert_eq!(config.validate(), Err(expected));
+        }
+    }
+
+    #[test]
+    fn unknown_model_is_reported_with_the_closest_known_one() {
+        let mut config = Config { model: "deepseek-resoner".to_string(), ..Config::default() };
+        assert!(!config.is_known_model());
+        let err = config.check_model().unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::UnknownModel {
+                model: "deepseek-resoner".to_string(),
+                suggestion: Some("deepseek-reasoner".to_string()),
+            }
+        );
+        assert!(err.to_string().contains("did you mean 'deepseek-reasoner'?"), "{}", err);
+
+        config.model = "llama".to_string();
+        let err = config.check_model().unwrap_err();
+        assert!(!err.to_string().contains("did you mean"), "{}", err);
+
+        config.extra_known_models = vec!["llama".to_string()];
+        assert!(config.check_model().is_ok());
     }
 
     #[test]
@@ -289,6 +971,7 @@ mod tests {
             max_tokens: 0,
             temperature: DEFAULT_TEMPERATURE,
             timeout: DEFAULT_TIMEOUT,
+            ..Config::default()
         };
         let err = config.validate().unwrap_err();
         assert!(
@@ -306,5 +989,15 @@ mod tests {
             "unexpected error: {}",
             err
         );
+
+        config.timeout = 1;
+        config.requests_per_minute = Some(0);
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Requests per minute must be greater than 0"),
+            "unexpected error: {}",
+            err
+        );
     }
 }
@@ -35,8 +35,58 @@ pub async fn prompt_user(prompt_text: &str) -> Result<String> {
     Ok(input.trim().to_string())
 }
 
+/// Acceptance criteria typed on one line, separated by `,`, `;` or newlines
+pub fn split_criteria(raw: &str) -> Vec<String> {
+    raw.split([',', ';', '\n'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Acceptance criteria entered one per line, up to the first blank line. Separators
+/// inside a line are kept, so a criterion may contain commas.
+pub fn criteria_from_lines<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|line| line.as_ref().trim().to_string())
+        .take_while(|line| !line.is_empty())
+        .collect()
+}
+
+/// Prompt for acceptance criteria one per line until an empty line is entered
+pub async fn prompt_criteria_lines() -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    loop {
+        let line = prompt_user("   • ").await?;
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    Ok(criteria_from_lines(lines))
+}
+
 /// Check if the input is a quit command
 #[allow(dead_code)]
 pub fn is_quit_command(input: &str) -> bool {
     input.eq_ignore_ascii_case("/quit") || input.eq_ignore_ascii_case("/exit")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_mode_keeps_commas_within_criteria() {
+        let lines = ["use present tense, active voice", "  <= 80 words; no jargon ", "", "ignored"];
+        assert_eq!(
+            criteria_from_lines(lines),
+            vec!["use present tense, active voice", "<= 80 words; no jargon"]
+        );
+        assert_eq!(
+            split_criteria("use present tense, active voice"),
+            vec!["use present tense", "active voice"]
+        );
+    }
+}
@@ -1,26 +1,79 @@
 #![allow(dead_code)]
 
 use anyhow::{Error, Result};
+use futures::StreamExt;
 use tokio::select;
 use std::path::Path;
+use std::sync::Arc;
 use colored::*;
 
 use crate::deepseek::{DeepSeekClient, DeepSeekError, DeepSeekResponse};
-use crate::agents::{Agent, ProducerAgent};
+use crate::agents::{Agent, AgentEvent, ProducerAgent};
+use crate::orchestrator::SOLUTION_KEY;
+use crate::storage::FsStore;
 use crate::types::{TaskSpec, DeliverableType, SolutionV1, ValidationV1};
 
 mod input;
 mod render;
 
+/// When to emit ANSI colors in console output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Let `colored` decide from the environment (NO_COLOR, CLICOLOR, tty)
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Apply this choice process-wide
+    pub fn apply(self) {
+        match self {
+            ColorChoice::Auto => colored::control::unset_override(),
+            ColorChoice::Always => colored::control::set_override(true),
+            ColorChoice::Never => colored::control::set_override(false),
+        }
+    }
+}
+
 /// Console interface for the DeepSeek application
 pub struct Console {
     client: DeepSeekClient,
+    quiet: bool,
+    require_existing_out_dir: bool,
 }
 
 impl Console {
     /// Create a new console interface with the provided DeepSeek client
     pub fn new(client: DeepSeekClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            quiet: false,
+            require_existing_out_dir: false,
+        }
+    }
+
+    /// Suppress the decorative status lines (📨/🛠️/💾) when enabled
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Fail instead of creating the output directory when it does not exist
+    pub fn with_require_existing_out_dir(mut self, required: bool) -> Self {
+        self.require_existing_out_dir = required;
+        self
+    }
+
+    /// Print a decorative status line unless quiet
+    fn status(&self, text: &str) {
+        self.write_status(&mut std::io::stdout().lock(), text);
+    }
+
+    fn write_status(&self, out: &mut impl std::io::Write, text: &str) {
+        if !self.quiet {
+            let _ = writeln!(out, "{}", text.bright_white());
+        }
     }
 
     /// Display a welcome banner
@@ -34,6 +87,11 @@ impl Console {
         input::get_user_input().await
     }
 
+    /// Prompt with a custom message and return the entered line (trimmed)
+    pub async fn prompt(prompt_text: &str) -> Result<String> {
+        input::prompt_user(prompt_text).await
+    }
+
     /// Check if the input is a quit command
     #[allow(dead_code)]
     pub fn is_quit_command(input_text: &str) -> bool {
@@ -57,6 +115,11 @@ impl Console {
         render::display_response(response);
     }
 
+    /// Wrap long deliverable lines to the terminal width (the default) or print them as-is
+    pub fn set_line_wrapping(enabled: bool) {
+        render::set_line_wrapping(enabled);
+    }
+
     /// Display a pretty-printed SolutionV1 artifact
     pub fn display_solution(solution: &SolutionV1) {
         render::display_solution(solution);
@@ -67,6 +130,21 @@ impl Console {
         render::display_validation(validation);
     }
 
+    /// Display a side-by-side comparison of two ValidationV1 artifacts
+    pub fn display_validation_comparison(
+        left_label: &str,
+        left: &ValidationV1,
+        right_label: &str,
+        right: &ValidationV1,
+    ) {
+        render::display_validation_comparison(left_label, left, right_label, right);
+    }
+
+    /// Display the colored end-of-run verdict banner
+    pub fn display_verdict_banner(validation: &ValidationV1) {
+        render::display_verdict_banner(validation);
+    }
+
     /// Display an error message with context-aware messaging
     pub fn display_error(error: &Error) {
         render::display_error(error);
@@ -102,7 +180,7 @@ impl Console {
                 }
                 // Handle user input
                 input_result = Self::get_user_input() => {
-                    println!("{}", "📨 Received input from user".bright_white());
+                    self.status("📨 Received input from user");
                     let input = match input_result {
                         Ok(input) => input,
                         Err(e) => {
@@ -123,21 +201,24 @@ impl Console {
                     Self::display_loading();
 
                     // Allow request to be cancelled by Ctrl+C
-                    select! {
-                        _ = tokio::signal::ctrl_c() => {
-                            println!("\n⚠️ Request cancelled by user");
+                    let cancel = async {
+                        let _ = tokio::signal::ctrl_c().await;
+                    };
+                    match self.client.send_request_cancellable(&input, cancel).await {
+                        Ok(response) => {
+                            self.status("🛠️ Processing input with agent");
+                            self.status("💾 Processed. Displaying result");
+                            Self::display_response(&response)
+                        }
+                        Err(e) if e.is_cancelled() => {
+                            println!();
+                            Self::display_deepseek_error(&e);
                             Self::display_goodbye();
                             break;
                         }
-                        result = self.client.send_request(&input) => {
-                            println!("{}", "🛠️ Processing input with agent".bright_white());
-                            match result {
-                                Ok(response) => {
-                                    println!("{}", "💾 Processed. Displaying result".bright_white());
-                                    Self::display_response(&response)
-                                },
-                                Err(e) => Self::display_deepseek_error(&e),
-                            }
+                        Err(e) => {
+                            self.status("🛠️ Processing input with agent");
+                            Self::display_deepseek_error(&e);
                         }
                     }
                 }
@@ -153,15 +234,15 @@ impl Console {
         let input_text = input::prompt_user("📥 Input/context: ").await?;
 
         let ac_raw = input::prompt_user(
-            "✅ Acceptance criteria (comma or semicolon separated): ",
+            "✅ Acceptance criteria (comma or semicolon separated; Enter alone to type one per line): ",
         )
         .await?;
-        let acceptance_criteria: Vec<String> = ac_raw
-            .split([',', ';', '\n'])
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-            .collect();
+        let acceptance_criteria = if ac_raw.is_empty() {
+            println!("{}", "One criterion per line; finish with an empty line.".blue());
+            input::prompt_criteria_lines().await?
+        } else {
+            input::split_criteria(&ac_raw)
+        };
 
         println!(
             "{}",
@@ -185,14 +266,21 @@ impl Console {
         let hints = input::prompt_user("💡 Hints (optional, Enter to skip): ").await?;
         let hints = if hints.trim().is_empty() { None } else { Some(hints) };
 
-        let task_spec = TaskSpec {
+        let mut task_spec = TaskSpec {
             task_id: uuid::Uuid::new_v4().to_string(),
             goal,
             input: input_text,
             acceptance_criteria,
             deliverable_type,
             hints,
+            json_schema: None,
+            deadline_ms: None,
+            scoring_guidance: None,
         };
+        let duplicates = task_spec.dedupe_criteria();
+        if duplicates > 0 {
+            tracing::warn!("Removed {} duplicate acceptance criteria", duplicates);
+        }
 
         // Show the JSON that will be sent to the agent
         let pretty = serde_json::to_string_pretty(&task_spec)?;
@@ -210,34 +298,46 @@ impl Console {
         );
 
         let task_spec = self.collect_task_spec().await?;
-        println!("{}", "📨 Received task specification from user".bright_white());
+        self.status("📨 Received task specification from user");
         // Pretty print the received task spec before processing
         Self::display_task(&task_spec);
 
-        tokio::fs::create_dir_all(out_dir).await?;
-        let out_path = out_dir.join("solution.json");
-
-        let agent = ProducerAgent::new(self.client.clone(), out_path.clone());
-        println!("{}", "🛠️ ProducerAgent is processing the task".bright_white());
-        match agent.execute(&task_spec).await {
-            Ok(solution) => {
-                println!(
-                    "{} {}\n  {}",
-                    "✅ ProducerAgent completed.".bright_green().bold(),
-                    format!("solution_id={}", solution.solution_id).bright_white(),
-                    out_path.display()
-                );
-                println!(
-                    "{} {}",
-                    "💾 Saved result to".bright_white(),
-                    out_path.display()
-                );
-                // Pretty-print the solution in console
-                Self::display_solution(&solution);
-            }
-            Err(e) => {
-                let err: Error = anyhow::anyhow!(e);
-                Self::display_error(&err);
+        if !self.require_existing_out_dir {
+            tokio::fs::create_dir_all(out_dir).await?;
+        }
+        let out_path = out_dir.join(SOLUTION_KEY);
+
+        let agent = ProducerAgent::new(
+            self.client.clone(),
+            Arc::new(
+                FsStore::new(out_dir)
+                    .with_require_existing_root(self.require_existing_out_dir)
+                    .with_mode(self.client.config().artifact_mode),
+            ),
+            SOLUTION_KEY,
+        );
+        self.status("🛠️ ProducerAgent is processing the task");
+        let mut events = agent.execute_with_events(&task_spec);
+        while let Some(event) = events.next().await {
+            match event {
+                AgentEvent::SendingRequest => self.status("📤 Sending request to the model"),
+                AgentEvent::ReceivedResponse => self.status("📥 Received model response"),
+                AgentEvent::Saved { location, output: solution } => {
+                    println!(
+                        "{} {}\n  {}",
+                        "✅ ProducerAgent completed.".bright_green().bold(),
+                        format!("solution_id={}", solution.solution_id).bright_white(),
+                        out_path.display()
+                    );
+                    self.status(&format!("💾 Saved result to {}", location));
+                    // Pretty-print the solution in console
+                    Self::display_solution(&solution);
+                }
+                AgentEvent::Failed(e) => {
+                    let err: Error = anyhow::anyhow!(e);
+                    Self::display_error(&err);
+                }
+                AgentEvent::PreparingOutput | AgentEvent::Parsing => {}
             }
         }
 
@@ -245,3 +345,34 @@ impl Console {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn console() -> Console {
+        let cfg = Config {
+            api_key: "k".to_string(),
+            ..Config::default()
+        };
+        Console::new(DeepSeekClient::new(cfg).unwrap())
+    }
+
+    #[test]
+    fn status_lines_are_printed_by_default() {
+        colored::control::set_override(false);
+        let mut out = Vec::new();
+        console().write_status(&mut out, "🛠️ ProducerAgent is processing the task");
+        assert_eq!(String::from_utf8(out).unwrap(), "🛠️ ProducerAgent is processing the task\n");
+    }
+
+    #[test]
+    fn status_lines_are_suppressed_when_quiet() {
+        let mut out = Vec::new();
+        console()
+            .with_quiet(true)
+            .write_status(&mut out, "📨 Received task specification from user");
+        assert!(out.is_empty());
+    }
+}
@@ -1,8 +1,70 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use anyhow::Error;
 use colored::*;
 
+use crate::compare::{CriterionChange, ValidationComparison};
 use crate::deepseek::{DeepSeekError, DeepSeekResponse};
-use crate::types::{DeliverableType, SolutionV1, ValidationV1, Verdict, TaskSpec};
+use crate::types::{DeliverableType, Severity, SolutionV1, ValidationV1, Verdict, TaskSpec};
+
+/// Width assumed when the terminal's is unknown (stdout is not a tty and `COLUMNS` is unset)
+const DEFAULT_TERMINAL_WIDTH: usize = 100;
+/// Prefix of a deliverable line inside a box, and of its wrapped continuations
+const LINE_PREFIX: &str = "│   ";
+const CONTINUATION_PREFIX: &str = "│     ";
+
+static WRAP_LINES: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable wrapping long deliverable lines to the terminal width (on by default)
+pub fn set_line_wrapping(enabled: bool) {
+    WRAP_LINES.store(enabled, Ordering::Relaxed);
+}
+
+/// Terminal width from an exported `COLUMNS`, else the width of the tty on stdout, else
+/// `DEFAULT_TERMINAL_WIDTH`. Shells set `COLUMNS` without exporting it, so the tty is
+/// usually what is read.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|&columns: &usize| columns > 0)
+        .or_else(|| {
+            terminal_size::terminal_size_of(std::io::stdout())
+                .map(|(terminal_size::Width(columns), _)| usize::from(columns))
+        })
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+/// `line` split into pieces of at most `width` characters, breaking after the last space
+/// within the limit when there is one and mid-word otherwise. Spaces at a break are dropped.
+pub fn wrap_line(line: &str, width: usize) -> Vec<&str> {
+    let width = width.max(1);
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while let Some((limit, _)) = rest.char_indices().nth(width) {
+        let cut = rest[..limit]
+            .rfind(' ')
+            .filter(|&space| !rest[..space].trim().is_empty())
+            .unwrap_or(limit);
+        pieces.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start_matches(' ');
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// Print one deliverable line inside a box, wrapping it with indented continuations
+fn print_boxed_line(line: &str) {
+    if !WRAP_LINES.load(Ordering::Relaxed) {
+        println!("{}{}", LINE_PREFIX, line.white());
+        return;
+    }
+    let width = terminal_width().saturating_sub(CONTINUATION_PREFIX.chars().count());
+    for (i, piece) in wrap_line(line, width).into_iter().enumerate() {
+        let prefix = if i == 0 { LINE_PREFIX } else { CONTINUATION_PREFIX };
+        println!("{}{}", prefix, piece.white());
+    }
+}
 
 pub fn display_welcome() {
     println!(
@@ -140,6 +202,14 @@ pub fn display_deepseek_error(error: &DeepSeekError) {
                 "💡 Tip: Check your environment variables and configuration.".red()
             );
         }
+        DeepSeekError::ContentFiltered => {
+            println!("{}", user_message.bright_yellow().bold());
+            println!(
+                "{}",
+                "💡 Tip: Retrying the same task will be blocked again; rephrase the goal or remove sensitive material from the input.".yellow()
+            );
+        }
+        DeepSeekError::Cancelled => println!("{}", user_message.yellow()),
     }
     println!();
 }
@@ -217,7 +287,7 @@ pub fn display_solution(solution: &SolutionV1) {
             if let Some(text) = &solution.deliverable.text {
                 println!("{}", "│ ── Text:".cyan());
                 for line in text.lines() {
-                    println!("│   {}", line.white());
+                    print_boxed_line(line);
                 }
             }
         }
@@ -226,7 +296,7 @@ pub fn display_solution(solution: &SolutionV1) {
                 let pretty = serde_json::to_string_pretty(json).unwrap_or_else(|_| json.to_string());
                 println!("{}", "│ ── JSON:".cyan());
                 for line in pretty.lines() {
-                    println!("│   {}", line.white());
+                    print_boxed_line(line);
                 }
             }
         }
@@ -239,7 +309,7 @@ pub fn display_solution(solution: &SolutionV1) {
                 );
                 println!("{}", "│ ── Content:".cyan());
                 for line in code.content.lines() {
-                    println!("│   {}", line.white());
+                    print_boxed_line(line);
                 }
             }
         }
@@ -293,7 +363,20 @@ pub fn display_validation(validation: &ValidationV1) {
                 chk.criterion.bright_white()
             );
             println!("│     {} {}", "reason:".white(), chk.reason.white());
-            println!("│     {} {}", "severity:".white(), format!("{:?}", chk.severity).white());
+            let severity = format!("{:?}", chk.severity);
+            let severity = match chk.severity {
+                Severity::Minor => severity.white(),
+                Severity::Major => severity.bright_yellow(),
+                Severity::Critical => format!("🛑 {}", severity).bright_red().bold(),
+            };
+            println!("│     {} {}", "severity:".white(), severity);
+            if let Some(partial) = chk.partial_score {
+                println!("│     {} {:.2}", "partial_score:".white(), partial);
+            }
+            if let Some(lines) = chk.evidence_lines.as_ref().filter(|l| !l.is_empty()) {
+                let lines: Vec<String> = lines.iter().map(u32::to_string).collect();
+                println!("│     {} {}", "lines:".white(), lines.join(", ").white());
+            }
             if let Some(suggest) = &chk.suggested_fix {
                 println!("│     {} {}", "suggested_fix:".white(), suggest.white());
             }
@@ -310,3 +393,184 @@ pub fn display_validation(validation: &ValidationV1) {
 
     println!("{}", "└─────────────────────────────────────────────────────────────\n".magenta());
 }
+
+/// Build the colored end-of-run banner: verdict, score and failed-check count
+pub fn verdict_banner(validation: &ValidationV1) -> String {
+    let failed = validation.checks.iter().filter(|c| !c.pass_).count();
+    let (icon, label) = match validation.verdict {
+        Verdict::Pass => ("✅", "PASS"),
+        Verdict::Warn => ("⚠️", "WARN"),
+        Verdict::Fail => ("❌", "FAIL"),
+    };
+    let border = "═".repeat(61);
+    let line = format!(
+        "  {} {}   score {:.2}   failed checks: {}/{}",
+        icon,
+        label,
+        validation.score,
+        failed,
+        validation.checks.len()
+    );
+    let (border, line) = match validation.verdict {
+        Verdict::Pass => (border.bright_green(), line.bright_green().bold()),
+        Verdict::Warn => (border.bright_yellow(), line.bright_yellow().bold()),
+        Verdict::Fail => (border.bright_red(), line.bright_red().bold()),
+    };
+    format!("{}\n{}\n{}", border, line, border)
+}
+
+pub fn display_verdict_banner(validation: &ValidationV1) {
+    println!("\n{}\n", verdict_banner(validation));
+}
+
+fn verdict_colored(verdict: &Verdict) -> ColoredString {
+    let s = verdict.to_string();
+    match verdict {
+        Verdict::Pass => s.bright_green().bold(),
+        Verdict::Warn => s.bright_yellow().bold(),
+        Verdict::Fail => s.bright_red().bold(),
+    }
+}
+
+fn pass_mark(pass: Option<bool>) -> ColoredString {
+    match pass {
+        Some(true) => "✔".bright_green(),
+        Some(false) => "✖".bright_red(),
+        None => "–".white(),
+    }
+}
+
+pub fn display_validation_comparison(
+    left_label: &str,
+    left: &ValidationV1,
+    right_label: &str,
+    right: &ValidationV1,
+) {
+    let cmp = ValidationComparison::new(left, right);
+    println!("\n{}", "🔀 Validation Comparison".bright_blue().bold());
+    println!("{}", "┌─────────────────────────────────────────────────────────────".blue());
+    println!("{} {}", "│ A:".blue(), left_label.white());
+    println!("{} {}", "│ B:".blue(), right_label.white());
+    println!(
+        "{} {} → {}",
+        "│ ⚖️  Verdict:".blue(),
+        verdict_colored(&cmp.verdicts.0),
+        verdict_colored(&cmp.verdicts.1)
+    );
+    let delta = cmp.scores.1 - cmp.scores.0;
+    let delta_str = format!("{:+.2}", delta);
+    let delta_colored = if delta < 0.0 {
+        delta_str.bright_red()
+    } else if delta > 0.0 {
+        delta_str.bright_green()
+    } else {
+        delta_str.white()
+    };
+    println!(
+        "{} {:.2} → {:.2} ({})",
+        "│ 📈 Score:".blue(),
+        cmp.scores.0,
+        cmp.scores.1,
+        delta_colored
+    );
+
+    if !cmp.criteria.is_empty() {
+        println!("{}", "│ ── Criteria (A → B):".blue());
+        for d in &cmp.criteria {
+            let label = match d.change {
+                CriterionChange::Unchanged => "".normal(),
+                CriterionChange::Fixed => "fixed".bright_green(),
+                CriterionChange::Regressed => "REGRESSED".bright_red().bold(),
+                CriterionChange::Added => "added".cyan(),
+                CriterionChange::Removed => "removed".cyan(),
+            };
+            let criterion = if d.change == CriterionChange::Regressed {
+                d.criterion.bright_red()
+            } else {
+                d.criterion.white()
+            };
+            println!(
+                "│   {} → {}  {} {}",
+                pass_mark(d.before),
+                pass_mark(d.after),
+                criterion,
+                label
+            );
+        }
+    }
+
+    let regressions = cmp.regressions().count();
+    if regressions > 0 {
+        println!(
+            "{} {}",
+            "│ ⚠️  Regressions:".bright_red().bold(),
+            regressions.to_string().bright_red().bold()
+        );
+    }
+    println!("{}", "└─────────────────────────────────────────────────────────────\n".blue());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CheckResult, ModelUsed};
+
+    fn validation(verdict: Verdict, score: f32, passes: &[bool]) -> ValidationV1 {
+        ValidationV1 {
+            schema_version: "validation_v1".to_string(),
+            task_id: "t".to_string(),
+            solution_id: "s".to_string(),
+            verdict,
+            score,
+            checks: passes
+                .iter()
+                .enumerate()
+                .map(|(i, pass)| CheckResult {
+                    criterion: format!("criterion {}", i),
+                    pass_: *pass,
+                    reason: String::new(),
+                    severity: Severity::Minor,
+                    suggested_fix: None,
+                    partial_score: None,
+                    unmatched: false,
+                    evidence_lines: None,
+                })
+                .collect(),
+            suggested_rewrite: None,
+            model_used: ModelUsed { name: "deepseek-reasoner".to_string(), temperature: 0.0 },
+            created_at: String::new(),
+            reasoning: None,
+        }
+    }
+
+    #[test]
+    fn long_lines_wrap_at_spaces_or_mid_word_and_short_lines_are_kept() {
+        assert_eq!(
+            wrap_line("use present tense and active voice", 12),
+            vec!["use present", "tense and", "active voice"]
+        );
+        let minified = "{\"a\":1,\"b\":2,\"c\":3}";
+        let pieces = wrap_line(minified, 8);
+        assert_eq!(pieces, vec!["{\"a\":1,\"", "b\":2,\"c\"", ":3}"]);
+        assert_eq!(pieces.concat(), minified);
+        assert_eq!(wrap_line("    let x = 1;", 20), vec!["    let x = 1;"]);
+        assert_eq!(wrap_line("    aaaaaaaa", 6), vec!["    aa", "aaaaaa"]);
+        assert_eq!(wrap_line("", 10), vec![""]);
+    }
+
+    #[test]
+    fn banner_reports_verdict_score_and_failed_checks() {
+        colored::control::set_override(false);
+        let cases = [
+            (validation(Verdict::Pass, 0.95, &[true, true]), "PASS", "score 0.95", "failed checks: 0/2"),
+            (validation(Verdict::Warn, 0.6, &[true, false]), "WARN", "score 0.60", "failed checks: 1/2"),
+            (validation(Verdict::Fail, 0.1, &[false, false, true]), "FAIL", "score 0.10", "failed checks: 2/3"),
+        ];
+        for (v, label, score, failed) in cases {
+            let banner = verdict_banner(&v);
+            assert!(banner.contains(label), "missing {} in {}", label, banner);
+            assert!(banner.contains(score), "missing {} in {}", score, banner);
+            assert!(banner.contains(failed), "missing {} in {}", failed, banner);
+        }
+    }
+}
@@ -1,13 +1,35 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::time::Duration;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
 use chrono::Utc;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::stream::{self, BoxStream, StreamExt};
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use crate::config::Config;
+use crate::rate_limit::{Cooldown, RateLimiter, RetryBudget};
+use crate::stream::SseParser;
+
+/// Attempts per logical request when retrying busy/network failures
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubled for each further one
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Reasoning model, which may be exempted from `response_format` (`json_format_for_reasoner`)
+const REASONER_MODEL: &str = "deepseek-reasoner";
+/// Back-off applied to all requests after a busy/rate-limit signal without `Retry-After`
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(1);
+/// Header carrying `Config::organization` (the OpenAI-compatible name)
+const ORGANIZATION_HEADER: &str = "OpenAI-Organization";
+/// Header carrying `Config::project` (the OpenAI-compatible name)
+const PROJECT_HEADER: &str = "OpenAI-Project";
 
 #[cfg(feature = "deepseek_api")]
 use deepseek_api::{
@@ -17,8 +39,22 @@ use deepseek_api::{
     DeepSeekClient as ExtDeepSeekClient, DeepSeekClientBuilder as ExtDeepSeekClientBuilder,
 };
 
+/// Default system prompt of the interactive chat (`Console::run`)
+const DEFAULT_CHAT_SYSTEM_PROMPT: &str =
+    "You are a helpful assistant that always responds with valid JSON in the specified format.";
+
+/// Default JSON schema requested by the interactive chat; `{timestamp}` is replaced per request
+const DEFAULT_CHAT_JSON_SCHEMA: &str = r#"{
+                "title": "A concise title for the topic (string)",
+                "description": "A brief description or summary (string)",
+                "content": "The main content or detailed response (string)",
+                "category": "Optional category classification (string or null)",
+                "timestamp": "Current response timestamp: {timestamp} (string)",
+                "confidence": "Optional confidence score between 0.0 and 1.0 (number or null)"
+                }"#;
+
 /// Custom error types for DeepSeek API interactions
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum DeepSeekError {
     #[error("DeepSeek servers are currently busy. Please try again in a few moments.")]
     ServerBusy,
@@ -37,6 +73,12 @@ pub enum DeepSeekError {
 
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
+
+    #[error("Response blocked by the provider's content filter")]
+    ContentFiltered,
+
+    #[error("Request cancelled")]
+    Cancelled,
 }
 
 impl DeepSeekError {
@@ -46,6 +88,22 @@ impl DeepSeekError {
         matches!(self, DeepSeekError::ServerBusy)
     }
 
+    /// Check if the response was blocked by content filtering (never worth retrying)
+    #[allow(dead_code)]
+    pub fn is_content_filtered(&self) -> bool {
+        matches!(self, DeepSeekError::ContentFiltered)
+    }
+
+    /// Check if the request was cancelled by the caller (never worth retrying)
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, DeepSeekError::Cancelled)
+    }
+
+    /// Check if retrying the request may succeed: a busy server or a network failure
+    pub fn is_retryable(&self) -> bool {
+        self.is_server_busy() || self.is_network_error()
+    }
+
     /// Check if the error is a network-related issue
     #[allow(dead_code)]
     pub fn is_network_error(&self) -> bool {
@@ -63,7 +121,7 @@ impl DeepSeekError {
                 "🌐 Network connection failed. Please check your internet connection and try again."
                     .to_string()
             }
-            DeepSeekError::Timeout { seconds } => {
+            DeepSeekError::Timeout { seconds, .. } => {
                 format!(
                     "⏰ Request timed out after {} seconds. The server might be overloaded.",
                     seconds
@@ -85,6 +143,10 @@ impl DeepSeekError {
             DeepSeekError::ConfigError { message } => {
                 format!("⚙️ Configuration error: {}", message)
             }
+            DeepSeekError::ContentFiltered => {
+                "🛑 The response was blocked by the provider's content filter.".to_string()
+            }
+            DeepSeekError::Cancelled => "⏹️ The request was cancelled.".to_string(),
         }
     }
 }
@@ -101,22 +163,181 @@ pub struct DeepSeekResponse {
     pub confidence: Option<f32>,
 }
 
+/// Author of a chat message, serialized as the API's role string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    /// Result of a tool call, answering the assistant message that requested it
+    Tool,
+}
+
 /// API request/response structures
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct ChatMessage {
-    pub role: String,
+    pub role: Role,
     pub content: String,
+    /// Id of the tool call a `Role::Tool` message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Participant that wrote the message (e.g. `producer` or `auditor` in a multi-agent
+    /// conversation), or the tool that produced a `Role::Tool` message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Tool calls requested by an assistant message, replayed so tool results can answer them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+}
+
+impl ChatMessage {
+    fn new(role: Role, content: impl Into<String>) -> Self {
+        Self { role, content: content.into(), tool_call_id: None, name: None, tool_calls: None }
+    }
+
+    /// System message
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    /// User message
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    /// Assistant message, e.g. a previous answer replayed as context
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    /// Tool message carrying the result of the tool call `tool_call_id`
+    #[allow(dead_code)]
+    pub fn tool(
+        content: impl Into<String>,
+        tool_call_id: impl Into<String>,
+        name: Option<String>,
+    ) -> Self {
+        Self { tool_call_id: Some(tool_call_id.into()), name, ..Self::new(Role::Tool, content) }
+    }
+
+    /// Attribute this message to the participant `name`
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Assistant message that requested `tool_calls`, to precede their `tool` results
+    #[allow(dead_code)]
+    pub fn assistant_tool_calls(tool_calls: Vec<ToolCall>) -> Self {
+        Self { tool_calls: Some(tool_calls), ..Self::new(Role::Assistant, "") }
+    }
+}
+
+/// A function the model may call: its name, what it does and a JSON Schema of its arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDef {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDef {
+    #[allow(dead_code)]
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self { name: name.into(), description: description.into(), parameters }
+    }
+}
+
+/// Wire form of a `ToolDef`: `{"type": "function", "function": {...}}`
+#[derive(Debug, Serialize)]
+struct RequestTool {
+    #[serde(rename = "type")]
+    tool_type: &'static str,
+    function: ToolDef,
+}
+
+/// Whether and which tool the model must call
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides between answering and calling tools (the API default)
+    Auto,
+    /// Never call a tool
+    None,
+    /// Call at least one tool
+    Required,
+    /// Call the named function
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => serde_json::json!({
+                "type": "function",
+                "function": { "name": name }
+            })
+            .serialize(serializer),
+        }
+    }
+}
+
+/// A function call requested by the model
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "function_tool_type")]
+    pub call_type: String,
+    pub function: FunctionCall,
+}
+
+fn function_tool_type() -> String {
+    "function".to_string()
+}
+
+/// Function name and its arguments as the JSON text generated by the model
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+/// Answer to a request offering tools: final content, or the tool calls to run first
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolResponse {
+    Content(String),
+    ToolCalls(Vec<ToolCall>),
 }
 
 #[derive(Debug, Serialize)]
 struct ChatRequest {
     model: String,
     messages: Vec<ChatMessage>,
-    response_format: ResponseFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
     max_tokens: u32,
     temperature: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     stop: Option<Vec<String>>,
+    /// Number of choices to generate; one when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<RequestTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ToolChoice>,
+    /// Ask for Server-Sent Events instead of one JSON body
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -125,21 +346,165 @@ struct ResponseFormat {
     format_type: String,
 }
 
+/// Per-call overrides of the request parameters (`send_messages_raw_with`), for tuning
+/// individual requests without building another client. `None` keeps the configured value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestOptions {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// `Some(true)` always sends `response_format: json_object` and `Some(false)` never does;
+    /// `None` sends it while the backend supports it
+    pub response_format: Option<bool>,
+    /// Sequences at which the model stops generating
+    pub stop: Option<Vec<String>>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiResponse {
     choices: Vec<Choice>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct Choice {
-    message: ChatMessage,
+    /// Chat-style output; absent from completion-style endpoints
+    #[serde(default)]
+    message: ResponseMessage,
+    /// Completion-style output of endpoints without `message`
+    #[serde(default)]
+    text: Option<String>,
+    /// Streaming-style output some endpoints return even for non-streamed requests
+    #[serde(default)]
+    delta: Option<StreamDelta>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+impl Choice {
+    /// The choice's output from `message.content`, else `text`, else `delta.content`
+    fn into_completion(self) -> Result<RawCompletion, DeepSeekError> {
+        let text = self.text.or_else(|| self.delta.and_then(|delta| delta.content));
+        RawCompletion::from_choice_parts(
+            self.message.content.as_deref(),
+            self.message.reasoning_content.as_deref(),
+            text.as_deref(),
+        )
+    }
 }
 
+/// One Server-Sent Event of a streamed completion
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    choices: Vec<StreamChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Terminal payload of an SSE completion stream
+const STREAM_DONE: &str = "[DONE]";
+
+/// `finish_reason` of a response blocked by content filtering
+const CONTENT_FILTER_FINISH_REASON: &str = "content_filter";
+
+/// Assistant message of a completion; `deepseek-reasoner` also returns its chain of thought
+#[derive(Debug, Default, Deserialize)]
+struct ResponseMessage {
+    /// Usually present; may be null or empty when the response was filtered
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    /// Function calls the model asks for instead of (or besides) answering
+    #[serde(default)]
+    tool_calls: Option<Vec<ToolCall>>,
+}
+
+/// Assistant output of one completion request
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCompletion {
+    /// The assistant `content` (the JSON answer)
+    pub content: String,
+    /// `reasoning_content` returned separately by `deepseek-reasoner`, if any
+    pub reasoning: Option<String>,
+}
+
+impl RawCompletion {
+    /// Build from the fields of a response choice: `message.content`, `message.reasoning_content`
+    /// and the legacy `text`, which is used when the message has no (or empty) content
+    fn from_choice_parts(
+        content: Option<&str>,
+        reasoning: Option<&str>,
+        text: Option<&str>,
+    ) -> Result<Self, DeepSeekError> {
+        let content = content
+            .filter(|c| !c.is_empty())
+            .or(text)
+            .or(content)
+            .ok_or_else(|| DeepSeekError::ParseError {
+            message: "Empty content in API response".to_string(),
+        })?;
+        Ok(Self {
+            content: content.to_string(),
+            reasoning: reasoning.filter(|r| !r.is_empty()).map(str::to_string),
+        })
+    }
+}
+
+/// Which implementation sends completion requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// This crate's reqwest-based client
+    Internal,
+    /// The `deepseek-api` crate (feature `deepseek_api`, official host only)
+    #[cfg_attr(not(feature = "deepseek_api"), allow(dead_code))]
+    External,
+}
+
+/// Shared handle on an in-flight completion request
+type InFlight = Shared<BoxFuture<'static, Result<RawCompletion, DeepSeekError>>>;
+
 /// DeepSeek API client
 #[derive(Clone)]
 pub struct DeepSeekClient {
-    client: Client,
+    /// Connection pool; shared with clients derived by `clone_with_model`
+    client: Arc<Client>,
     config: Config,
+    /// Shared by clones so that all of them respect the same budget
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Caps requests in flight (`max_concurrent_requests`); shared with clones and with
+    /// clients derived by `clone_with_model`
+    request_slots: Option<Arc<Semaphore>>,
+    /// Set when the server reports overload; shared by clones
+    cooldown: Arc<Cooldown>,
+    /// Requests currently on the wire, keyed by `request_hash`; identical concurrent
+    /// requests await the same call instead of sending their own
+    in_flight: Arc<Mutex<HashMap<u64, InFlight>>>,
+    /// Whether to send `response_format`; starts from the config and is cleared (for all
+    /// clones) once the backend rejects the field
+    response_format_supported: Arc<AtomicBool>,
+    /// Run-wide cap on retries; when set, agent requests also retry transient failures
+    retry_budget: Option<Arc<RetryBudget>>,
     #[cfg(feature = "deepseek_api")]
     ext_client: Option<ExtDeepSeekClient>,
 }
@@ -163,16 +528,47 @@ impl DeepSeekClient {
 
         let client = Client::builder()
             .timeout(Duration::from_secs(config.timeout))
-            .user_agent("deepseek_json/0.1.0")
+            .user_agent(config.user_agent())
             .build()
             .map_err(|e| DeepSeekError::ConfigError {
                 message: format!("Failed to create HTTP client: {}", e),
             })?;
 
+        Ok(Self::with_http_client(config, Arc::new(client)))
+    }
+
+    /// Client for the same API as this one but `model`. Requests go out under the same API
+    /// key, so it shares this client's HTTP connection pool, retry budget, concurrency limit,
+    /// rate limiter and 429 cooldown. Only `response_format` support is derived afresh.
+    pub fn clone_with_model(&self, model: &str) -> Result<Self, DeepSeekError> {
+        let config = Config { model: model.to_string(), ..self.config.clone() };
+        config.validate().map_err(|e| DeepSeekError::ConfigError {
+            message: e.to_string(),
+        })?;
+        let mut derived = Self::with_http_client(config, self.client.clone());
+        derived.retry_budget = self.retry_budget.clone();
+        derived.request_slots = self.request_slots.clone();
+        derived.rate_limiter = self.rate_limiter.clone();
+        derived.cooldown = self.cooldown.clone();
+        Ok(derived)
+    }
+
+    /// Sample at `temperature` instead of the configured one
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.config.temperature = temperature;
+        self
+    }
+
+    /// Client for a validated `config` sending requests through `client`
+    fn with_http_client(config: Config, client: Arc<Client>) -> Self {
         #[cfg(feature = "deepseek_api")]
         let ext_client = {
-            // Use the external client when the base_url targets the official DeepSeek API host.
-            if is_official_deepseek_host(config.base_url.as_str()) {
+            // Use the external client when the base_url targets the official DeepSeek API host,
+            // unless the internal path is forced for debugging.
+            if config.force_internal {
+                tracing::info!("Internal HTTP client forced; not using deepseek-api");
+                None
+            } else if is_official_deepseek_host(config.base_url.as_str()) {
                 match ExtDeepSeekClientBuilder::new(config.api_key.clone())
                     .timeout(config.timeout)
                     .build()
@@ -191,27 +587,143 @@ impl DeepSeekClient {
         #[cfg(not(feature = "deepseek_api"))]
         let _ext_client: Option<()> = None;
 
-        Ok(Self {
+        let rate_limiter = config
+            .requests_per_minute
+            .map(|rpm| Arc::new(RateLimiter::per_minute(rpm)));
+        let request_slots = config
+            .max_concurrent_requests
+            .map(|limit| Arc::new(Semaphore::new(limit)));
+
+        // The reasoner may do better when free to reason before the JSON; `extract_json` copes
+        let reasoner_opted_out =
+            config.model == REASONER_MODEL && !config.json_format_for_reasoner;
+        if reasoner_opted_out {
+            tracing::debug!("Omitting response_format for {}", REASONER_MODEL);
+        }
+        let response_format_supported = Arc::new(AtomicBool::new(
+            config.response_format_supported && !reasoner_opted_out,
+        ));
+
+        Self {
             client,
             config,
+            rate_limiter,
+            request_slots,
+            cooldown: Arc::new(Cooldown::new()),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            response_format_supported,
+            retry_budget: None,
             #[cfg(feature = "deepseek_api")]
             ext_client,
-        })
+        }
     }
-    /// Send a request to the DeepSeek API with retry logic
+
+    /// Draw retries from `budget`, shared with the other clients of the run. Agent requests
+    /// (`send_messages_raw`) retry transient failures only while a budget is attached.
+    pub fn with_retry_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// Wait for a free request slot when `max_concurrent_requests` is set; the request counts
+    /// as in flight until the returned permit is dropped
+    async fn acquire_request_slot(&self) -> Option<OwnedSemaphorePermit> {
+        // The semaphore is never closed, so acquiring only fails when there is no limit
+        self.request_slots.clone()?.acquire_owned().await.ok()
+    }
+
+    /// Backend that sends this client's completion requests
+    pub fn backend_kind(&self) -> BackendKind {
+        #[cfg(feature = "deepseek_api")]
+        if self.ext_client.is_some() {
+            return BackendKind::External;
+        }
+        BackendKind::Internal
+    }
+
+    /// Model this client sends requests to
+    #[allow(dead_code)]
+    pub fn model_name(&self) -> &str {
+        &self.config.model
+    }
+
+    /// Sampling temperature sent with each request
+    #[allow(dead_code)]
+    pub fn temperature(&self) -> f32 {
+        self.config.temperature
+    }
+
+    /// Configuration this client was built with
+    #[allow(dead_code)]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Send a request to the DeepSeek API with retry logic, using the configured
+    /// (or default) chat system prompt
     #[allow(dead_code)]
     pub async fn send_request(&self, user_input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
+        let system_prompt = self
+            .config
+            .chat_system_prompt
+            .as_deref()
+            .unwrap_or(DEFAULT_CHAT_SYSTEM_PROMPT);
+        self.send_request_with_prompt(user_input, system_prompt).await
+    }
+
+    /// Send a request with a custom system prompt, with retry logic
+    #[allow(dead_code)]
+    pub async fn send_request_with_prompt(
+        &self,
+        user_input: &str,
+        system_prompt: &str,
+    ) -> Result<DeepSeekResponse, DeepSeekError> {
+        // One key per logical request, shared by all of its retries
+        let idempotency_key = self.new_idempotency_key();
+        self.retry_transient(|| {
+            self.send_request_once(user_input, system_prompt, idempotency_key.as_deref())
+        })
+        .await
+    }
+
+    /// `send_request`, abandoned with `DeepSeekError::Cancelled` as soon as `cancel`
+    /// completes (e.g. on Ctrl+C)
+    pub async fn send_request_cancellable(
+        &self,
+        user_input: &str,
+        cancel: impl std::future::Future<Output = ()>,
+    ) -> Result<DeepSeekResponse, DeepSeekError> {
+        tokio::select! {
+            _ = cancel => {
+                tracing::info!("Request cancelled");
+                Err(DeepSeekError::Cancelled)
+            }
+            result = self.send_request(user_input) => result,
+        }
+    }
+
+    /// Run `op`, retrying busy/network failures with exponential backoff, up to
+    /// `MAX_ATTEMPTS` attempts. With a retry budget, each retry is also taken from it.
+    async fn retry_transient<T, Fut>(&self, mut op: impl FnMut() -> Fut) -> Result<T, DeepSeekError>
+    where
+        Fut: std::future::Future<Output = Result<T, DeepSeekError>>,
+    {
         let mut attempts = 0;
-        let max_attempts = 3;
-        let mut backoff = Duration::from_millis(500);
+        let cap = Duration::from_millis(self.config.max_backoff_ms);
+        let mut backoff = INITIAL_BACKOFF.min(cap);
 
         loop {
-            match self.send_request_once(user_input).await {
-                Ok(response) => return Ok(response),
-                Err(e)
-                    if (e.is_server_busy() || e.is_network_error())
-                        && attempts < max_attempts - 1 =>
+            match op().await {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is_retryable() && attempts < MAX_ATTEMPTS - 1 =>
                 {
+                    if let Some(budget) = &self.retry_budget {
+                        if !budget.try_consume() {
+                            tracing::warn!("Request failed: {}; retry budget exhausted, not retrying", e);
+                            return Err(e);
+                        }
+                        tracing::info!("Retry budget: {} retries left", budget.remaining());
+                    }
                     attempts += 1;
                     tracing::warn!(
                         "Request attempt {} failed: {}, retrying in {:?}",
@@ -220,46 +732,57 @@ impl DeepSeekClient {
                         backoff
                     );
                     tokio::time::sleep(backoff).await;
-                    backoff = backoff.saturating_mul(2);
+                    backoff = next_backoff(backoff, cap);
                 }
                 Err(e) => return Err(e),
             }
         }
     }
 
-    /// Send a single request to the DeepSeek API and return a structured response
-    #[allow(dead_code)]
-    async fn send_request_once(&self, user_input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
+    /// Build the system and user messages for the interactive chat request
+    fn build_request_messages(&self, user_input: &str, system_prompt: &str) -> Vec<ChatMessage> {
         let current_timestamp = Utc::now().to_rfc3339();
+        let schema = self
+            .config
+            .chat_json_schema
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CHAT_JSON_SCHEMA.replace("{timestamp}", &current_timestamp));
 
-        let system_prompt = "You are a helpful assistant that always responds with valid JSON in the specified format.";
         let json_format_prompt = format!(
             r#"
                 Please respond with a JSON object containing the following fields:
-                {{
-                "title": "A concise title for the topic (string)",
-                "description": "A brief description or summary (string)",
-                "content": "The main content or detailed response (string)",
-                "category": "Optional category classification (string or null)",
-                "timestamp": "Current response timestamp: {} (string)",
-                "confidence": "Optional confidence score between 0.0 and 1.0 (number or null)"
-                }}
+                {}
 
                 Make sure to provide valid JSON format in your response. Use the provided timestamp as the current response time.
                 Do not include any other text or comments in your response.
             "#,
-            current_timestamp
+            schema
         );
         let combined_prompt = format!("{}\n\n{}", user_input, json_format_prompt);
 
-        let raw = self
-            .send_messages_raw(vec![
-                ChatMessage { role: "system".to_string(), content: system_prompt.to_string() },
-                ChatMessage { role: "user".to_string(), content: combined_prompt },
-            ])
+        vec![
+            ChatMessage::system(system_prompt),
+            ChatMessage::user(combined_prompt),
+        ]
+    }
+
+    /// Send a single request to the DeepSeek API and return a structured response
+    #[allow(dead_code)]
+    async fn send_request_once(
+        &self,
+        user_input: &str,
+        system_prompt: &str,
+        idempotency_key: Option<&str>,
+    ) -> Result<DeepSeekResponse, DeepSeekError> {
+        let completion = self
+            .send_messages_keyed(
+                self.build_request_messages(user_input, system_prompt),
+                &RequestOptions::default(),
+                idempotency_key,
+            )
             .await?;
 
-        let parsed_response: DeepSeekResponse = serde_json::from_str(&raw).map_err(|e| {
+        let parsed_response: DeepSeekResponse = serde_json::from_str(&completion.content).map_err(|e| {
             DeepSeekError::ParseError {
                 message: format!("Failed to parse JSON response from DeepSeek: {}", e),
             }
@@ -271,9 +794,7 @@ impl DeepSeekClient {
     /// Map reqwest errors to our custom error types
     fn map_reqwest_error(&self, error: reqwest::Error) -> DeepSeekError {
         if error.is_timeout() {
-            return DeepSeekError::Timeout {
-                seconds: self.config.timeout,
-            };
+            return DeepSeekError::Timeout { seconds: self.config.timeout };
         }
 
         if error.is_connect() {
@@ -335,12 +856,167 @@ impl DeepSeekClient {
         }
     }
 
+    /// List the model ids available to the configured API key (`GET /models`)
+    pub async fn list_models(&self) -> Result<Vec<String>, DeepSeekError> {
+        let request = self
+            .client
+            .get(format!("{}/models", self.config.base_url))
+            .header("Authorization", format!("Bearer {}", self.config.api_key));
+        let response = self
+            .with_billing_tags(request)
+            .send()
+            .await
+            .map_err(|e| self.map_reqwest_error(e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.handle_error_response(status, response).await);
+        }
+
+        let models: ModelsResponse = response.json().await.map_err(|e| DeepSeekError::ParseError {
+            message: format!("Failed to parse models response: {}", e),
+        })?;
+        Ok(models.data.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Fresh idempotency key for one logical request, when enabled in the config
+    fn new_idempotency_key(&self) -> Option<String> {
+        self.config
+            .use_idempotency_key
+            .then(|| uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Send a trivial request and check that the configured model answers with parseable JSON,
+    /// i.e. that the endpoint honors the JSON response format
+    pub async fn preflight_json(&self) -> Result<(), DeepSeekError> {
+        let messages = vec![
+            ChatMessage::system("You are a health check. Respond with a JSON object only."),
+            ChatMessage::user(r#"Reply with exactly {"ok": true}"#),
+        ];
+        let raw = self.send_messages_raw(messages).await?;
+        serde_json::from_str::<serde_json::Value>(raw.trim()).map_err(|e| {
+            let snippet: String = raw.chars().take(80).collect();
+            DeepSeekError::ParseError {
+                message: format!(
+                    "Preflight failed: model '{}' ignored the JSON response format ({}); response began with: {}",
+                    self.config.model, e, snippet
+                ),
+            }
+        })?;
+        Ok(())
+    }
+
     /// Send arbitrary chat messages and return the raw assistant content string.
     /// The response is requested as a JSON object to encourage strict JSON outputs.
     pub async fn send_messages_raw(
         &self,
         messages: Vec<ChatMessage>,
     ) -> Result<String, DeepSeekError> {
+        self.send_messages_raw_with(messages, RequestOptions::default()).await
+    }
+
+    /// Like `send_messages_raw`, but also returns the reasoner's separate `reasoning_content`
+    pub async fn send_messages_with_reasoning(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        self.send_coalesced(messages, RequestOptions::default()).await
+    }
+
+    /// `send_messages_raw` with `options` taking precedence over the configured temperature,
+    /// max tokens, response format and stop sequences for this call only
+    #[allow(dead_code)]
+    pub async fn send_messages_raw_with(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: RequestOptions,
+    ) -> Result<String, DeepSeekError> {
+        self.send_coalesced(messages, options)
+            .await
+            .map(|completion| completion.content)
+    }
+
+    /// Send `messages` with `options` taking precedence over the configured temperature, max
+    /// tokens and response format; the completion carries the reasoner's `reasoning_content`.
+    /// Concurrent calls with identical messages, options and model settings share one API call.
+    async fn send_coalesced(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: RequestOptions,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        let key = self.request_hash(&messages, &options);
+        let request = {
+            let mut in_flight = self.in_flight.lock().expect("in-flight map mutex poisoned");
+            match in_flight.get(&key) {
+                Some(existing) => {
+                    tracing::debug!("Joining identical in-flight request {:016x}", key);
+                    existing.clone()
+                }
+                None => {
+                    let client = self.clone();
+                    let request = async move {
+                        let idempotency_key = client.new_idempotency_key();
+                        if client.retry_budget.is_none() {
+                            return client
+                                .send_messages_keyed(messages, &options, idempotency_key.as_deref())
+                                .await;
+                        }
+                        client
+                            .retry_transient(|| {
+                                client.send_messages_keyed(
+                                    messages.clone(),
+                                    &options,
+                                    idempotency_key.as_deref(),
+                                )
+                            })
+                            .await
+                    }
+                    .boxed()
+                    .shared();
+                    in_flight.insert(key, request.clone());
+                    request
+                }
+            }
+        };
+
+        let result = request.clone().await;
+        let mut in_flight = self.in_flight.lock().expect("in-flight map mutex poisoned");
+        if in_flight.get(&key).is_some_and(|current| current.ptr_eq(&request)) {
+            in_flight.remove(&key);
+        }
+        result
+    }
+
+    /// Identity of a completion request for coalescing: model settings, per-call options
+    /// and messages
+    fn request_hash(&self, messages: &[ChatMessage], options: &RequestOptions) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.config.base_url.hash(&mut hasher);
+        self.config.model.hash(&mut hasher);
+        self.config.max_tokens.hash(&mut hasher);
+        self.config.temperature.to_bits().hash(&mut hasher);
+        options.temperature.map(f32::to_bits).hash(&mut hasher);
+        options.max_tokens.hash(&mut hasher);
+        options.response_format.hash(&mut hasher);
+        options.stop.hash(&mut hasher);
+        messages.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// `send_messages_raw` with a caller-provided idempotency key, so that
+    /// retries of one logical request can reuse it
+    async fn send_messages_keyed(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &RequestOptions,
+        idempotency_key: Option<&str>,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        self.cooldown.wait().await;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let _slot = self.acquire_request_slot().await;
+
         // If the external client is available (official host and feature enabled), use it.
         #[cfg(feature = "deepseek_api")]
         {
@@ -348,24 +1024,34 @@ impl DeepSeekClient {
                 // Map our ChatMessage types to deepseek-api MessageRequest
                 let mapped: Vec<ExtMessageRequest> = messages
                     .iter()
-                    .map(|m| match m.role.as_str() {
-                        "system" => ExtMessageRequest::sys(&m.content),
-                        "assistant" => {
+                    .map(|m| match m.role {
+                        Role::System => ExtMessageRequest::sys(&m.content),
+                        Role::Assistant => {
                             ExtMessageRequest::Assistant(deepseek_api::response::AssistantMessage::new(&m.content))
                         }
-                        _ => ExtMessageRequest::user(&m.content),
+                        Role::User => ExtMessageRequest::user(&m.content),
+                        // deepseek-api has no tool message; pass the result on as user content
+                        Role::Tool => ExtMessageRequest::user(&m.content),
                     })
                     .collect();
 
                 // Build request enforcing JSON response format to encourage structured outputs
                 // Builder in this crate is by-value; use consuming setters and rebind
                 let mut builder = ExtCompletionsRequestBuilder::new(&mapped)
-                    .response_format(deepseek_api::request::ResponseType::Json)
                     .use_model(map_model_string_to_ext(&self.config.model));
+                if options
+                    .response_format
+                    .unwrap_or_else(|| self.response_format_supported.load(Ordering::Relaxed))
+                {
+                    builder = builder.response_format(deepseek_api::request::ResponseType::Json);
+                }
+                if options.stop.is_some() {
+                    tracing::warn!("Stop sequences are not supported by the deepseek-api backend; ignored");
+                }
 
-                let clamped_max = self.config.max_tokens.min(8192).max(1);
+                let clamped_max = options.max_tokens.unwrap_or(self.config.max_tokens).min(8192).max(1);
                 builder = builder.max_tokens(clamped_max).unwrap();
-                let clamped_temp = self.config.temperature.max(0.0).min(2.0);
+                let clamped_temp = options.temperature.unwrap_or(self.config.temperature).max(0.0).min(2.0);
                 builder = builder.temperature(clamped_temp).unwrap();
 
                 // Execute
@@ -377,9 +1063,11 @@ impl DeepSeekClient {
                 return match resp {
                     ExtChatResponse::Full(full) => {
                         let first = full.choices.get(0).ok_or_else(|| DeepSeekError::ParseError { message: "No choices in API response".to_string() })?;
-                        if let Some(msg) = &first.message { Ok(msg.content.clone()) }
-                        else if let Some(text) = &first.text { Ok(text.clone()) }
-                        else { Err(DeepSeekError::ParseError { message: "Empty content in API response".to_string() }) }
+                        RawCompletion::from_choice_parts(
+                            first.message.as_ref().map(|m| m.content.as_str()),
+                            first.message.as_ref().and_then(|m| m.reasoning_content.as_deref()),
+                            first.text.as_deref(),
+                        )
                     }
                     ExtChatResponse::Stream(_) => {
                         // We didn't request streaming; treat as error if encountered.
@@ -390,52 +1078,388 @@ impl DeepSeekClient {
         }
 
         // Fallback: internal HTTP implementation honoring custom base_url (e.g., tests)
-        self.send_messages_raw_internal(messages).await
+        self.send_messages_raw_internal(messages, options, idempotency_key)
+            .await
     }
 }
 
 impl DeepSeekClient {
+    /// Whether requests currently carry `response_format: json_object`
+    pub fn response_format_supported(&self) -> bool {
+        self.response_format_supported.load(Ordering::Relaxed)
+    }
+
     async fn send_messages_raw_internal(
         &self,
         messages: Vec<ChatMessage>,
-    ) -> Result<String, DeepSeekError> {
+        options: &RequestOptions,
+        idempotency_key: Option<&str>,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        if let Some(json_format) = options.response_format {
+            return self.post_completion(messages, options, idempotency_key, json_format).await;
+        }
+        if !self.response_format_supported() {
+            return self.post_completion(messages, options, idempotency_key, false).await;
+        }
+        match self.post_completion(messages.clone(), options, idempotency_key, true).await {
+            // Only a rejection of the field itself; other 400s are the request's own fault
+            Err(DeepSeekError::ApiError { status: 400, message }) if message.contains("response_format") => {
+                tracing::warn!(
+                    "Request with response_format rejected (400: {}); retrying without it",
+                    message
+                );
+                // A different body needs its own idempotency key
+                let retry_key = idempotency_key.and(self.new_idempotency_key());
+                let result = self
+                    .post_completion(messages, options, retry_key.as_deref(), false)
+                    .await;
+                // Concurrent fallbacks all clear the flag; only the first one logs it
+                if result.is_ok() && self.response_format_supported.swap(false, Ordering::Relaxed) {
+                    tracing::warn!(
+                        "Backend does not support response_format; relying on prompt instructions for JSON output"
+                    );
+                }
+                result
+            }
+            other => other,
+        }
+    }
+
+    /// POST one chat completion, with or without `response_format: json_object`
+    async fn post_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &RequestOptions,
+        idempotency_key: Option<&str>,
+        json_format: bool,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        let mut choices = self
+            .post_completion_choices(messages, options, idempotency_key, json_format, None)
+            .await?;
+        // Never empty: an empty result is reported as an error
+        Ok(choices.swap_remove(0))
+    }
+
+    /// POST one chat completion asking for `n` choices. Returns every choice that was not
+    /// blocked by the content filter, in order; an error if all of them were.
+    async fn post_completion_choices(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &RequestOptions,
+        idempotency_key: Option<&str>,
+        json_format: bool,
+        n: Option<u8>,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError> {
+        let request = ChatRequest {
+            model: self.config.model.clone(),
+            messages,
+            response_format: json_format
+                .then(|| ResponseFormat { format_type: "json_object".to_string() }),
+            max_tokens: options.max_tokens.unwrap_or(self.config.max_tokens),
+            temperature: options.temperature.unwrap_or(self.config.temperature),
+            stop: options.stop.clone(),
+            n,
+            tools: None,
+            tool_choice: None,
+            stream: false,
+        };
+        let response = self.post_chat(&request, idempotency_key).await?;
+
+        let api_response: ApiResponse = response
+            .json()
+            .await
+            .map_err(|e| DeepSeekError::ParseError { message: format!("Failed to parse API response: {}", e) })?;
+
+        if api_response.choices.is_empty() {
+            return Err(DeepSeekError::ParseError { message: "No choices in API response".to_string() });
+        }
+
+        let total = api_response.choices.len();
+        let mut completions = Vec::with_capacity(total);
+        let mut empty_choice = None;
+        for (index, choice) in api_response.choices.into_iter().enumerate() {
+            if choice.finish_reason.as_deref() == Some(CONTENT_FILTER_FINISH_REASON) {
+                if total > 1 {
+                    tracing::warn!("Choice {} of {} was blocked by the content filter", index + 1, total);
+                }
+                continue;
+            }
+            match choice.into_completion() {
+                Ok(completion) => completions.push(completion),
+                Err(e) => {
+                    if total > 1 {
+                        tracing::warn!("Choice {} of {} has no content; skipped", index + 1, total);
+                    }
+                    empty_choice = Some(e);
+                }
+            }
+        }
+        if completions.is_empty() {
+            if let Some(e) = empty_choice {
+                return Err(e);
+            }
+            tracing::warn!("Model response was blocked by the content filter");
+            return Err(DeepSeekError::ContentFiltered);
+        }
+        Ok(completions)
+    }
+
+    /// Request `n` alternative completions of `messages` in one call (the API's `n`).
+    /// Content-filtered and empty choices are dropped. Uses the internal HTTP client; retried only
+    /// when a retry budget is attached.
+    pub async fn send_messages_candidates(
+        &self,
+        messages: Vec<ChatMessage>,
+        n: u8,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError> {
+        let idempotency_key = self.new_idempotency_key();
+        let send = || async {
+            self.cooldown.wait().await;
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+            let _slot = self.acquire_request_slot().await;
+            self.post_completion_choices(
+                messages.clone(),
+                &RequestOptions::default(),
+                idempotency_key.as_deref(),
+                self.response_format_supported(),
+                Some(n),
+            )
+            .await
+        };
+        if self.retry_budget.is_none() {
+            return send().await;
+        }
+        self.retry_transient(send).await
+    }
+
+    /// Send chat messages offering `tools`. Returns the tool calls the model requested, or
+    /// its content when it answered directly. No `response_format` is requested; uses the
+    /// internal HTTP client and is retried only when a retry budget is attached.
+    #[allow(dead_code)]
+    pub async fn send_messages_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: &[ToolDef],
+        tool_choice: Option<ToolChoice>,
+    ) -> Result<ToolResponse, DeepSeekError> {
         let request = ChatRequest {
             model: self.config.model.clone(),
             messages,
-            response_format: ResponseFormat { format_type: "json_object".to_string() },
+            response_format: None,
             max_tokens: self.config.max_tokens,
             temperature: self.config.temperature,
             stop: None,
+            n: None,
+            tools: Some(
+                tools
+                    .iter()
+                    .map(|tool| RequestTool { tool_type: "function", function: tool.clone() })
+                    .collect(),
+            ),
+            tool_choice,
+            stream: false,
+        };
+        let idempotency_key = self.new_idempotency_key();
+        let send = || async {
+            self.cooldown.wait().await;
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+            let _slot = self.acquire_request_slot().await;
+            let response = self.post_chat(&request, idempotency_key.as_deref()).await?;
+            let api_response: ApiResponse = response.json().await.map_err(|e| {
+                DeepSeekError::ParseError { message: format!("Failed to parse API response: {}", e) }
+            })?;
+            tool_response(api_response)
         };
+        if self.retry_budget.is_none() {
+            return send().await;
+        }
+        self.retry_transient(send).await
+    }
+}
 
-        let response = self
+/// First choice of a tool-enabled completion as a `ToolResponse`
+fn tool_response(api_response: ApiResponse) -> Result<ToolResponse, DeepSeekError> {
+    let choice = api_response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| DeepSeekError::ParseError { message: "No choices in API response".to_string() })?;
+    if choice.finish_reason.as_deref() == Some(CONTENT_FILTER_FINISH_REASON) {
+        tracing::warn!("Model response was blocked by the content filter");
+        return Err(DeepSeekError::ContentFiltered);
+    }
+    match choice.message.tool_calls {
+        Some(calls) if !calls.is_empty() => Ok(ToolResponse::ToolCalls(calls)),
+        _ => {
+            let completion =
+                RawCompletion::from_choice_parts(choice.message.content.as_deref(), None, None)?;
+            Ok(ToolResponse::Content(completion.content))
+        }
+    }
+}
+
+impl DeepSeekClient {
+    /// `builder` with the configured organization/project headers, for billing attribution
+    fn with_billing_tags(&self, mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(organization) = &self.config.organization {
+            builder = builder.header(ORGANIZATION_HEADER, organization);
+        }
+        if let Some(project) = &self.config.project {
+            builder = builder.header(PROJECT_HEADER, project);
+        }
+        builder
+    }
+
+    /// POST `request` to `/chat/completions`, mapping error statuses and pausing all
+    /// requests after a busy signal
+    async fn post_chat(
+        &self,
+        request: &ChatRequest,
+        idempotency_key: Option<&str>,
+    ) -> Result<reqwest::Response, DeepSeekError> {
+        let mut builder = self
             .client
             .post(format!("{}/chat/completions", self.config.base_url))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
+            .header("Content-Type", "application/json");
+        if let Some(key) = idempotency_key {
+            builder = builder.header("Idempotency-Key", key);
+        }
+        builder = self.with_billing_tags(builder);
+
+        let response = builder
+            .json(request)
             .send()
             .await
             .map_err(|e| self.map_reqwest_error(e))?;
 
         let status = response.status();
         if !status.is_success() {
-            return Err(self.handle_error_response(status, response).await);
+            let retry_after = retry_after(&response);
+            let err = self.handle_error_response(status, response).await;
+            if err.is_server_busy() {
+                let cooldown = retry_after.unwrap_or(DEFAULT_COOLDOWN);
+                tracing::warn!("Server busy ({}); pausing all requests for {:?}", status, cooldown);
+                self.cooldown.trigger(cooldown);
+            }
+            return Err(err);
         }
+        Ok(response)
+    }
 
-        let api_response: ApiResponse = response
-            .json()
-            .await
-            .map_err(|e| DeepSeekError::ParseError { message: format!("Failed to parse API response: {}", e) })?;
-
-        if api_response.choices.is_empty() {
-            return Err(DeepSeekError::ParseError { message: "No choices in API response".to_string() });
+    /// Send chat messages with `stream: true` and yield the assistant content as it arrives,
+    /// one delta per item, without buffering the whole answer. No `response_format` is
+    /// requested and nothing is retried; always uses the internal HTTP client. A stream
+    /// that times out ends with `Timeout`; the deltas before it are not repeated, so a
+    /// consumer wanting the partial answer keeps what it was given.
+    pub async fn stream_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<BoxStream<'static, Result<String, DeepSeekError>>, DeepSeekError> {
+        self.cooldown.wait().await;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
         }
+        // Held by the stream until it is dropped: the connection stays open that long
+        let slot = self.acquire_request_slot().await;
+        let request = ChatRequest {
+            model: self.config.model.clone(),
+            messages,
+            response_format: None,
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+            stop: None,
+            n: None,
+            tools: None,
+            tool_choice: None,
+            stream: true,
+        };
+        let idempotency_key = self.new_idempotency_key();
+        let response = self.post_chat(&request, idempotency_key.as_deref()).await?;
 
-        Ok(api_response.choices[0].message.content.clone())
+        let seconds = self.config.timeout;
+        // Only the number of content bytes received is tracked, for the timeout warning
+        let state = (Some(response), SseParser::new(), VecDeque::new(), 0usize, slot);
+        Ok(stream::unfold(state, move |(mut response, mut parser, mut pending, mut received, slot)| async move {
+            loop {
+                if let Some(item) = pending.pop_front() {
+                    let item: Result<String, DeepSeekError> = item;
+                    match &item {
+                        Ok(delta) => received += delta.len(),
+                        // Nothing after an error is meaningful
+                        Err(_) => return Some((item, (None, parser, VecDeque::new(), received, slot))),
+                    }
+                    return Some((item, (response, parser, pending, received, slot)));
+                }
+                let payloads = match response.as_mut()?.chunk().await {
+                    Ok(Some(bytes)) => parser.push(&bytes),
+                    Ok(None) => {
+                        response = None;
+                        parser.finish()
+                    }
+                    Err(e) => {
+                        let err = if e.is_timeout() {
+                            if received > 0 {
+                                tracing::warn!("Stream timed out after {} bytes of content", received);
+                            }
+                            DeepSeekError::Timeout { seconds }
+                        } else {
+                            DeepSeekError::NetworkError {
+                                message: format!("Stream interrupted: {}", e),
+                            }
+                        };
+                        return Some((Err(err), (None, parser, pending, received, slot)));
+                    }
+                };
+                pending.extend(payloads.iter().filter_map(|p| stream_delta(p).transpose()));
+            }
+        })
+        .boxed())
     }
 }
 
+/// Content of one SSE payload of a streamed completion; `None` for `[DONE]` and
+/// events without content
+fn stream_delta(payload: &str) -> Result<Option<String>, DeepSeekError> {
+    let payload = payload.trim();
+    if payload.is_empty() || payload == STREAM_DONE {
+        return Ok(None);
+    }
+    let chunk: StreamChunk = serde_json::from_str(payload).map_err(|e| DeepSeekError::ParseError {
+        message: format!("Failed to parse stream event: {}", e),
+    })?;
+    let Some(choice) = chunk.choices.into_iter().next() else {
+        return Ok(None);
+    };
+    if choice.finish_reason.as_deref() == Some(CONTENT_FILTER_FINISH_REASON) {
+        tracing::warn!("Streamed model response was blocked by the content filter");
+        return Err(DeepSeekError::ContentFiltered);
+    }
+    Ok(choice.delta.content.filter(|c| !c.is_empty()))
+}
+
+/// Exponential back-off step: double `current`, never exceeding `cap`
+fn next_backoff(current: Duration, cap: Duration) -> Duration {
+    current.saturating_mul(2).min(cap)
+}
+
+/// Parse a `Retry-After` header given in seconds
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
 #[cfg(feature = "deepseek_api")]
 fn is_official_deepseek_host(base_url: &str) -> bool {
     // Accept both https://api.deepseek.com and https://api.deepseek.com/v1
@@ -460,3 +1484,935 @@ fn map_model_string_to_ext(model: &str) -> ExtModelType {
 #[cfg(not(feature = "deepseek_api"))]
 #[allow(dead_code)]
 fn map_model_string_to_ext(_model: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{completion_body, mock_config};
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn user(content: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage::user(content)]
+    }
+
+    #[test]
+    fn roles_serialize_to_api_strings() {
+        for (message, role) in [
+            (ChatMessage::system("s"), "system"),
+            (ChatMessage::user("u"), "user"),
+            (ChatMessage::assistant("a"), "assistant"),
+            (ChatMessage::tool("t", "call_1", None), "tool"),
+        ] {
+            let value = serde_json::to_value(&message).unwrap();
+            assert_eq!(value["role"], role);
+            let back: ChatMessage = serde_json::from_value(value).unwrap();
+            assert_eq!(back.role, message.role);
+        }
+        assert!(serde_json::from_value::<Role>(json!("admin")).is_err());
+    }
+
+    #[tokio::test]
+    async fn tool_messages_are_sent_with_their_metadata() {
+        let tool = ChatMessage::tool("{\"temp\": 21}", "call_1", Some("weather".to_string()));
+        assert_eq!(
+            serde_json::to_value(&tool).unwrap(),
+            json!({"role": "tool", "content": "{\"temp\": 21}", "tool_call_id": "call_1", "name": "weather"})
+        );
+        assert_eq!(
+            serde_json::to_value(ChatMessage::user("u")).unwrap(),
+            json!({"role": "user", "content": "u"})
+        );
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{\"ok\": true}")))
+            .mount(&server)
+            .await;
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        client
+            .send_messages_raw(vec![ChatMessage::user("weather?"), tool])
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["messages"][1]["role"], "tool");
+        assert_eq!(body["messages"][1]["tool_call_id"], "call_1");
+        assert_eq!(body["messages"][1]["name"], "weather");
+    }
+
+    #[tokio::test]
+    async fn named_messages_are_attributed_in_the_request() {
+        let producer = ChatMessage::assistant("draft").with_name("producer");
+        assert_eq!(
+            serde_json::to_value(&producer).unwrap(),
+            json!({"role": "assistant", "content": "draft", "name": "producer"})
+        );
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{\"ok\": true}")))
+            .mount(&server)
+            .await;
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        client
+            .send_messages_raw(vec![
+                ChatMessage::user("task"),
+                producer,
+                ChatMessage::user("3 bullets required").with_name("auditor"),
+            ])
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body["messages"][0].get("name").is_none());
+        assert_eq!(body["messages"][1]["name"], "producer");
+        assert_eq!(body["messages"][2]["name"], "auditor");
+    }
+
+    fn weather_tool() -> ToolDef {
+        ToolDef::new(
+            "get_weather",
+            "Current weather for a city",
+            json!({"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]}),
+        )
+    }
+
+    #[tokio::test]
+    async fn tool_defs_and_choice_are_sent_and_tool_calls_parsed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "get_weather", "arguments": "{\"city\": \"Oslo\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let response = client
+            .send_messages_with_tools(
+                user("weather in Oslo?"),
+                &[weather_tool()],
+                Some(ToolChoice::Function("get_weather".to_string())),
+            )
+            .await
+            .unwrap();
+
+        let ToolResponse::ToolCalls(calls) = response else { panic!("expected tool calls") };
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].function.arguments, "{\"city\": \"Oslo\"}");
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body["tools"],
+            json!([{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather for a city",
+                    "parameters": {"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]}
+                }
+            }])
+        );
+        assert_eq!(body["tool_choice"], json!({"type": "function", "function": {"name": "get_weather"}}));
+        assert!(body.get("response_format").is_none());
+
+        let replay = ChatMessage::assistant_tool_calls(calls);
+        assert_eq!(serde_json::to_value(&replay).unwrap()["tool_calls"][0]["type"], "function");
+    }
+
+    #[tokio::test]
+    async fn tool_request_answered_directly_returns_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("It is sunny.")))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let response = client
+            .send_messages_with_tools(user("hi"), &[weather_tool()], None)
+            .await
+            .unwrap();
+        assert_eq!(response, ToolResponse::Content("It is sunny.".to_string()));
+        assert_eq!(serde_json::to_value(ToolChoice::Auto).unwrap(), json!("auto"));
+        assert_eq!(serde_json::to_value(ToolChoice::None).unwrap(), json!("none"));
+        assert_eq!(serde_json::to_value(ToolChoice::Required).unwrap(), json!("required"));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("tool_choice").is_none());
+    }
+
+    #[test]
+    fn clone_with_model_shares_the_http_client() {
+        let chat = DeepSeekClient::new(Config {
+            api_key: "k".to_string(),
+            json_format_for_reasoner: false,
+            requests_per_minute: Some(60),
+            ..Config::default()
+        })
+        .unwrap()
+        .with_retry_budget(Arc::new(RetryBudget::new(2)));
+        let reasoner = chat.clone_with_model(REASONER_MODEL).unwrap().with_temperature(0.0);
+
+        assert_eq!(chat.config().model, "deepseek-chat");
+        assert_eq!(reasoner.config().model, REASONER_MODEL);
+        assert_eq!(reasoner.config().temperature, 0.0);
+        assert!(Arc::ptr_eq(&chat.client, &reasoner.client));
+        assert!(Arc::ptr_eq(
+            chat.retry_budget.as_ref().unwrap(),
+            reasoner.retry_budget.as_ref().unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            chat.rate_limiter.as_ref().unwrap(),
+            reasoner.rate_limiter.as_ref().unwrap()
+        ));
+        assert!(Arc::ptr_eq(&chat.cooldown, &reasoner.cooldown));
+        // Model-specific state is derived afresh
+        assert!(chat.response_format_supported());
+        assert!(!reasoner.response_format_supported());
+
+        let standalone = DeepSeekClient::new(chat.config().clone()).unwrap();
+        assert!(!Arc::ptr_eq(&chat.client, &standalone.client));
+    }
+
+    #[tokio::test]
+    async fn configured_user_agent_is_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("user-agent", "team-a/1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = mock_config(&server);
+        config.user_agent = Some("team-a/1.0".to_string());
+        let client = DeepSeekClient::new(config).unwrap();
+
+        let content = client.send_messages_raw(user("hi")).await.unwrap();
+        assert_eq!(content, "{}");
+    }
+
+    #[tokio::test]
+    async fn request_options_take_precedence_over_config() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("done")))
+            .mount(&server)
+            .await;
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+
+        client.send_messages_raw(user("hi")).await.unwrap();
+        let options = RequestOptions {
+            temperature: Some(0.1),
+            max_tokens: Some(64),
+            response_format: Some(false),
+            stop: Some(vec!["END".to_string()]),
+        };
+        client.send_messages_raw_with(user("hi"), options).await.unwrap();
+        // A partial override keeps the configured values for the other fields
+        let options = RequestOptions { max_tokens: Some(32), ..RequestOptions::default() };
+        client.send_messages_raw_with(user("hi"), options).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let bodies: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .collect();
+        let config = client.config();
+        assert_eq!(bodies[0]["max_tokens"], config.max_tokens);
+        assert_eq!(bodies[0]["response_format"]["type"], "json_object");
+        assert!(bodies[0].get("stop").is_none());
+
+        assert_eq!(bodies[1]["max_tokens"], 64);
+        assert!((bodies[1]["temperature"].as_f64().unwrap() - 0.1).abs() < 1e-6);
+        assert!(bodies[1].get("response_format").is_none());
+        assert_eq!(bodies[1]["stop"], serde_json::json!(["END"]));
+
+        assert_eq!(bodies[2]["max_tokens"], 32);
+        assert_eq!(bodies[2]["temperature"], bodies[0]["temperature"]);
+        assert_eq!(bodies[2]["response_format"]["type"], "json_object");
+    }
+
+    #[tokio::test]
+    async fn billing_tags_are_sent_as_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("openai-organization", "org-billing"))
+            .and(header("openai-project", "search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = mock_config(&server);
+        config.organization = Some("org-billing".to_string());
+        config.project = Some("search".to_string());
+        let client = DeepSeekClient::new(config).unwrap();
+
+        assert_eq!(client.send_messages_raw(user("hi")).await.unwrap(), "{}");
+    }
+
+    #[test]
+    fn external_choice_mapping_keeps_reasoning() {
+        let completion =
+            RawCompletion::from_choice_parts(Some("{\"ok\":true}"), Some("Check each criterion."), None)
+                .unwrap();
+        assert_eq!(completion.content, "{\"ok\":true}");
+        assert_eq!(completion.reasoning.as_deref(), Some("Check each criterion."));
+
+        let legacy = RawCompletion::from_choice_parts(None, None, Some("{}")).unwrap();
+        assert_eq!(legacy, RawCompletion { content: "{}".to_string(), reasoning: None });
+
+        let empty = RawCompletion::from_choice_parts(Some("{}"), Some(""), None).unwrap();
+        assert!(empty.reasoning.is_none());
+
+        assert!(RawCompletion::from_choice_parts(None, Some("thinking"), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn every_choice_shape_resolves_to_its_content() {
+        let shapes = [
+            json!({ "choices": [{ "message": { "role": "assistant", "content": "from message" } }] }),
+            json!({ "choices": [{ "text": "from text", "finish_reason": "stop" }] }),
+            json!({ "choices": [{ "message": { "role": "assistant", "content": "" }, "text": "from text" }] }),
+            json!({ "choices": [{ "delta": { "content": "from delta" } }] }),
+            // The first choice is empty; the content is in a later one
+            json!({ "choices": [
+                { "message": { "role": "assistant", "content": null } },
+                { "message": { "role": "assistant", "content": "from second choice" } }
+            ] }),
+        ];
+        let expected = ["from message", "from text", "from text", "from delta", "from second choice"];
+        for (shape, expected) in shapes.into_iter().zip(expected) {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(shape))
+                .mount(&server)
+                .await;
+            let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+            assert_eq!(client.send_messages_raw(user("hi")).await.unwrap(), expected);
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "choices": [{ "index": 0 }] })))
+            .mount(&server)
+            .await;
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let err = client.send_messages_raw(user("hi")).await.unwrap_err();
+        assert!(err.to_string().contains("Empty content"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn content_filter_finish_reason_maps_to_content_filtered() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": null },
+                    "finish_reason": "content_filter"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server))
+            .unwrap()
+            .with_retry_budget(Arc::new(RetryBudget::new(5)));
+        let err = client.send_messages_raw(user("hi")).await.unwrap_err();
+        assert!(err.is_content_filtered(), "unexpected error: {}", err);
+        assert!(err.user_message().contains("content filter"));
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn stalled_stream_times_out_after_the_deltas_received() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Sends one delta, then stalls without ending the response
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+            let event = format!(
+                "data: {}\n\n",
+                json!({ "choices": [{ "delta": { "content": "partial answer" } }] })
+            );
+            let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n";
+            let response = format!("{}{:x}\r\n{}\r\n", head, event.len(), event);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        });
+        let config = Config {
+            api_key: "k".to_string(),
+            base_url: format!("http://{}", addr),
+            timeout: 1,
+            ..Config::default()
+        };
+
+        let client = DeepSeekClient::new(config).unwrap();
+        let items: Vec<_> = client.stream_messages(user("hi")).await.unwrap().collect().await;
+        server.abort();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_deref().unwrap(), "partial answer");
+        let err = items[1].as_ref().unwrap_err();
+        assert!(matches!(err, DeepSeekError::Timeout { seconds: 1 }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn streamed_completion_yields_deltas_and_stops_on_content_filter() {
+        use futures::TryStreamExt;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(crate::test_support::sse_body(&["Hel", "lo ", "世界"])),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let filtered = format!(
+            "data: {}\n\ndata: {}\n\n",
+            json!({ "choices": [{ "delta": { "content": "par" } }] }),
+            json!({ "choices": [{ "delta": {}, "finish_reason": "content_filter" }] })
+        );
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(filtered))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let deltas: Vec<String> = client.stream_messages(user("hi")).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(deltas, vec!["Hel", "lo ", "世界"]);
+        let body: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(body["stream"], json!(true));
+        assert!(body.get("response_format").is_none());
+
+        let items: Vec<_> = client.stream_messages(user("hi")).await.unwrap().collect().await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_deref().unwrap(), "par");
+        assert!(items[1].as_ref().unwrap_err().is_content_filtered());
+    }
+
+    #[tokio::test]
+    async fn reasoning_content_is_returned_alongside_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": "{}",
+                        "reasoning_content": "The user wants JSON."
+                    }
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let completion = client.send_messages_with_reasoning(user("hi")).await.unwrap();
+        assert_eq!(completion.content, "{}");
+        assert_eq!(completion.reasoning.as_deref(), Some("The user wants JSON."));
+    }
+
+    #[tokio::test]
+    async fn rate_limit_on_one_request_throttles_the_others() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("first"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("second"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let start = std::time::Instant::now();
+
+        let first = {
+            let client = client.clone();
+            tokio::spawn(async move { client.send_messages_raw(user("first")).await })
+        };
+        let second = {
+            let client = client.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                client.send_messages_raw(user("second")).await
+            })
+        };
+
+        assert!(first.await.unwrap().unwrap_err().is_server_busy());
+        assert_eq!(second.await.unwrap().unwrap(), "{}");
+        assert!(
+            start.elapsed() >= Duration::from_millis(900),
+            "second request was not throttled: {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_never_exceed_the_configured_limit() {
+        let server = MockServer::start().await;
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        let recorded = arrivals.clone();
+        Mock::given(method("POST"))
+            .respond_with(move |_: &wiremock::Request| {
+                recorded.lock().unwrap().push(std::time::Instant::now());
+                ResponseTemplate::new(200)
+                    .set_body_json(completion_body("{}"))
+                    .set_delay(Duration::from_millis(200))
+            })
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(Config {
+            max_concurrent_requests: Some(2),
+            ..mock_config(&server)
+        })
+        .unwrap();
+        // Derived clients draw from the same slots
+        let reasoner = client.clone_with_model(REASONER_MODEL).unwrap();
+        let calls = (0..6).map(|i| {
+            let client = if i % 2 == 0 { client.clone() } else { reasoner.clone() };
+            // Distinct messages, so no two calls are coalesced into one request
+            async move { client.send_messages_raw(user(&format!("call {}", i))).await }
+        });
+        for result in futures::future::join_all(calls).await {
+            assert_eq!(result.unwrap(), "{}");
+        }
+
+        // With two slots, any three arrivals span at least one full response delay
+        let arrivals = arrivals.lock().unwrap();
+        assert_eq!(arrivals.len(), 6);
+        let mut arrivals = arrivals.clone();
+        arrivals.sort();
+        for window in arrivals.windows(3) {
+            let span = window[2] - window[0];
+            assert!(span >= Duration::from_millis(180), "3 requests in flight within {:?}", span);
+        }
+    }
+
+    #[tokio::test]
+    async fn open_stream_holds_its_request_slot_until_dropped() {
+        let server = MockServer::start().await;
+        Mock::given(body_string_contains("\"stream\":true"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(crate::test_support::sse_body(&["a", "b"])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(Config {
+            max_concurrent_requests: Some(1),
+            ..mock_config(&server)
+        })
+        .unwrap();
+        let mut deltas = client.stream_messages(user("stream")).await.unwrap();
+        assert_eq!(deltas.next().await.unwrap().unwrap(), "a");
+        let waiting = tokio::time::timeout(Duration::from_millis(300), client.send_messages_raw(user("hi")));
+        assert!(waiting.await.is_err(), "request sent while the stream held the only slot");
+
+        drop(deltas);
+        assert_eq!(client.send_messages_raw(user("hi")).await.unwrap(), "{}");
+    }
+
+    #[test]
+    fn request_messages_use_configured_prompt_and_schema() {
+        let config = Config {
+            api_key: "k".to_string(),
+            chat_system_prompt: Some("You are a release-notes bot.".to_string()),
+            chat_json_schema: Some("{ \"title\": \"(string)\", \"version\": \"(string)\" }".to_string()),
+            ..Config::default()
+        };
+        let client = DeepSeekClient::new(config).unwrap();
+
+        let messages = client.build_request_messages("hello", "Custom system prompt");
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(messages[0].content, "Custom system prompt");
+        assert!(messages[1].content.starts_with("hello"));
+        assert!(messages[1].content.contains("\"version\""));
+    }
+
+    #[tokio::test]
+    async fn send_request_uses_configured_system_prompt() {
+        let server = MockServer::start().await;
+        let reply = r#"{"title":"t","description":"d","content":"c"}"#;
+        Mock::given(method("POST"))
+            .and(body_string_contains("You are a release-notes bot."))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(reply)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = mock_config(&server);
+        config.chat_system_prompt = Some("You are a release-notes bot.".to_string());
+        let client = DeepSeekClient::new(config).unwrap();
+
+        let response = client.send_request("hi").await.unwrap();
+        assert_eq!(response.title, "t");
+    }
+
+    #[tokio::test]
+    async fn cancelled_request_returns_the_cancelled_variant() {
+        let server = MockServer::start().await;
+        let reply = r#"{"title":"t","description":"d","content":"c"}"#;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completion_body(reply))
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&server)
+            .await;
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+
+        let cancel = tokio::time::sleep(Duration::from_millis(50));
+        let err = client.send_request_cancellable("hi", cancel).await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::Cancelled), "{:?}", err);
+        assert!(err.is_cancelled());
+        assert!(!err.is_retryable());
+        assert_eq!(err.user_message(), "⏹️ The request was cancelled.");
+    }
+
+    #[test]
+    fn forced_internal_backend_is_used_even_on_the_official_host() {
+        let config = Config {
+            api_key: "k".to_string(),
+            base_url: "https://api.deepseek.com/v1".to_string(),
+            force_internal: true,
+            ..Config::default()
+        };
+        let client = DeepSeekClient::new(config).unwrap();
+        assert_eq!(client.backend_kind(), BackendKind::Internal);
+    }
+
+    #[test]
+    fn backoff_never_exceeds_the_cap() {
+        let cap = Duration::from_millis(3_000);
+        let mut backoff = INITIAL_BACKOFF;
+        let mut schedule = Vec::new();
+        for _ in 0..10 {
+            backoff = next_backoff(backoff, cap);
+            assert!(backoff <= cap, "{:?} exceeds {:?}", backoff, cap);
+            schedule.push(backoff.as_millis());
+        }
+        assert_eq!(&schedule[..4], &[1_000, 2_000, 3_000, 3_000]);
+
+        // Saturates instead of overflowing
+        assert_eq!(next_backoff(Duration::MAX, cap), cap);
+    }
+
+    #[tokio::test]
+    async fn retries_sleep_no_longer_than_max_backoff() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).insert_header("retry-after", "0"))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        let reply = r#"{"title":"t","description":"d","content":"c"}"#;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(reply)))
+            .mount(&server)
+            .await;
+
+        let mut config = mock_config(&server);
+        config.max_backoff_ms = 50;
+        let client = DeepSeekClient::new(config).unwrap();
+        let start = std::time::Instant::now();
+        client.send_request("hi").await.unwrap();
+
+        // Uncapped, the two retries would sleep 500ms + 1s
+        assert!(start.elapsed() < Duration::from_millis(500), "{:?}", start.elapsed());
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_reuse_the_same_idempotency_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let reply = r#"{"title":"t","description":"d","content":"c"}"#;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(reply)))
+            .mount(&server)
+            .await;
+
+        let mut config = mock_config(&server);
+        config.use_idempotency_key = true;
+        let client = DeepSeekClient::new(config).unwrap();
+        client.send_request("hi").await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let keys: Vec<&str> = requests
+            .iter()
+            .map(|r| r.headers.get("idempotency-key").unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(keys[0], keys[1]);
+        assert!(uuid::Uuid::parse_str(keys[0]).is_ok());
+
+        // A new logical request gets a new key
+        client.send_request("again").await.unwrap();
+        let requests = server.received_requests().await.unwrap();
+        let third = requests[2].headers.get("idempotency-key").unwrap();
+        assert_ne!(third.to_str().unwrap(), keys[0]);
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_is_omitted_by_default() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        client.send_messages_raw(user("hi")).await.unwrap();
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests[0].headers.get("idempotency-key").is_none());
+    }
+
+    #[tokio::test]
+    async fn identical_concurrent_requests_share_one_call() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completion_body("{\"n\": 1}"))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let clone = client.clone();
+        let (a, b) = tokio::join!(
+            client.send_messages_raw(user("same prompt")),
+            clone.send_messages_raw(user("same prompt")),
+        );
+        assert_eq!(a.unwrap(), "{\"n\": 1}");
+        assert_eq!(b.unwrap(), "{\"n\": 1}");
+        server.verify().await;
+        assert!(client.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn different_or_sequential_requests_are_not_coalesced() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let (a, b) = tokio::join!(
+            client.send_messages_raw(user("first")),
+            client.send_messages_raw(user("second")),
+        );
+        a.unwrap();
+        b.unwrap();
+        client.send_messages_raw(user("first")).await.unwrap();
+        server.verify().await;
+    }
+
+    fn sent_response_format(request: &wiremock::Request) -> bool {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        body.get("response_format").is_some()
+    }
+
+    #[tokio::test]
+    async fn response_format_is_omitted_when_disabled() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .mount(&server)
+            .await;
+
+        let mut config = mock_config(&server);
+        config.response_format_supported = false;
+        let client = DeepSeekClient::new(config).unwrap();
+        client.send_messages_raw(user("hi")).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(!sent_response_format(&requests[0]));
+    }
+
+    #[tokio::test]
+    async fn reasoner_omits_response_format_when_json_format_for_reasoner_is_off() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .mount(&server)
+            .await;
+
+        for (model, json_format_for_reasoner) in
+            [("deepseek-reasoner", false), ("deepseek-reasoner", true), ("deepseek-chat", false)]
+        {
+            let config = Config {
+                model: model.to_string(),
+                json_format_for_reasoner,
+                ..mock_config(&server)
+            };
+            DeepSeekClient::new(config).unwrap().send_messages_raw(user(model)).await.unwrap();
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let sent: Vec<bool> = requests.iter().map(sent_response_format).collect();
+        assert_eq!(sent, vec![false, true, true]);
+    }
+
+    #[tokio::test]
+    async fn rejected_response_format_falls_back_once_and_is_remembered() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("response_format"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("unknown field response_format"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{}")))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        assert!(client.response_format_supported());
+        client.send_messages_raw(user("first")).await.unwrap();
+        assert!(!client.clone().response_format_supported());
+        client.send_messages_raw(user("second")).await.unwrap();
+
+        server.verify().await;
+        let requests = server.received_requests().await.unwrap();
+        let sent: Vec<bool> = requests.iter().map(sent_response_format).collect();
+        assert_eq!(sent, vec![true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn unrelated_bad_request_keeps_response_format() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("max_tokens is too large"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let err = client.send_messages_raw(user("hi")).await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ApiError { status: 400, .. }), "unexpected error: {:?}", err);
+        assert!(client.response_format_supported());
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn preflight_accepts_json_and_rejects_prose() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("{\"ok\": true}")))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body("Sure! Here you go: ok")))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        client.preflight_json().await.unwrap();
+
+        let err = client.preflight_json().await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+        let msg = err.to_string();
+        assert!(msg.contains("Preflight failed"), "unexpected error: {}", msg);
+        assert!(msg.contains("Sure! Here you go"), "unexpected error: {}", msg);
+    }
+
+    #[tokio::test]
+    async fn list_models_parses_model_ids() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer test_key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    { "id": "deepseek-chat", "object": "model", "owned_by": "deepseek" },
+                    { "id": "deepseek-reasoner", "object": "model", "owned_by": "deepseek" }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let models = client.list_models().await.unwrap();
+        assert_eq!(models, vec!["deepseek-chat", "deepseek-reasoner"]);
+    }
+
+    #[tokio::test]
+    async fn list_models_maps_http_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("bad key"))
+            .mount(&server)
+            .await;
+
+        let client = DeepSeekClient::new(mock_config(&server)).unwrap();
+        let err = client.list_models().await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ApiError { status: 401, .. }), "{:?}", err);
+    }
+}
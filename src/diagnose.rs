@@ -0,0 +1,134 @@
+//! `--diagnose`: explain why a saved raw model response (e.g. `solution.raw.txt`) does not
+//! parse, naming the offending field by its path, without re-running the model.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+use crate::agents::parse::{error_snippet, extract_json};
+use crate::types::{SolutionV1, ValidationV1};
+
+/// Artifact schema a response is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+    Solution,
+    Validation,
+}
+
+impl Schema {
+    pub fn name(self) -> &'static str {
+        match self {
+            Schema::Solution => "SolutionV1",
+            Schema::Validation => "ValidationV1",
+        }
+    }
+
+    fn from_version(version: &str) -> Option<Self> {
+        match version {
+            "solution_v1" => Some(Schema::Solution),
+            "validation_v1" => Some(Schema::Validation),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of checking a response against one schema
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+    /// The response is not a JSON document
+    Syntax { line: usize, column: usize, message: String, snippet: String },
+    Parses(Schema),
+    /// Deserialization failed at `path` (`.` for the document itself)
+    Field { schema: Schema, path: String, message: String },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Syntax { line, column, message, snippet } => write!(
+                f,
+                "not valid JSON at line {}, column {}: {}\n  {}",
+                line, column, message, snippet
+            ),
+            Finding::Parses(schema) => write!(f, "{}: parses", schema.name()),
+            Finding::Field { schema, path, message } => {
+                write!(f, "{}: at `{}`: {}", schema.name(), path, message)
+            }
+        }
+    }
+}
+
+impl Finding {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Finding::Parses(_))
+    }
+}
+
+fn check<T: DeserializeOwned>(json: &str, schema: Schema) -> Finding {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    match serde_path_to_error::deserialize::<_, T>(&mut deserializer) {
+        Ok(_) => Finding::Parses(schema),
+        Err(e) => Finding::Field {
+            schema,
+            path: e.path().to_string(),
+            message: e.inner().to_string(),
+        },
+    }
+}
+
+/// Check `raw` against the schema named by its `schema_version`, or against both artifact
+/// schemas when it names none. A response that is not JSON yields a single `Syntax` finding.
+pub fn diagnose(raw: &str) -> Vec<Finding> {
+    let json = extract_json(raw);
+    let value: serde_json::Value = match serde_json::from_str(json) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![Finding::Syntax {
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+                snippet: error_snippet(json, e.line(), e.column()),
+            }];
+        }
+    };
+    let schemas = match value["schema_version"].as_str().and_then(Schema::from_version) {
+        Some(schema) => vec![schema],
+        None => vec![Schema::Solution, Schema::Validation],
+    };
+    schemas
+        .into_iter()
+        .map(|schema| match schema {
+            Schema::Solution => check::<SolutionV1>(json, schema),
+            Schema::Validation => check::<ValidationV1>(json, schema),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample_solution_json, sample_validation_json};
+
+    #[test]
+    fn missing_and_wrong_typed_fields_are_reported_by_path() {
+        let mut validation: serde_json::Value =
+            serde_json::from_str(&sample_validation_json()).unwrap();
+        validation["checks"][0].as_object_mut().unwrap().remove("reason");
+        let findings = diagnose(&validation.to_string());
+        assert_eq!(findings.len(), 1);
+        let Finding::Field { schema, path, message } = &findings[0] else {
+            panic!("unexpected finding: {:?}", findings[0]);
+        };
+        assert_eq!(*schema, Schema::Validation);
+        assert_eq!(path, "checks[0]");
+        assert!(message.contains("missing field `reason`"), "{}", message);
+
+        let mut solution: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        solution["usage"]["prompt_tokens"] = serde_json::json!("many");
+        let finding = diagnose(&solution.to_string()).remove(0);
+        assert!(finding.to_string().starts_with("SolutionV1: at `usage.prompt_tokens`:"), "{}", finding);
+
+        assert!(diagnose(&sample_solution_json())[0].is_success());
+        assert!(matches!(diagnose("{\"task_id\": ")[0], Finding::Syntax { line: 1, .. }));
+    }
+}
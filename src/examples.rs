@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::orchestrator::SOLUTION_KEY;
+use crate::types::{SolutionV1, TaskSpec};
+
+/// File holding the `TaskSpec` of a few-shot example
+pub const EXAMPLE_TASK_FILE: &str = "task.json";
+
+/// Load few-shot examples from `dir`: every subdirectory holding a `task.json` and a
+/// `solution.json` is one example. Examples are ordered by subdirectory name.
+pub async fn load_examples(dir: &Path) -> Result<Vec<(TaskSpec, SolutionV1)>> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .with_context(|| format!("Failed to read examples directory {}", dir.display()))?;
+    let mut example_dirs = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            example_dirs.push(entry.path());
+        }
+    }
+    example_dirs.sort();
+
+    let mut examples = Vec::with_capacity(example_dirs.len());
+    for example_dir in example_dirs {
+        let task_path = example_dir.join(EXAMPLE_TASK_FILE);
+        let solution_path = example_dir.join(SOLUTION_KEY);
+        let task: TaskSpec = serde_json::from_slice(
+            &tokio::fs::read(&task_path)
+                .await
+                .with_context(|| format!("Failed to read {}", task_path.display()))?,
+        )
+        .with_context(|| format!("Invalid TaskSpec in {}", task_path.display()))?;
+        let solution: SolutionV1 = serde_json::from_slice(
+            &tokio::fs::read(&solution_path)
+                .await
+                .with_context(|| format!("Failed to read {}", solution_path.display()))?,
+        )
+        .with_context(|| format!("Invalid SolutionV1 in {}", solution_path.display()))?;
+        examples.push((task, solution));
+    }
+    Ok(examples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample_solution_json, sample_task};
+
+    #[tokio::test]
+    async fn loads_examples_sorted_by_directory() {
+        let root = std::env::temp_dir().join(format!("examples_{}", uuid::Uuid::new_v4()));
+        for (name, task_id) in [("b", "task-b"), ("a", "task-a")] {
+            let dir = root.join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut task = sample_task();
+            task.task_id = task_id.to_string();
+            std::fs::write(dir.join(EXAMPLE_TASK_FILE), serde_json::to_string(&task).unwrap()).unwrap();
+            std::fs::write(dir.join(SOLUTION_KEY), sample_solution_json()).unwrap();
+        }
+        std::fs::write(root.join("README.md"), "ignored").unwrap();
+
+        let examples = load_examples(&root).await.unwrap();
+        let ids: Vec<&str> = examples.iter().map(|(t, _)| t.task_id.as_str()).collect();
+        assert_eq!(ids, vec!["task-a", "task-b"]);
+
+        std::fs::remove_file(root.join("a").join(SOLUTION_KEY)).unwrap();
+        let err = load_examples(&root).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("solution.json"), "unexpected error: {:#}", err);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
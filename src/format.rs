@@ -0,0 +1,145 @@
+//! `--format-code`: run the language's formatter over a code deliverable before it is saved.
+//! Formatting is best effort: a missing formatter or code it cannot parse leaves the
+//! deliverable as the model wrote it.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::types::SolutionV1;
+
+/// How long a formatter may run before the deliverable is kept as written
+const FORMATTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// External formatter reading source on stdin and writing the formatted source to stdout
+struct Formatter {
+    name: &'static str,
+    program: &'static str,
+    args: &'static [&'static str],
+}
+
+const RUSTFMT: Formatter = Formatter {
+    name: "rustfmt",
+    program: "rustfmt",
+    args: &["--edition", "2024", "--emit", "stdout"],
+};
+
+const BLACK: Formatter = Formatter { name: "black", program: "black", args: &["--quiet", "-"] };
+
+/// Run `formatter` on `content`; `None` when it is not installed, rejects the code or runs
+/// longer than `FORMATTER_TIMEOUT` (it is then killed)
+async fn run_formatter(formatter: &Formatter, content: &str) -> Option<String> {
+    let mut child = Command::new(formatter.program)
+        .args(formatter.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| tracing::warn!("{} is not available: {}", formatter.name, e))
+        .ok()?;
+    let mut stdin = child.stdin.take()?;
+    // Written alongside the read so a large input cannot deadlock against a full stdout pipe
+    let write = async move {
+        let written = stdin.write_all(content.as_bytes()).await;
+        drop(stdin);
+        written
+    };
+    let run = async { tokio::join!(write, child.wait_with_output()) };
+    let (written, output) = match tokio::time::timeout(FORMATTER_TIMEOUT, run).await {
+        Ok((written, output)) => (written.is_ok(), output.ok()?),
+        Err(_) => {
+            tracing::warn!(
+                "{} did not finish within {}s; keeping the code deliverable as written",
+                formatter.name,
+                FORMATTER_TIMEOUT.as_secs()
+            );
+            return None;
+        }
+    };
+    if !output.status.success() || !written {
+        tracing::warn!(
+            "{} could not format the code deliverable: {}",
+            formatter.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// `content` formatted for `language` (`rust`/`rs`, `python`/`py` or `json`), with the name
+/// of the formatter used; `None` for other languages or when formatting fails
+pub async fn format_code(language: &str, content: &str) -> Option<(String, &'static str)> {
+    match language.trim().to_lowercase().as_str() {
+        "rust" | "rs" => run_formatter(&RUSTFMT, content).await.map(|code| (code, RUSTFMT.name)),
+        "python" | "py" => run_formatter(&BLACK, content).await.map(|code| (code, BLACK.name)),
+        "json" => {
+            let value: serde_json::Value = serde_json::from_str(content)
+                .map_err(|e| tracing::warn!("Code deliverable is not valid JSON: {}", e))
+                .ok()?;
+            let pretty = serde_json::to_string_pretty(&value).ok()?;
+            Some((format!("{}\n", pretty), "serde_json"))
+        }
+        _ => None,
+    }
+}
+
+/// Format the solution's code deliverable in place and note it in `evidence.usage_note`.
+/// Returns whether the code was formatted.
+pub async fn format_solution_code(solution: &mut SolutionV1) -> bool {
+    let Some(code) = solution.deliverable.code.as_mut() else {
+        return false;
+    };
+    let Some((content, formatter)) = format_code(&code.language, &code.content).await else {
+        return false;
+    };
+    code.content = content;
+    solution
+        .evidence
+        .prepend_note(&format!("Code formatted with {}", formatter));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_solution_json;
+    use crate::types::CodeArtifact;
+
+    #[tokio::test]
+    #[ignore = "needs rustfmt on PATH"]
+    async fn unformatted_rust_is_formatted_and_invalid_rust_is_left_alone() {
+        let (formatted, formatter) =
+            format_code("Rust", "fn main(){let x=1;println!(\"{}\",x);}").await.unwrap();
+        assert_eq!(formatter, "rustfmt");
+        assert_eq!(formatted, "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n");
+        assert!(format_code("rust", "fn main( {").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn json_is_pretty_printed_and_unknown_languages_are_skipped() {
+        let (formatted, formatter) = format_code("json", "{\"a\":[1,2]}").await.unwrap();
+        assert_eq!(formatter, "serde_json");
+        assert_eq!(formatted, "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n");
+        assert!(format_code("cobol", "DISPLAY 'HI'.").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_or_unsupported_formatter_leaves_the_deliverable_unchanged() {
+        let missing =
+            Formatter { name: "missing", program: "deepseek-agents-no-such-formatter", args: &[] };
+        assert!(run_formatter(&missing, "fn main(){}").await.is_none());
+
+        let mut solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        solution.deliverable.text = None;
+        solution.deliverable.code =
+            Some(CodeArtifact { language: "cobol".to_string(), content: "DISPLAY 'HI'.".to_string() });
+        let note = solution.evidence.usage_note.clone();
+        assert!(!format_solution_code(&mut solution).await);
+        assert_eq!(solution.deliverable.code.unwrap().content, "DISPLAY 'HI'.");
+        assert_eq!(solution.evidence.usage_note, note);
+    }
+}
@@ -0,0 +1,368 @@
+//! `LlmClient`, the model interface the agents depend on, plus fixture recording and
+//! replay for deterministic runs without network access.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::deepseek::{ChatMessage, DeepSeekClient, DeepSeekError, RawCompletion};
+
+/// Chat-completion operations used by the agents
+#[async_trait]
+pub trait LlmClient: Send + Sync {
+    /// Configuration of the model behind this client
+    fn config(&self) -> &Config;
+
+    /// Send chat messages and return the raw assistant content
+    async fn send_messages_raw(&self, messages: Vec<ChatMessage>) -> Result<String, DeepSeekError>;
+
+    /// Send chat messages and return the content with any separate reasoning. Clients
+    /// without reasoning (e.g. fixture replay) return the content alone.
+    async fn send_messages_with_reasoning(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        let content = self.send_messages_raw(messages).await?;
+        Ok(RawCompletion { content, reasoning: None })
+    }
+
+    /// Request `n` alternative completions of `messages`
+    async fn send_messages_candidates(
+        &self,
+        messages: Vec<ChatMessage>,
+        n: u8,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError>;
+
+    /// Stream the assistant content of `messages` as it is generated
+    async fn stream_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<BoxStream<'static, Result<String, DeepSeekError>>, DeepSeekError>;
+}
+
+#[async_trait]
+impl LlmClient for DeepSeekClient {
+    fn config(&self) -> &Config {
+        DeepSeekClient::config(self)
+    }
+
+    async fn send_messages_raw(&self, messages: Vec<ChatMessage>) -> Result<String, DeepSeekError> {
+        DeepSeekClient::send_messages_raw(self, messages).await
+    }
+
+    async fn send_messages_with_reasoning(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        DeepSeekClient::send_messages_with_reasoning(self, messages).await
+    }
+
+    async fn send_messages_candidates(
+        &self,
+        messages: Vec<ChatMessage>,
+        n: u8,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError> {
+        DeepSeekClient::send_messages_candidates(self, messages, n).await
+    }
+
+    async fn stream_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<BoxStream<'static, Result<String, DeepSeekError>>, DeepSeekError> {
+        DeepSeekClient::stream_messages(self, messages).await
+    }
+}
+
+#[async_trait]
+impl<T: LlmClient + ?Sized> LlmClient for Arc<T> {
+    fn config(&self) -> &Config {
+        (**self).config()
+    }
+
+    async fn send_messages_raw(&self, messages: Vec<ChatMessage>) -> Result<String, DeepSeekError> {
+        (**self).send_messages_raw(messages).await
+    }
+
+    async fn send_messages_with_reasoning(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<RawCompletion, DeepSeekError> {
+        (**self).send_messages_with_reasoning(messages).await
+    }
+
+    async fn send_messages_candidates(
+        &self,
+        messages: Vec<ChatMessage>,
+        n: u8,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError> {
+        (**self).send_messages_candidates(messages, n).await
+    }
+
+    async fn stream_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<BoxStream<'static, Result<String, DeepSeekError>>, DeepSeekError> {
+        (**self).stream_messages(messages).await
+    }
+}
+
+/// How fixture files are named
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FixtureKey {
+    /// `0000.json`, `0001.json`, ... in call order across all clients sharing the directory
+    Order,
+    /// `<sha256>.json` of the model name and messages, independent of call order
+    Hash,
+}
+
+/// Record model responses as fixtures, or answer from recorded ones instead of the API
+#[derive(Debug, Clone)]
+pub enum FixtureMode {
+    Record(Arc<FixtureDir>),
+    Replay(Arc<FixtureDir>),
+}
+
+impl FixtureMode {
+    /// `client` wrapped for this mode
+    pub fn client_for(&self, client: &DeepSeekClient) -> Arc<dyn LlmClient> {
+        match self {
+            FixtureMode::Record(fixtures) => {
+                Arc::new(RecordingClient::new(client.clone(), fixtures.clone()))
+            }
+            FixtureMode::Replay(fixtures) => {
+                Arc::new(ReplayClient::new(fixtures.clone(), client.config().clone()))
+            }
+        }
+    }
+}
+
+/// One recorded model call
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    model: String,
+    /// Assistant contents; one per choice
+    completions: Vec<String>,
+}
+
+/// Directory of fixtures shared by the producer and auditor clients of a run
+#[derive(Debug)]
+pub struct FixtureDir {
+    dir: PathBuf,
+    key: FixtureKey,
+    next: AtomicUsize,
+}
+
+impl FixtureDir {
+    pub fn new(dir: impl Into<PathBuf>, key: FixtureKey) -> Self {
+        Self { dir: dir.into(), key, next: AtomicUsize::new(0) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File of the next call with `messages` to `model`; in `Order` mode this claims the next slot
+    fn path_for(&self, model: &str, messages: &[ChatMessage]) -> PathBuf {
+        let name = match self.key {
+            FixtureKey::Order => format!("{:04}.json", self.next.fetch_add(1, Ordering::SeqCst)),
+            FixtureKey::Hash => {
+                // A stable digest, so fixtures recorded by one build replay under another
+                let request =
+                    serde_json::to_vec(&(model, messages)).expect("chat messages serialize");
+                let digest: String =
+                    Sha256::digest(&request).iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("{}.json", digest)
+            }
+        };
+        self.dir.join(name)
+    }
+}
+
+fn fixture_error(path: &Path, problem: impl std::fmt::Display) -> DeepSeekError {
+    DeepSeekError::ConfigError { message: format!("fixture {}: {}", path.display(), problem) }
+}
+
+/// Answers every call from the fixtures in a directory instead of the API
+pub struct ReplayClient {
+    fixtures: Arc<FixtureDir>,
+    config: Config,
+}
+
+impl ReplayClient {
+    /// Replay `fixtures` as the model configured in `config`
+    pub fn new(fixtures: Arc<FixtureDir>, config: Config) -> Self {
+        Self { fixtures, config }
+    }
+
+    async fn replay(&self, messages: &[ChatMessage]) -> Result<Vec<String>, DeepSeekError> {
+        let path = self.fixtures.path_for(&self.config.model, messages);
+        let contents = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| fixture_error(&path, e))?;
+        let fixture: Fixture =
+            serde_json::from_str(&contents).map_err(|e| fixture_error(&path, e))?;
+        if fixture.model != self.config.model {
+            tracing::warn!(
+                "Replaying {} recorded with model {} as {}",
+                path.display(),
+                fixture.model,
+                self.config.model
+            );
+        }
+        if fixture.completions.is_empty() {
+            return Err(fixture_error(&path, "no completions recorded"));
+        }
+        tracing::debug!("Replayed {}", path.display());
+        Ok(fixture.completions)
+    }
+}
+
+#[async_trait]
+impl LlmClient for ReplayClient {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    async fn send_messages_raw(&self, messages: Vec<ChatMessage>) -> Result<String, DeepSeekError> {
+        Ok(self.replay(&messages).await?.swap_remove(0))
+    }
+
+    async fn send_messages_candidates(
+        &self,
+        messages: Vec<ChatMessage>,
+        _n: u8,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError> {
+        let completions = self.replay(&messages).await?;
+        Ok(completions
+            .into_iter()
+            .map(|content| RawCompletion { content, reasoning: None })
+            .collect())
+    }
+
+    async fn stream_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<BoxStream<'static, Result<String, DeepSeekError>>, DeepSeekError> {
+        let content = self.replay(&messages).await?.swap_remove(0);
+        Ok(stream::once(async move { Ok(content) }).boxed())
+    }
+}
+
+/// Forwards every call to the API and saves the responses as fixtures for `ReplayClient`
+pub struct RecordingClient {
+    inner: DeepSeekClient,
+    fixtures: Arc<FixtureDir>,
+}
+
+impl RecordingClient {
+    pub fn new(inner: DeepSeekClient, fixtures: Arc<FixtureDir>) -> Self {
+        Self { inner, fixtures }
+    }
+
+    async fn record(&self, path: &Path, completions: Vec<String>) -> Result<(), DeepSeekError> {
+        let fixture = Fixture { model: self.inner.config().model.clone(), completions };
+        let json = serde_json::to_string_pretty(&fixture).map_err(|e| fixture_error(path, e))?;
+        tokio::fs::create_dir_all(self.fixtures.dir())
+            .await
+            .map_err(|e| fixture_error(path, e))?;
+        tokio::fs::write(path, json).await.map_err(|e| fixture_error(path, e))?;
+        tracing::info!("Recorded fixture {}", path.display());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmClient for RecordingClient {
+    fn config(&self) -> &Config {
+        self.inner.config()
+    }
+
+    async fn send_messages_raw(&self, messages: Vec<ChatMessage>) -> Result<String, DeepSeekError> {
+        let path = self.fixtures.path_for(&self.inner.config().model, &messages);
+        let content = self.inner.send_messages_raw(messages).await?;
+        self.record(&path, vec![content.clone()]).await?;
+        Ok(content)
+    }
+
+    async fn send_messages_candidates(
+        &self,
+        messages: Vec<ChatMessage>,
+        n: u8,
+    ) -> Result<Vec<RawCompletion>, DeepSeekError> {
+        let path = self.fixtures.path_for(&self.inner.config().model, &messages);
+        let completions = self.inner.send_messages_candidates(messages, n).await?;
+        self.record(&path, completions.iter().map(|c| c.content.clone()).collect())
+            .await?;
+        Ok(completions)
+    }
+
+    async fn stream_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<BoxStream<'static, Result<String, DeepSeekError>>, DeepSeekError> {
+        // Recording would mean buffering the whole stream, which is what streaming avoids
+        tracing::warn!("Streamed responses are not recorded as fixtures");
+        self.inner.stream_messages(messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, mount_completion};
+    use wiremock::MockServer;
+
+    fn temp_fixture_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("fixtures_{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn recorded_fixtures_replay_in_order_and_by_hash() {
+        let server = MockServer::start().await;
+        mount_completion(&server, "{\"answer\": 42}").await;
+        let messages = vec![ChatMessage::user("question")];
+
+        for key in [FixtureKey::Order, FixtureKey::Hash] {
+            let dir = temp_fixture_dir();
+            let recorder =
+                RecordingClient::new(mock_client(&server), Arc::new(FixtureDir::new(&dir, key)));
+            assert_eq!(recorder.send_messages_raw(messages.clone()).await.unwrap(), "{\"answer\": 42}");
+            assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+            let replay = ReplayClient::new(
+                Arc::new(FixtureDir::new(&dir, key)),
+                recorder.config().clone(),
+            );
+            assert_eq!(replay.send_messages_raw(messages.clone()).await.unwrap(), "{\"answer\": 42}");
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn hash_keys_do_not_change_between_builds() {
+        let fixtures = FixtureDir::new("fixtures", FixtureKey::Hash);
+        let path = fixtures.path_for("deepseek-chat", &[ChatMessage::user("question")]);
+        assert_eq!(
+            path,
+            Path::new("fixtures")
+                .join("7d10eb9dc5486132e853a78b947a478f434375f1af31d9a66a65c46551d7872b.json")
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_fixture_is_an_error() {
+        let dir = temp_fixture_dir();
+        let replay = ReplayClient::new(
+            Arc::new(FixtureDir::new(&dir, FixtureKey::Order)),
+            Config::default(),
+        );
+        let err = replay.send_messages_raw(vec![ChatMessage::user("q")]).await.unwrap_err();
+        assert!(err.to_string().contains("0000.json"), "{}", err);
+    }
+}
@@ -1,50 +1,517 @@
+mod compare;
 mod config;
 mod deepseek;
+mod diagnose;
+mod examples;
+mod format;
+mod llm;
 mod agents;
+mod align;
+mod batch;
 mod types;
 mod console;
+mod memory;
 mod orchestrator;
+mod pii;
+mod preset;
+mod rate_limit;
+mod report;
+mod review;
+mod scoring;
+mod task_md;
+mod templates;
+mod watch;
+mod storage;
+mod summary;
+mod sweep;
+mod stream;
+#[cfg(test)]
+mod test_support;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use tracing_subscriber::{fmt, EnvFilter};
 
-use crate::config::Config;
-use crate::orchestrator::Orchestrator;
+use crate::config::{Config, JsonIndent};
+use crate::console::{ColorChoice, Console};
+use crate::deepseek::DeepSeekClient;
+use crate::orchestrator::{
+    create_run_dir, Orchestrator, PipelineOptions, SOLUTION_KEY, VALIDATION_KEY,
+};
+use crate::report::{RunOutput, RunSummary, ScoreHistogram};
+use crate::scoring::ScoringKind;
 use crate::types::{DeliverableType, TaskSpec};
 
 #[derive(Debug, Parser)]
 struct Args {
     /// Path to TaskSpec JSON file. If omitted, a demo TaskSpec is used
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["task_md", "template"])]
     task: Option<PathBuf>,
 
+    /// Read the TaskSpec JSON from stdin, e.g. `cat task.json | deepseek_agents --stdin-task-json`
+    #[arg(long, default_value_t = false, conflicts_with_all = ["task", "task_md", "template", "batch", "watch"])]
+    stdin_task_json: bool,
+
+    /// Path to a markdown file with `## Goal`, `## Input`, `## Acceptance Criteria`
+    /// and `## Deliverable Type` sections to import as the TaskSpec
+    #[arg(long, conflicts_with = "template")]
+    task_md: Option<PathBuf>,
+
+    /// Built-in TaskSpec (summarize, code-review, translate); supply the input
+    /// with --input or --input-file
+    #[arg(long, value_name = "NAME", value_parser = templates::parse_template)]
+    template: Option<TaskSpec>,
+
+    /// Override the task's input text
+    #[arg(long, conflicts_with = "input_file")]
+    input: Option<String>,
+
+    /// Override the task's input with the contents of a file
+    #[arg(long)]
+    input_file: Option<PathBuf>,
+
+    /// Largest accepted task input in bytes; larger inputs are an error unless
+    /// --truncate is given
+    #[arg(long, value_name = "BYTES", default_value_t = types::DEFAULT_MAX_INPUT_BYTES)]
+    max_input_bytes: usize,
+
+    /// Trim a task input over --max-input-bytes (marked "[truncated]") instead
+    /// of failing
+    #[arg(long, default_value_t = false)]
+    truncate: bool,
+
+    /// Most acceptance criteria a task may have; more is an error unless
+    /// --truncate-criteria is given
+    #[arg(long, value_name = "N", default_value_t = types::DEFAULT_MAX_CRITERIA)]
+    max_criteria: usize,
+
+    /// Keep only the first --max-criteria acceptance criteria instead of failing
+    #[arg(long, default_value_t = false)]
+    truncate_criteria: bool,
+
+    /// Override the task's goal
+    #[arg(long)]
+    goal: Option<String>,
+
+    /// Override the task's acceptance criteria (repeatable; replaces all of them)
+    #[arg(long = "criterion", value_name = "TEXT")]
+    criteria: Vec<String>,
+
+    /// Override the task's deliverable type (text, json or code)
+    #[arg(long)]
+    deliverable_type: Option<DeliverableType>,
+
+    /// Prior `solution.json` or plain-text draft the producer refines instead
+    /// of starting from scratch
+    #[arg(long)]
+    seed_from_file: Option<PathBuf>,
+
+    /// Directory of few-shot examples: one subdirectory per example holding
+    /// `task.json` and `solution.json` (at most 3 are used)
+    #[arg(long)]
+    examples_dir: Option<PathBuf>,
+
+    /// Embed the TaskSpec in solution.json so the artifact is self-contained
+    #[arg(long, default_value_t = false)]
+    embed_task: bool,
+
+    /// Ask the producer model for N candidate solutions in one request and keep
+    /// the best (same as DEEPSEEK_CANDIDATES)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..))]
+    candidates: Option<u8>,
+
+    /// Save every model response of the run as a fixture in DIR, for --replay-fixtures
+    #[arg(long, value_name = "DIR", conflicts_with = "replay_fixtures")]
+    record_fixtures: Option<PathBuf>,
+
+    /// Answer every model call from the fixtures in DIR instead of the API
+    #[arg(long, value_name = "DIR")]
+    replay_fixtures: Option<PathBuf>,
+
+    /// How fixtures are matched to calls: by call order or by a hash of the messages
+    #[arg(long, value_enum, default_value_t = llm::FixtureKey::Order)]
+    fixture_key: llm::FixtureKey,
+
+    /// Stream a text/code deliverable straight to this file while it is generated
+    /// instead of buffering the whole response (json deliverables are unaffected)
+    #[arg(long, value_name = "PATH")]
+    stream_deliverable: Option<PathBuf>,
+
     /// Output directory for artifacts
     #[arg(long, default_value = "out")] 
     out_dir: PathBuf,
 
+    /// Write the artifacts of this run to a new `<out-dir>/<timestamp>/`
+    /// directory instead of overwriting the previous run
+    #[arg(long, default_value_t = false)]
+    timestamped: bool,
+
+    /// Fail if the output directory does not exist instead of creating it
+    /// (catches path typos)
+    #[arg(long, default_value_t = false)]
+    require_existing_out_dir: bool,
+
     /// Run interactive console to collect a task and execute ProducerAgent
     #[arg(long, default_value_t = false)]
     console_producer: bool,
+
+    /// On a model-response parse failure, save the raw response as
+    /// `solution.raw.txt`/`validation.raw.txt` before reporting the error
+    #[arg(long, default_value_t = false, overrides_with = "no_continue_on_parse_error")]
+    continue_on_parse_error: bool,
+
+    /// Turn off --continue-on-parse-error (e.g. when --lenient turns it on)
+    #[arg(long, default_value_t = false)]
+    no_continue_on_parse_error: bool,
+
+    /// After the audit, prompt to accept, request a revision, or reject
+    /// (exits non-zero). Skipped when stdin is not a terminal
+    #[arg(long, default_value_t = false)]
+    interactive_review: bool,
+
+    /// When a `json` deliverable violates the task's JSON Schema, run one
+    /// revision round before the audit
+    #[arg(long, default_value_t = false, overrides_with = "no_revise_on_schema_violation")]
+    revise_on_schema_violation: bool,
+
+    /// Turn off --revise-on-schema-violation (e.g. when --strict turns it on)
+    #[arg(long, default_value_t = false)]
+    no_revise_on_schema_violation: bool,
+
+    /// Print a single JSON object (solution, validation, summary) to stdout
+    /// instead of the decorative console output; logs go to stderr
+    #[arg(long, default_value_t = false)]
+    json: bool,
+
+    /// Print only the verdict (pass, warn or fail) to stdout and exit with
+    /// 0 for pass, 2 for warn, 3 for fail; everything else is suppressed
+    #[arg(long, default_value_t = false, conflicts_with = "json")]
+    verdict_only: bool,
+
+    /// Run every TaskSpec JSON file in DIR, each into OUT_DIR/<task_id>, recording
+    /// completed tasks in OUT_DIR/batch_checkpoint.txt
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["task", "task_md", "template", "verdict_only", "json", "bundle", "checks_csv"]
+    )]
+    batch: Option<PathBuf>,
+
+    /// Resume an interrupted --batch: skip the tasks its checkpoint records as completed
+    #[arg(long, default_value_t = false, requires = "batch")]
+    resume_batch: bool,
+
+    /// Abort the --batch at the first task that errors or gets a fail verdict,
+    /// cancelling the tasks still running
+    #[arg(long, default_value_t = false, requires = "batch")]
+    fail_fast: bool,
+
+    /// Number of --batch tasks (or --temperature-sweep runs) run at the same time
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    concurrency: u16,
+
+    /// Run the task once per producer temperature from START to END in STEP increments
+    /// (e.g. 0.2,1.0,0.2), each into OUT_DIR/<task_id>-t<temperature>, and report the
+    /// best-scoring temperature. Runs --concurrency at a time
+    #[arg(
+        long,
+        value_name = "START,END,STEP",
+        value_parser = sweep::TemperatureSweep::parse,
+        conflicts_with_all = ["batch", "watch", "verdict_only"]
+    )]
+    temperature_sweep: Option<sweep::TemperatureSweep>,
+
+    /// Keep running: re-run the pipeline each time the --task file changes
+    #[arg(long, default_value_t = false, requires = "task", conflicts_with = "verdict_only")]
+    watch: bool,
+
+    /// Only log warnings and skip the progress output; the final artifacts
+    /// list and verdict are still printed
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
+
+    /// Preset for a demanding run: --deterministic-verdict strict,
+    /// --revise-on-schema-violation and --strict-model. Flags given alongside
+    /// (including the --no-* ones) take precedence
+    #[arg(long, default_value_t = false, conflicts_with = "lenient")]
+    strict: bool,
+
+    /// Preset for a forgiving run: --deterministic-verdict lenient and
+    /// --continue-on-parse-error. Flags given alongside (including the --no-* ones)
+    /// take precedence
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+
+    /// Recompute the audit score and verdict from the checks instead of
+    /// trusting the model (default strategy: strict)
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "strict", value_name = "STRATEGY")]
+    deterministic_verdict: Option<ScoringKind>,
+
+    /// Keep the model's own score and verdict (e.g. when a preset sets --deterministic-verdict)
+    #[arg(long, default_value_t = false, conflicts_with = "deterministic_verdict")]
+    no_deterministic_verdict: bool,
+
+    /// Rename audit checks to the wording of the most similar acceptance criterion
+    /// (similarity threshold in [0.0, 1.0], default 0.5) and flag checks that match none
+    #[arg(long, num_args = 0..=1, value_name = "THRESHOLD", value_parser = parse_threshold)]
+    align_criteria: Option<Option<f32>>,
+
+    /// Number the lines of text/code deliverables for the auditor and have each
+    /// check cite the supporting lines in `evidence_lines`
+    #[arg(long, default_value_t = false)]
+    cite_lines: bool,
+
+    /// Format code deliverables before saving: rustfmt for Rust, black for Python,
+    /// pretty-printing for JSON; skipped when the formatter is missing or fails
+    #[arg(long, default_value_t = false)]
+    format_code: bool,
+
+    /// Add a structured summary of text deliverables (key points, word count) to the
+    /// solution as evidence.summary
+    #[arg(long, default_value_t = false)]
+    with_summary: bool,
+
+    /// Save the auditor's reasoning (reasoning_content, else inline <think> blocks) in
+    /// validation.json as `reasoning`; inline blocks are always stripped before parsing
+    #[arg(long, default_value_t = false)]
+    keep_reasoning: bool,
+
+    /// Also append each validation, timestamped, to OUT_DIR/validations.json so re-audits
+    /// of a solution keep the earlier verdicts (validation.json holds the latest)
+    #[arg(long, default_value_t = false)]
+    append_validations: bool,
+
+    /// Indentation of the saved JSON artifacts: a number of spaces or `tab`
+    /// (same as DEEPSEEK_JSON_INDENT; default 2 spaces)
+    #[arg(long, value_name = "N|tab")]
+    indent: Option<JsonIndent>,
+
+    /// Write the JSON artifacts with all object keys sorted, so identical content
+    /// diffs cleanly across runs (same as DEEPSEEK_SORT_KEYS)
+    #[arg(long, default_value_t = false)]
+    sort_keys: bool,
+
+    /// Scan text/code deliverables for PII (emails, phone numbers, SSNs) and
+    /// note findings in the solution's evidence.usage_note
+    #[arg(long, default_value_t = false)]
+    scan_pii: bool,
+
+    /// Extra PII pattern for the scan as NAME=REGEX (repeatable)
+    #[arg(long = "pii-pattern", value_name = "NAME=REGEX", value_parser = pii::PiiPattern::parse)]
+    pii_patterns: Vec<pii::PiiPattern>,
+
+    /// Fail the run when the PII scan finds anything (implies --scan-pii)
+    #[arg(long, default_value_t = false)]
+    fail_on_pii: bool,
+
+    /// Retry busy/network failures of model requests, at most N retries in total
+    /// across the producer, revision and auditor requests of the run
+    #[arg(long, value_name = "N")]
+    retry_budget: Option<u32>,
+
+    /// On a fail verdict, re-run the producer from scratch and re-audit, up to N more
+    /// times; the best-scoring attempt is kept
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retry_pipeline: u32,
+
+    /// Fail a stage (producer, revision or auditor) that takes longer than SECS in
+    /// total, retries included; the HTTP timeout only bounds a single request
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    agent_timeout: Option<u64>,
+
+    /// Also write the audit checks as CSV (criterion, pass, severity, reason,
+    /// suggested_fix) to this path
+    #[arg(long)]
+    checks_csv: Option<PathBuf>,
+
+    /// Also package the task, solution, validation and run summary (plus a
+    /// manifest) into this zip archive
+    #[arg(long, value_name = "PATH")]
+    bundle: Option<PathBuf>,
+
+    /// Report which field of a saved raw model response (e.g. solution.raw.txt) keeps it
+    /// from parsing as SolutionV1/ValidationV1, and exit
+    #[arg(long, value_name = "RAW_FILE")]
+    diagnose: Option<PathBuf>,
+
+    /// Compare the validation.json of two output directories and exit
+    #[arg(long, num_args = 2, value_names = ["DIR1", "DIR2"])]
+    compare: Option<Vec<PathBuf>>,
+
+    /// Before running, check that the configured models return parseable JSON
+    #[arg(long, default_value_t = false)]
+    preflight: bool,
+
+    /// Fail instead of warning when DEEPSEEK_MODEL is not a known model
+    #[arg(long, default_value_t = false, overrides_with = "no_strict_model")]
+    strict_model: bool,
+
+    /// Only warn about an unknown DEEPSEEK_MODEL (e.g. when --strict turns on --strict-model)
+    #[arg(long, default_value_t = false)]
+    no_strict_model: bool,
+
+    /// Always use the internal HTTP client, bypassing the `deepseek_api` backend
+    /// (same as DEEPSEEK_FORCE_INTERNAL=1)
+    #[arg(long, default_value_t = false)]
+    force_internal: bool,
+
+    /// Print the resolved configuration of the producer and auditor (API key
+    /// masked) and exit without calling the API
+    #[arg(long, default_value_t = false)]
+    print_config: bool,
+
+    /// List the models available to the configured API key and exit
+    #[arg(long, default_value_t = false)]
+    list_models: bool,
+
+    /// Load environment variables from this dotenv file (e.g. `.env.prod`) instead of `.env`
+    #[arg(long, value_name = "PATH")]
+    env_file: Option<PathBuf>,
+
+    /// When to use colored console output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Print long deliverable lines as-is instead of wrapping them to the terminal
+    /// width ($COLUMNS or the tty size, default 100)
+    #[arg(long, default_value_t = false)]
+    no_wrap: bool,
 }
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
-    dotenv::dotenv().ok();
-    let args = Args::parse();
+    let mut args = Args::parse();
+    apply_preset(&mut args);
+    config::load_env_file(args.env_file.as_deref())?;
+    args.color.apply();
+    Console::set_line_wrapping(!args.no_wrap);
 
     // logging
-    let filter_layer = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    fmt().with_env_filter(filter_layer).init();
+    let filter_layer = if args.quiet || args.verdict_only {
+        EnvFilter::new("warn")
+    } else {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
+    };
+    fmt()
+        .with_env_filter(filter_layer)
+        .with_writer(std::io::stderr)
+        .init();
 
     // startup information
     tracing::info!("Starting DeepSeek Agents application");
 
+    // Diagnosis and comparison modes work on saved artifacts only and need no API access
+    if let Some(path) = &args.diagnose {
+        let raw = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let findings = diagnose::diagnose(&raw);
+        for finding in &findings {
+            println!("{}", finding);
+        }
+        if !findings.iter().any(diagnose::Finding::is_success) {
+            anyhow::bail!("{} does not parse as a solution or validation", path.display());
+        }
+        return Ok(());
+    }
+    if let Some(dirs) = &args.compare {
+        let left = compare::load_validation(&dirs[0]).await?;
+        let right = compare::load_validation(&dirs[1]).await?;
+        Console::display_validation_comparison(
+            &dirs[0].display().to_string(),
+            &left,
+            &dirs[1].display().to_string(),
+            &right,
+        );
+        return Ok(());
+    }
+
     // base config from env and orchestrator setup
-    let base_cfg = Config::load()?;
-    let orchestrator = Orchestrator::new(base_cfg)?;
+    let mut base_cfg = Config::load()?;
+    base_cfg.force_internal |= args.force_internal;
+    base_cfg.sort_keys |= args.sort_keys;
+    if args.candidates.is_some() {
+        base_cfg.n = args.candidates;
+    }
+    if let Some(indent) = args.indent {
+        base_cfg.json_indent = indent;
+    }
+    tracing::debug!("Loaded config: {}", base_cfg.redacted_debug());
+    if let Err(e) = base_cfg.check_model() {
+        if args.strict_model {
+            return Err(e.into());
+        }
+        tracing::warn!("{}", e);
+    }
+
+    if args.list_models {
+        let client = DeepSeekClient::new(base_cfg)?;
+        for model in client.list_models().await? {
+            println!("{}", model);
+        }
+        return Ok(());
+    }
+
+    let seed_draft = match &args.seed_from_file {
+        Some(path) => {
+            let contents = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read seed draft {}", path.display()))?;
+            Some(agents::seed_draft_from(&contents))
+        }
+        None => None,
+    };
+
+    let examples = match &args.examples_dir {
+        Some(dir) => examples::load_examples(dir).await?,
+        None => Vec::new(),
+    };
+
+    let orchestrator = Orchestrator::new(base_cfg)?.with_options(PipelineOptions {
+        continue_on_parse_error: args.continue_on_parse_error,
+        interactive_review: args.interactive_review,
+        json_output: args.json || args.verdict_only,
+        quiet: args.quiet || args.verdict_only,
+        require_existing_out_dir: args.require_existing_out_dir,
+        seed_draft,
+        examples,
+        scoring: args.deterministic_verdict,
+        pii_scanner: (args.scan_pii || args.fail_on_pii || !args.pii_patterns.is_empty())
+            .then(|| Arc::new(pii::PiiScanner::with_extra_patterns(args.pii_patterns.clone()))),
+        fail_on_pii: args.fail_on_pii,
+        retry_budget: args.retry_budget,
+        embed_task: args.embed_task,
+        stream_deliverable: args.stream_deliverable.clone(),
+        align_criteria: args
+            .align_criteria
+            .map(|threshold| threshold.unwrap_or(align::DEFAULT_MATCH_THRESHOLD)),
+        cite_lines: args.cite_lines,
+        format_code: args.format_code,
+        with_summary: args.with_summary,
+        append_validations: args.append_validations,
+        keep_reasoning: args.keep_reasoning,
+        retry_pipeline: args.retry_pipeline,
+        input_note: None,
+        agent_timeout: args.agent_timeout.map(std::time::Duration::from_secs),
+        fixtures: match (&args.record_fixtures, &args.replay_fixtures) {
+            (Some(dir), _) => Some(llm::FixtureMode::Record(Arc::new(llm::FixtureDir::new(dir, args.fixture_key)))),
+            (None, Some(dir)) => Some(llm::FixtureMode::Replay(Arc::new(llm::FixtureDir::new(dir, args.fixture_key)))),
+            (None, None) => None,
+        },
+        revise_on_schema_violation: args.revise_on_schema_violation,
+    });
+
+    if args.print_config {
+        println!("{}", orchestrator.resolved_config());
+        return Ok(());
+    }
+
+    if args.preflight {
+        orchestrator.preflight().await?;
+    }
 
     // If console mode is requested, run interactive ProducerAgent flow and exit
     if args.console_producer {
@@ -52,22 +519,319 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    // load or construct TaskSpec
-    tracing::info!("Pipeline mode: ProducerAgent → AuditorAgent");
-    let task_spec: TaskSpec = match &args.task {
-        Some(path) => {
+    if let Some(dir) = &args.batch {
+        return run_batch_mode(orchestrator, &args, dir).await;
+    }
+    if let Some(sweep) = &args.temperature_sweep {
+        return run_sweep_mode(orchestrator, &args, sweep).await;
+    }
+    if args.watch {
+        let path = args.task.clone().expect("--watch requires --task");
+        let changes = watch::TaskWatcher::new(path).changes().await;
+        watch::run_on_changes(changes, || run_task(orchestrator.clone(), &args)).await;
+        return Ok(());
+    }
+    run_task(orchestrator, &args).await
+}
+
+/// Resolve the settings `--strict`/`--lenient` decide: a flag given explicitly, on or
+/// (with its `--no-*` form) off, wins over the preset
+fn apply_preset(args: &mut Args) {
+    let preset = if args.strict {
+        preset::Preset::strict()
+    } else if args.lenient {
+        preset::Preset::lenient()
+    } else {
+        preset::Preset::default()
+    };
+    let resolve = |on: bool, off: bool, preset: bool| on || (!off && preset);
+    args.deterministic_verdict = if args.no_deterministic_verdict {
+        None
+    } else {
+        args.deterministic_verdict.or(preset.deterministic_verdict)
+    };
+    args.revise_on_schema_violation = resolve(
+        args.revise_on_schema_violation,
+        args.no_revise_on_schema_violation,
+        preset.revise_on_schema_violation,
+    );
+    args.continue_on_parse_error = resolve(
+        args.continue_on_parse_error,
+        args.no_continue_on_parse_error,
+        preset.continue_on_parse_error,
+    );
+    args.strict_model = resolve(args.strict_model, args.no_strict_model, preset.strict_model);
+}
+
+/// Load the task from stdin, a template, --task, --task-md or the demo, with the CLI
+/// overrides applied
+async fn load_task(args: &Args) -> Result<TaskSpec> {
+    let mut task_spec: TaskSpec = match (&args.task, &args.task_md, &args.template) {
+        _ if args.stdin_task_json => {
+            tracing::info!("Reading TaskSpec JSON from stdin");
+            TaskSpec::read_json(tokio::io::stdin(), "stdin").await?
+        }
+        (_, _, Some(template)) => {
+            tracing::info!("Using built-in task template");
+            template.clone()
+        }
+        (Some(path), _, _) => {
             tracing::info!("Loading TaskSpec from file: {}", path.display());
             let bytes = tokio::fs::read(path).await?;
             serde_json::from_slice(&bytes)?
         }
-        None => {
+        (None, Some(path), _) => {
+            tracing::info!("Importing TaskSpec from markdown: {}", path.display());
+            let md = tokio::fs::read_to_string(path).await?;
+            task_md::parse_task_markdown(&md)
+                .with_context(|| format!("Invalid task markdown in {}", path.display()))?
+        }
+        (None, None, None) => {
             tracing::info!("No --task provided. Using demo TaskSpec");
             demo_task_spec()
         },
     };
+    apply_task_overrides(&mut task_spec, args).await?;
+    if args.template.is_some() && task_spec.input.trim().is_empty() {
+        anyhow::bail!("--template needs the task input: pass --input or --input-file");
+    }
+    Ok(task_spec)
+}
+
+/// Create OUT_DIR, or with `--require-existing-out-dir` check that it exists, before any
+/// API request is made
+async fn prepare_out_dir(args: &Args) -> Result<()> {
+    if !args.require_existing_out_dir {
+        tokio::fs::create_dir_all(&args.out_dir).await?;
+    } else if !tokio::fs::metadata(&args.out_dir).await.is_ok_and(|m| m.is_dir()) {
+        anyhow::bail!(
+            "Output directory {} does not exist (--require-existing-out-dir)",
+            args.out_dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Load the task, run the pipeline on it and write the requested outputs
+async fn run_task(orchestrator: Orchestrator, args: &Args) -> Result<()> {
+    tracing::info!("Pipeline mode: ProducerAgent → AuditorAgent");
+    let mut task_spec = load_task(args).await?;
+    let input_note = prepare_task(&mut task_spec, args)?;
+    let orchestrator = orchestrator.with_input_note(input_note);
+
+    prepare_out_dir(args).await?;
+    let out_dir = if args.timestamped {
+        let dir = create_run_dir(&args.out_dir, chrono::Utc::now())
+            .await
+            .with_context(|| format!("Failed to create a run directory in {}", args.out_dir.display()))?;
+        tracing::info!("Run directory: {}", dir.display());
+        if !args.json && !args.verdict_only {
+            println!("Run directory: {}", dir.display());
+        }
+        dir
+    } else {
+        args.out_dir.clone()
+    };
+    let result = orchestrator.run_pipeline(task_spec.clone(), &out_dir).await?;
+    let validation = result.validation();
+
+    if let Some(path) = &args.checks_csv {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        report::write_checks_csv(file, validation)?;
+        tracing::info!("Wrote checks CSV to {}", path.display());
+    }
 
-    tokio::fs::create_dir_all(&args.out_dir).await?;
-    let _ = orchestrator.run_pipeline(task_spec, &args.out_dir).await?;
+    let summary = RunSummary::new(
+        validation,
+        &out_dir.join(SOLUTION_KEY),
+        &out_dir.join(VALIDATION_KEY),
+    )
+    .with_billing_tags(orchestrator.config());
+    let output = RunOutput { result: &result, summary: &summary };
+
+    if let Some(path) = &args.bundle {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        report::write_bundle(file, &task_spec, &output)?;
+        tracing::info!("Wrote run bundle to {}", path.display());
+    }
+
+    if args.json {
+        report::write_json_output(&mut std::io::stdout().lock(), &output)?;
+    }
+    if args.verdict_only {
+        report::write_verdict(&mut std::io::stdout().lock(), validation)?;
+        std::process::exit(validation.verdict.exit_code());
+    }
+    Ok(())
+}
+
+/// Run every task of the `--batch` directory, then list the outcomes; fails if any task did
+async fn run_batch_mode(orchestrator: Orchestrator, args: &Args, dir: &Path) -> Result<()> {
+    let tasks = batch::load_batch_tasks(dir).await?;
+    tracing::info!("Batch of {} task(s) from {}", tasks.len(), dir.display());
+    prepare_out_dir(args).await?;
+    let checkpoint = batch::BatchCheckpoint::open(&args.out_dir, args.resume_batch).await?;
+
+    let concurrency = args.concurrency.into();
+    let outcomes = batch::run_batch(tasks, &checkpoint, concurrency, args.fail_fast, |mut task| {
+        let orchestrator = orchestrator.clone();
+        let out_dir = args.out_dir.join(storage::sanitize_filename(&task.task_id));
+        async move {
+            let input_note = prepare_task(&mut task, args)?;
+            orchestrator.with_input_note(input_note).run_pipeline(task, &out_dir).await
+        }
+    })
+    .await;
+
+    let mut failed = 0;
+    let mut scores = Vec::new();
+    for (task_id, outcome) in &outcomes {
+        match outcome {
+            batch::BatchOutcome::Skipped => {
+                println!("{}: skipped (already completed)", task_id);
+                // Completed by an earlier run; its saved validation still counts
+                let dir = args.out_dir.join(storage::sanitize_filename(task_id));
+                if let Ok(validation) = compare::load_validation(&dir).await {
+                    scores.push(validation.score);
+                }
+            }
+            batch::BatchOutcome::Completed(result) => {
+                scores.push(result.validation().score);
+                println!(
+                    "{}: {} (score {:.2})",
+                    task_id,
+                    result.verdict(),
+                    result.validation().score
+                );
+            }
+            batch::BatchOutcome::Failed(e) => {
+                failed += 1;
+                println!("{}: error: {:#}", task_id, e);
+            }
+            batch::BatchOutcome::Aborted => println!("{}: not run (aborted by --fail-fast)", task_id),
+        }
+    }
+    if let Some(histogram) = ScoreHistogram::from_scores(&scores) {
+        println!("\nScores of {} task(s):\n{}", scores.len(), histogram.render());
+    }
+    if args.fail_fast
+        && let Some((task_id, _)) = outcomes.iter().find(|(_, outcome)| outcome.is_failure())
+    {
+        anyhow::bail!("Batch aborted: task {} failed (--fail-fast)", task_id);
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} batch task(s) failed; rerun with --resume-batch to retry them",
+            failed,
+            outcomes.len()
+        );
+    }
+    Ok(())
+}
+
+/// Run the task at each temperature of `sweep` through the batch runner and print the
+/// temperature → score table
+async fn run_sweep_mode(
+    orchestrator: Orchestrator,
+    args: &Args,
+    sweep: &sweep::TemperatureSweep,
+) -> Result<()> {
+    let mut task = load_task(args).await?;
+    let input_note = prepare_task(&mut task, args)?;
+    let orchestrator = orchestrator.with_input_note(input_note);
+    prepare_out_dir(args).await?;
+    let temperatures = sweep.values();
+    tracing::info!("Temperature sweep of task {} over {:?}", task.task_id, temperatures);
+
+    // One batch entry per temperature, told apart by its run id
+    let runs: Vec<TaskSpec> = temperatures
+        .iter()
+        .map(|&temperature| TaskSpec { task_id: sweep::run_id(&task.task_id, temperature), ..task.clone() })
+        .collect();
+    let temperature_of: std::collections::HashMap<String, f32> = runs
+        .iter()
+        .map(|run| run.task_id.clone())
+        .zip(temperatures.iter().copied())
+        .collect();
+    // Not persisted: a sweep is not resumable, and OUT_DIR may hold a batch's checkpoint
+    let checkpoint = batch::BatchCheckpoint::in_memory();
+    let outcomes = batch::run_batch(runs, &checkpoint, args.concurrency.into(), false, |run| {
+        let orchestrator = orchestrator.clone().with_producer_temperature(temperature_of[&run.task_id]);
+        let out_dir = args.out_dir.join(storage::sanitize_filename(&run.task_id));
+        let task = TaskSpec { task_id: task.task_id.clone(), ..run };
+        async move { orchestrator.run_pipeline(task, &out_dir).await }
+    })
+    .await;
+
+    let mut rows = Vec::with_capacity(outcomes.len());
+    for ((run_id, outcome), temperature) in outcomes.iter().zip(temperatures) {
+        let score = match outcome {
+            batch::BatchOutcome::Completed(result) => Some(result.validation().score),
+            batch::BatchOutcome::Failed(e) => {
+                println!("{}: error: {:#}", run_id, e);
+                None
+            }
+            batch::BatchOutcome::Skipped | batch::BatchOutcome::Aborted => None,
+        };
+        rows.push((temperature, score));
+    }
+    println!("\n{}", sweep::render_table(&rows));
+    if rows.iter().all(|(_, score)| score.is_none()) {
+        anyhow::bail!("Every run of the temperature sweep failed");
+    }
+    Ok(())
+}
+
+/// De-duplicate the criteria and enforce --max-criteria and --max-input-bytes; returns the
+/// truncation notes
+fn prepare_task(task: &mut TaskSpec, args: &Args) -> Result<Option<String>> {
+    let duplicates = task.dedupe_criteria();
+    if duplicates > 0 {
+        tracing::warn!("Removed {} duplicate acceptance criteria", duplicates);
+    }
+    let criteria_note = task.limit_criteria(args.max_criteria, args.truncate_criteria)?;
+    if let Some(note) = &criteria_note {
+        tracing::warn!("{} (--max-criteria {})", note, args.max_criteria);
+    }
+    let input_note = task.limit_input(args.max_input_bytes, args.truncate)?;
+    if let Some(note) = &input_note {
+        tracing::warn!("{} (--max-input-bytes {})", note, args.max_input_bytes);
+    }
+    Ok(match (criteria_note, input_note) {
+        (Some(criteria), Some(input)) => Some(format!("{}; {}", criteria, input)),
+        (criteria, input) => criteria.or(input),
+    })
+}
+
+/// `--align-criteria` value: a similarity in [0.0, 1.0]
+fn parse_threshold(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(t) if (0.0..=1.0).contains(&t) => Ok(t),
+        _ => Err(format!("expected a number between 0.0 and 1.0, got '{}'", value)),
+    }
+}
+
+/// Apply the --input/--input-file, --goal, --criterion and --deliverable-type overrides
+async fn apply_task_overrides(task: &mut TaskSpec, args: &Args) -> Result<()> {
+    if let Some(input) = &args.input {
+        task.input = input.clone();
+    }
+    if let Some(path) = &args.input_file {
+        task.input = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read input file {}", path.display()))?;
+    }
+    if let Some(goal) = &args.goal {
+        task.goal = goal.clone();
+    }
+    if !args.criteria.is_empty() {
+        task.acceptance_criteria = args.criteria.clone();
+    }
+    if let Some(deliverable_type) = &args.deliverable_type {
+        task.deliverable_type = deliverable_type.clone();
+    }
     Ok(())
 }
 
@@ -83,5 +847,60 @@ fn demo_task_spec() -> TaskSpec {
         ],
         deliverable_type: DeliverableType::Text,
         hints: Some("Be concise".to_string()),
+        json_schema: None,
+        deadline_ms: None,
+        scoring_guidance: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(flags: &[&str]) -> Args {
+        let mut args = Args::try_parse_from(std::iter::once("deepseek_agents").chain(flags.iter().copied()))
+            .unwrap();
+        apply_preset(&mut args);
+        args
+    }
+
+    #[test]
+    fn presets_set_their_flags_and_explicit_flags_win() {
+        let strict = resolved(&["--strict"]);
+        assert_eq!(strict.deterministic_verdict, Some(ScoringKind::Strict));
+        assert!(strict.revise_on_schema_violation && strict.strict_model);
+        assert!(!strict.continue_on_parse_error);
+
+        let lenient = resolved(&["--lenient"]);
+        assert_eq!(lenient.deterministic_verdict, Some(ScoringKind::Lenient));
+        assert!(lenient.continue_on_parse_error);
+        assert!(!lenient.revise_on_schema_violation && !lenient.strict_model);
+
+        let overridden = resolved(&[
+            "--strict",
+            "--deterministic-verdict",
+            "weighted",
+            "--no-strict-model",
+            "--no-revise-on-schema-violation",
+        ]);
+        assert_eq!(overridden.deterministic_verdict, Some(ScoringKind::Weighted));
+        assert!(!overridden.strict_model && !overridden.revise_on_schema_violation);
+
+        let overridden = resolved(&["--lenient", "--no-deterministic-verdict", "--no-continue-on-parse-error"]);
+        assert_eq!(overridden.deterministic_verdict, None);
+        assert!(!overridden.continue_on_parse_error);
+
+        // Without a preset the flags keep their usual meaning
+        let plain = resolved(&["--strict-model"]);
+        assert!(plain.strict_model && plain.deterministic_verdict.is_none());
+    }
+
+    #[test]
+    fn batch_rejects_single_run_outputs() {
+        assert!(Args::try_parse_from(["deepseek_agents", "--batch", "tasks"]).is_ok());
+        for flag in [&["--json"][..], &["--bundle", "run.zip"], &["--checks-csv", "checks.csv"]] {
+            let flags = ["deepseek_agents", "--batch", "tasks"].iter().chain(flag);
+            assert!(Args::try_parse_from(flags).is_err(), "{:?} was accepted with --batch", flag);
+        }
     }
 }
@@ -0,0 +1,99 @@
+use crate::types::{SolutionV1, ValidationV1, Verdict};
+
+/// Compact record of one audited attempt
+#[derive(Debug, Clone)]
+pub struct AttemptSummary {
+    pub solution_id: String,
+    pub verdict: Verdict,
+    pub score: f32,
+    /// Criteria of the failing checks
+    pub failed_criteria: Vec<String>,
+}
+
+/// Accumulates what earlier produce → audit rounds got wrong, so a revision can be told
+/// about them without re-sending every previous solution and validation
+#[derive(Debug, Clone, Default)]
+pub struct PipelineMemory {
+    attempts: Vec<AttemptSummary>,
+}
+
+impl PipelineMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember an audited attempt
+    pub fn record(&mut self, solution: &SolutionV1, validation: &ValidationV1) {
+        self.attempts.push(AttemptSummary {
+            solution_id: solution.solution_id.clone(),
+            verdict: validation.verdict.clone(),
+            score: validation.score,
+            failed_criteria: validation
+                .checks
+                .iter()
+                .filter(|c| !c.pass_)
+                .map(|c| c.criterion.clone())
+                .collect(),
+        });
+    }
+
+    /// One line per attempt, e.g. `Attempt 1 (sol-1): fail, score 0.40; failed: a; b`.
+    /// `None` while nothing has been recorded.
+    pub fn summary(&self) -> Option<String> {
+        if self.attempts.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = self
+            .attempts
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let failed = if a.failed_criteria.is_empty() {
+                    "none".to_string()
+                } else {
+                    a.failed_criteria.join("; ")
+                };
+                format!(
+                    "Attempt {} ({}): {}, score {:.2}; failed: {}",
+                    i + 1,
+                    a.solution_id,
+                    a.verdict,
+                    a.score,
+                    failed
+                )
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample_solution_json, sample_validation_json};
+
+    #[test]
+    fn summary_lists_attempts_and_failed_checks() {
+        let mut memory = PipelineMemory::new();
+        assert!(memory.summary().is_none());
+
+        let mut solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        let mut validation: ValidationV1 = serde_json::from_str(&sample_validation_json()).unwrap();
+        validation.verdict = Verdict::Fail;
+        validation.score = 0.4;
+        validation.checks[0].pass_ = false;
+        memory.record(&solution, &validation);
+
+        solution.solution_id = "sol-2".to_string();
+        validation.verdict = Verdict::Pass;
+        validation.score = 0.95;
+        validation.checks[0].pass_ = true;
+        memory.record(&solution, &validation);
+
+        assert_eq!(
+            memory.summary().unwrap(),
+            "Attempt 1 (sol-1): fail, score 0.40; failed: exactly 3 bullets\n\
+             Attempt 2 (sol-2): pass, score 0.95; failed: none"
+        );
+    }
+}
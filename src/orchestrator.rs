@@ -1,78 +1,411 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::Result;
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use tracing::info;
 
-use crate::agents::{Agent, AuditInput, AuditorAgent, ProducerAgent};
+use crate::agents::{
+    Agent, AuditInput, AuditorAgent, ProducerAgent, RevisionAgent, RevisionInput, StreamTarget,
+};
 use crate::config::Config;
 use crate::console::Console;
 use crate::deepseek::DeepSeekClient;
-use crate::types::{SolutionV1, TaskSpec, ValidationV1};
+use crate::llm::{FixtureMode, LlmClient};
+use crate::memory::PipelineMemory;
+use crate::pii::{describe_findings, PiiScanner};
+use crate::rate_limit::RetryBudget;
+use crate::report::{PipelineResult, RunReport};
+use crate::review::{ask_decision, ConsoleChoices, ReviewState};
+use crate::scoring::ScoringKind;
+use crate::storage::{sanitize_filename, ArtifactStore, FsStore};
+use crate::types::{
+    CheckResult, ModelUsed, Severity, SolutionV1, TaskSpec, ValidationV1, Verdict,
+};
+
+/// Artifact key of the ProducerAgent output
+pub const SOLUTION_KEY: &str = "solution.json";
+/// Artifact key of the AuditorAgent output
+pub const VALIDATION_KEY: &str = "validation.json";
+/// Audit trail of every validation of the run's solutions (`--append-validations`)
+pub const VALIDATIONS_KEY: &str = "validations.json";
 
+/// The pipeline did not finish within the task's `deadline_ms`
+#[derive(Debug, thiserror::Error)]
+#[error("task {task_id} exceeded its deadline of {deadline_ms} ms{}", partial_note(.partial_solution))]
+pub struct DeadlineExceeded {
+    pub task_id: String,
+    pub deadline_ms: u64,
+    /// The latest solution produced before the deadline, not (fully) audited
+    pub partial_solution: Option<Box<SolutionV1>>,
+}
+
+fn partial_note(partial_solution: &Option<Box<SolutionV1>>) -> String {
+    match partial_solution {
+        Some(solution) => format!(
+            "; unaudited solution {} was saved to {}",
+            solution.solution_id, SOLUTION_KEY
+        ),
+        None => " before a solution was produced".to_string(),
+    }
+}
+
+/// Create a fresh `<base>/<timestamp>` directory for one run (`--timestamped`).
+/// The timestamp is RFC3339 passed through `sanitize_filename`, so `:` becomes `-`;
+/// a numeric suffix is added if the directory already exists. `base` itself must exist.
+pub async fn create_run_dir(base: &Path, started_at: DateTime<Utc>) -> std::io::Result<PathBuf> {
+    let stamp = sanitize_filename(&started_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string());
+    let mut candidate = base.join(&stamp);
+    for n in 1.. {
+        match tokio::fs::create_dir(&candidate).await {
+            Ok(()) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                candidate = base.join(format!("{}-{}", stamp, n));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(candidate)
+}
+
+/// Behavior toggles for pipeline runs, usually populated from CLI flags
+#[derive(Debug, Clone, Default)]
+pub struct PipelineOptions {
+    /// Save unparseable model responses as `*.raw.txt` next to the artifacts
+    pub continue_on_parse_error: bool,
+    /// Let the user accept, revise or reject the audited solution
+    pub interactive_review: bool,
+    /// Machine-readable mode: skip decorative console rendering so stdout carries only JSON
+    pub json_output: bool,
+    /// Suppress progress rendering (task, solution, validation) and console status lines;
+    /// the final artifacts list and verdict banner are still printed
+    pub quiet: bool,
+    /// Fail instead of creating the output directory when it does not exist
+    pub require_existing_out_dir: bool,
+    /// Run one revision round before auditing when a `Json` deliverable violates the task's `json_schema`
+    pub revise_on_schema_violation: bool,
+    /// Draft the producer refines instead of starting from scratch (`--seed-from-file`)
+    pub seed_draft: Option<String>,
+    /// Few-shot `(task, solution)` examples for the producer (`--examples-dir`)
+    pub examples: Vec<(TaskSpec, SolutionV1)>,
+    /// Recompute the auditor's score and verdict with this strategy (`--deterministic-verdict`)
+    pub scoring: Option<ScoringKind>,
+    /// Scan text/code deliverables for PII after producing (`--scan-pii`)
+    pub pii_scanner: Option<Arc<PiiScanner>>,
+    /// Abort the run when the PII scan finds anything
+    pub fail_on_pii: bool,
+    /// Total retries of transient request failures allowed across all stages (`--retry-budget`)
+    pub retry_budget: Option<u32>,
+    /// Embed the `TaskSpec` in the saved solution (`--embed-task`)
+    pub embed_task: bool,
+    /// Stream `text`/`code` deliverables to this file as they are generated (`--stream-deliverable`)
+    pub stream_deliverable: Option<PathBuf>,
+    /// Upper bound on each agent's whole run, retries included (`--agent-timeout`)
+    pub agent_timeout: Option<Duration>,
+    /// Record model responses as fixtures or replay recorded ones (`--record-fixtures`/`--replay-fixtures`)
+    pub fixtures: Option<FixtureMode>,
+    /// Align audit checks with the task's criteria at this similarity threshold (`--align-criteria`)
+    pub align_criteria: Option<f32>,
+    /// Have the auditor cite supporting deliverable lines in each check (`--cite-lines`)
+    pub cite_lines: bool,
+    /// Format code deliverables with the language's formatter before saving (`--format-code`)
+    pub format_code: bool,
+    /// Keep the auditor's inline `<think>` reasoning in the validation (`--keep-reasoning`)
+    pub keep_reasoning: bool,
+    /// Append every validation to `validations.json` as well as saving `validation.json`
+    /// (`--append-validations`)
+    pub append_validations: bool,
+    /// Add a JSON summary of text deliverables to the solution's evidence (`--with-summary`)
+    pub with_summary: bool,
+    /// Re-run the whole pipeline from scratch up to this many times while the verdict is `Fail`
+    /// (`--retry-pipeline`)
+    pub retry_pipeline: u32,
+    /// Set when the task input was truncated (`--truncate`); recorded in the solution's evidence
+    pub input_note: Option<String>,
+}
+
+#[derive(Clone)]
 pub struct Orchestrator {
     chat_client: DeepSeekClient,
     reasoner_client: DeepSeekClient,
+    options: PipelineOptions,
 }
 
 impl Orchestrator {
     pub fn new(base_cfg: Config) -> Result<Self> {
-        let chat_client = DeepSeekClient::new(base_cfg.clone())?;
-
-        let mut reasoner_cfg = base_cfg;
-        reasoner_cfg.model = "deepseek-reasoner".to_string();
-        let reasoner_client = DeepSeekClient::new(reasoner_cfg)?;
+        let auditor_temperature = base_cfg.auditor_temperature();
+        let chat_client = DeepSeekClient::new(base_cfg)?;
+        let reasoner_client = chat_client
+            .clone_with_model("deepseek-reasoner")?
+            .with_temperature(auditor_temperature);
 
         Ok(Self {
             chat_client,
             reasoner_client,
+            options: PipelineOptions::default(),
         })
     }
 
+    /// Replace the pipeline options
+    pub fn with_options(mut self, options: PipelineOptions) -> Self {
+        if let Some(max_retries) = options.retry_budget {
+            let budget = Arc::new(RetryBudget::new(max_retries));
+            self.chat_client = self.chat_client.with_retry_budget(budget.clone());
+            self.reasoner_client = self.reasoner_client.with_retry_budget(budget);
+        }
+        self.options = options;
+        self
+    }
+
+    /// Run the producer at `temperature` instead of the configured one; the auditor keeps its own
+    pub fn with_producer_temperature(mut self, temperature: f32) -> Self {
+        self.chat_client = self.chat_client.with_temperature(temperature);
+        self
+    }
+
+    /// Record how the task input was prepared (e.g. truncated) in the solution's evidence
+    pub fn with_input_note(mut self, note: Option<String>) -> Self {
+        self.options.input_note = note;
+        self
+    }
+
+    /// Configuration of the producer client, which the auditor's is derived from
+    pub fn config(&self) -> &Config {
+        self.chat_client.config()
+    }
+
+    /// Effective configuration of the producer and auditor clients, API key masked
+    pub fn resolved_config(&self) -> String {
+        format!(
+            "producer ({:?} backend): {}\nauditor ({:?} backend): {}",
+            self.chat_client.backend_kind(),
+            self.chat_client.config().redacted_debug(),
+            self.reasoner_client.backend_kind(),
+            self.reasoner_client.config().redacted_debug()
+        )
+    }
+
+    /// Check that both the producer and the auditor models answer with parseable JSON
+    pub async fn preflight(&self) -> Result<()> {
+        for client in [&self.chat_client, &self.reasoner_client] {
+            info!("Preflight: checking JSON output of model {}", client.config().model);
+            client.preflight_json().await?;
+        }
+        Ok(())
+    }
+
+    /// What the agents talk to: `client`, unless fixtures are being recorded or replayed
+    fn llm(&self, client: &DeepSeekClient) -> Arc<dyn LlmClient> {
+        match &self.options.fixtures {
+            Some(mode) => mode.client_for(client),
+            None => Arc::new(client.clone()),
+        }
+    }
+
+    /// Run a decorative console rendering step unless output is machine-readable
+    fn show(&self, render: impl FnOnce()) {
+        if !self.options.json_output {
+            render();
+        }
+    }
+
+    /// Run a progress rendering step unless output is machine-readable or quiet
+    fn show_progress(&self, render: impl FnOnce()) {
+        if !self.options.quiet {
+            self.show(render);
+        }
+    }
+
     pub async fn run_console_producer(&self, out_dir: &Path) -> Result<()> {
         info!(
             "Interactive mode: you'll be prompted to enter a task for the ProducerAgent, which will process it and save the result"
         );
-        let console = Console::new(self.chat_client.clone());
+        let console = Console::new(self.chat_client.clone())
+            .with_quiet(self.options.quiet)
+            .with_require_existing_out_dir(self.options.require_existing_out_dir);
         console.run_producer_agent(out_dir).await
     }
 
+    /// Produce and audit a solution for `task_spec`, saving both artifacts in `out_dir`.
+    /// With `retry_pipeline`, a `Fail` verdict starts a fresh attempt; the best result
+    /// (a non-failing verdict first, then the higher score) is returned and left on disk.
+    /// A task with `deadline_ms` fails with `DeadlineExceeded` when the run takes longer,
+    /// carrying the solution produced so far.
     pub async fn run_pipeline(
         &self,
         task_spec: TaskSpec,
         out_dir: &Path,
-    ) -> Result<(SolutionV1, ValidationV1)> {
+    ) -> Result<PipelineResult> {
+        let produced = std::sync::Mutex::new(None);
+        let Some(deadline_ms) = task_spec.deadline_ms else {
+            return self.run_attempts(task_spec, out_dir, &produced).await;
+        };
+        let task_id = task_spec.task_id.clone();
+        let run = self.run_attempts(task_spec, out_dir, &produced);
+        match tokio::time::timeout(Duration::from_millis(deadline_ms), run).await {
+            Ok(result) => result,
+            Err(_) => {
+                let partial_solution = produced.lock().expect("produced solution lock").take();
+                Err(DeadlineExceeded { task_id, deadline_ms, partial_solution }.into())
+            }
+        }
+    }
+
+    /// `run_pipeline` without the deadline. Each produced solution is put in `produced`
+    /// before it is audited.
+    async fn run_attempts(
+        &self,
+        task_spec: TaskSpec,
+        out_dir: &Path,
+        produced: &std::sync::Mutex<Option<Box<SolutionV1>>>,
+    ) -> Result<PipelineResult> {
+        let attempts = self.options.retry_pipeline + 1;
+        let mut best = self.run_pipeline_once(task_spec.clone(), out_dir, produced).await?;
+        let mut best_is_saved = true;
+        for attempt in 2..=attempts {
+            if !matches!(best.validation.verdict, Verdict::Fail) {
+                break;
+            }
+            info!(
+                "Verdict fail (score {:.2}); re-running the pipeline from scratch (attempt {} of {})",
+                best.validation.score,
+                attempt,
+                attempts
+            );
+            let result = self.run_pipeline_once(task_spec.clone(), out_dir, produced).await?;
+            // The latest attempt's artifacts are the ones on disk now
+            best_is_saved = rank(&result) > rank(&best);
+            if best_is_saved {
+                best = result;
+            }
+        }
+        if !best_is_saved {
+            info!(
+                "Keeping the best attempt, solution {} (score {:.2})",
+                best.solution.solution_id,
+                best.validation.score
+            );
+            let config = self.chat_client.config();
+            let store = self.artifact_store(out_dir);
+            store
+                .save(SOLUTION_KEY, &config.artifact_json(&best.solution)?)
+                .await?;
+            store
+                .save(VALIDATION_KEY, &config.artifact_json(&best.validation)?)
+                .await?;
+        }
+        Ok(best)
+    }
+
+    /// Store for the artifacts written under `root`, honouring `artifact_mode` and
+    /// `--require-existing-out-dir`
+    fn artifact_store(&self, root: &Path) -> FsStore {
+        FsStore::new(root)
+            .with_require_existing_root(self.options.require_existing_out_dir)
+            .with_mode(self.chat_client.config().artifact_mode)
+    }
+
+    /// `--stream-deliverable` target: `path`'s file in a store rooted at its directory
+    fn stream_target(&self, path: &Path) -> StreamTarget {
+        let root = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        StreamTarget {
+            store: Arc::new(self.artifact_store(root)),
+            key: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        }
+    }
+
+    async fn run_pipeline_once(
+        &self,
+        task_spec: TaskSpec,
+        out_dir: &Path,
+        produced: &std::sync::Mutex<Option<Box<SolutionV1>>>,
+    ) -> Result<PipelineResult> {
         info!("Pipeline mode: ProducerAgent → AuditorAgent");
+        let started_at = Utc::now();
+        let run_start = Instant::now();
 
-        tokio::fs::create_dir_all(out_dir).await?;
-        let solution_path: PathBuf = out_dir.join("solution.json");
-        let validation_path: PathBuf = out_dir.join("validation.json");
+        if !self.options.require_existing_out_dir {
+            tokio::fs::create_dir_all(out_dir).await?;
+        }
+        let store = self.artifact_store(out_dir);
+        // Fail before the producer's request rather than when its result is saved
+        store.check_root().await.map_err(crate::agents::AgentError::from)?;
+        let store: Arc<dyn ArtifactStore> = Arc::new(store);
+        let solution_path: PathBuf = out_dir.join(SOLUTION_KEY);
+        let validation_path: PathBuf = out_dir.join(VALIDATION_KEY);
 
-        let agent1 = ProducerAgent::new(self.chat_client.clone(), solution_path.clone());
+        let agent1 = ProducerAgent::new(self.llm(&self.chat_client), store.clone(), SOLUTION_KEY)
+            .with_save_raw_on_parse_error(self.options.continue_on_parse_error)
+            .with_seed_draft(self.options.seed_draft.clone())
+            .with_examples(self.options.examples.clone())
+            .with_embed_task(self.options.embed_task)
+            .with_stream_to(self.options.stream_deliverable.as_deref().map(|path| self.stream_target(path)))
+            .with_input_note(self.options.input_note.clone())
+            .with_format_code(self.options.format_code)
+            .with_summary(self.options.with_summary)
+            .with_execute_timeout(self.options.agent_timeout);
         info!(
             "Agent1 (Producer): received task_id={} — processing",
             task_spec.task_id
         );
-        Console::display_task(&task_spec);
-        let solution = agent1.execute(&task_spec).await?;
-        let solution_for_return = solution.clone();
+        self.show_progress(|| Console::display_task(&task_spec));
+        let producer_start = Instant::now();
+        let mut solution = agent1.execute(&task_spec).await?;
+        if self.options.revise_on_schema_violation && !solution.evidence.schema_violations.is_empty() {
+            info!(
+                "Agent1 solution {} violates json_schema — requesting a revision",
+                solution.solution_id
+            );
+            let reviser = RevisionAgent::new(self.llm(&self.chat_client), store.clone(), SOLUTION_KEY)
+                .with_save_raw_on_parse_error(self.options.continue_on_parse_error)
+                .with_embed_task(self.options.embed_task)
+                .with_summary(self.options.with_summary)
+                .with_execute_timeout(self.options.agent_timeout);
+            let validation = schema_violation_feedback(&solution);
+            solution = reviser
+                .execute(&RevisionInput {
+                    task: task_spec.clone(),
+                    solution,
+                    validation,
+                    prior_attempts: None,
+                })
+                .await?;
+        }
+        if let Some(scanner) = &self.options.pii_scanner {
+            self.scan_pii(scanner, &mut solution, store.as_ref()).await?;
+        }
+        let producer_ms = producer_start.elapsed().as_millis() as u64;
         info!("Agent1 produced solution: {}", solution.solution_id);
         info!(
             "Agent1 saved solution to {}",
             solution_path.display()
         );
-        Console::display_solution(&solution);
+        self.show_progress(|| Console::display_solution(&solution));
+        *produced.lock().expect("produced solution lock") = Some(Box::new(solution.clone()));
 
-        let agent2 = AuditorAgent::new(self.reasoner_client.clone(), validation_path.clone());
+        let agent2 = AuditorAgent::new(self.llm(&self.reasoner_client), store.clone(), VALIDATION_KEY)
+            .with_save_raw_on_parse_error(self.options.continue_on_parse_error)
+            .with_scoring(self.options.scoring.map(ScoringKind::strategy))
+            .with_criterion_alignment(self.options.align_criteria)
+            .with_cite_lines(self.options.cite_lines)
+            .with_keep_reasoning(self.options.keep_reasoning)
+            .with_history_key(self.options.append_validations.then(|| VALIDATIONS_KEY.to_string()))
+            .with_execute_timeout(self.options.agent_timeout);
         info!(
             "Agent2 (Auditor): received solution {} from Agent1 — processing",
             solution.solution_id
         );
+        let auditor_start = Instant::now();
         let validation = agent2
             .execute(&AuditInput {
-                task: task_spec,
-                solution,
+                task: task_spec.clone(),
+                solution: solution.clone(),
             })
             .await?;
+        let auditor_ms = auditor_start.elapsed().as_millis() as u64;
         info!(
             "Agent2 verdict: {} (score {:.2})",
             validation.verdict,
@@ -82,16 +415,649 @@ impl Orchestrator {
             "Agent2 saved validation to {}",
             validation_path.display()
         );
-        Console::display_validation(&validation);
+        self.show_progress(|| Console::display_validation(&validation));
 
-        println!(
-            "Artifacts:\n  {}\n  {}",
-            solution_path.display(),
-            validation_path.display()
+        let (solution, validation) = if self.options.interactive_review {
+            self.interactive_review(&task_spec, solution, validation, &agent2, store)
+                .await?
+        } else {
+            (solution, validation)
+        };
+
+        self.show(|| {
+            println!(
+                "Artifacts:\n  {}\n  {}",
+                solution_path.display(),
+                validation_path.display()
+            );
+            Console::display_verdict_banner(&validation);
+        });
+
+        let report = RunReport {
+            started_at: started_at.to_rfc3339(),
+            finished_at: Utc::now().to_rfc3339(),
+            producer_ms,
+            auditor_ms,
+            total_ms: run_start.elapsed().as_millis() as u64,
+        };
+        info!(
+            "Pipeline finished in {} ms (producer {} ms, auditor {} ms)",
+            report.total_ms,
+            report.producer_ms,
+            report.auditor_ms
         );
 
-        Ok((solution_for_return, validation))
+        Ok(PipelineResult { solution, validation, report })
+    }
+
+    /// Record PII findings in `evidence.usage_note` (re-saving the solution) and fail if configured to
+    async fn scan_pii(
+        &self,
+        scanner: &PiiScanner,
+        solution: &mut SolutionV1,
+        store: &dyn ArtifactStore,
+    ) -> Result<()> {
+        let findings = scanner.scan_deliverable(&solution.deliverable);
+        if findings.is_empty() {
+            return Ok(());
+        }
+        let description = describe_findings(&findings);
+        tracing::warn!("Solution {}: {}", solution.solution_id, description);
+        solution.evidence.usage_note = Some(match solution.evidence.usage_note.take() {
+            Some(note) if !note.trim().is_empty() => format!("{}; {}", note, description),
+            _ => description.clone(),
+        });
+        store
+            .save(SOLUTION_KEY, &self.chat_client.config().artifact_json(&*solution)?)
+            .await?;
+        if self.options.fail_on_pii {
+            bail!("{} in solution {}", description, solution.solution_id);
+        }
+        Ok(())
+    }
+
+    /// Human-in-the-loop step: accept, request a revision (revise and re-audit), or reject
+    async fn interactive_review(
+        &self,
+        task: &TaskSpec,
+        mut solution: SolutionV1,
+        mut validation: ValidationV1,
+        auditor: &AuditorAgent,
+        store: Arc<dyn ArtifactStore>,
+    ) -> Result<(SolutionV1, ValidationV1)> {
+        if !std::io::stdin().is_terminal() {
+            tracing::warn!("Skipping interactive review: stdin is not a terminal");
+            return Ok((solution, validation));
+        }
+
+        let reviser = RevisionAgent::new(self.llm(&self.chat_client), store, SOLUTION_KEY)
+            .with_save_raw_on_parse_error(self.options.continue_on_parse_error)
+            .with_embed_task(self.options.embed_task)
+            .with_summary(self.options.with_summary)
+            .with_execute_timeout(self.options.agent_timeout);
+        let mut choices = ConsoleChoices;
+        let mut memory = PipelineMemory::new();
+        let mut state = ReviewState::start();
+        while !state.is_final() {
+            state = state.on_decision(ask_decision(&mut choices).await?);
+            if let ReviewState::Revising { round } = state {
+                info!("Reviewer requested revision {} of solution {}", round, solution.solution_id);
+                // The latest attempt is sent in full; older ones only as a summary
+                let prior_attempts = memory.summary();
+                memory.record(&solution, &validation);
+                solution = reviser
+                    .execute(&RevisionInput {
+                        task: task.clone(),
+                        solution,
+                        validation,
+                        prior_attempts,
+                    })
+                    .await?;
+                self.show_progress(|| Console::display_solution(&solution));
+                validation = auditor
+                    .execute(&AuditInput {
+                        task: task.clone(),
+                        solution: solution.clone(),
+                    })
+                    .await?;
+                self.show_progress(|| Console::display_validation(&validation));
+                state = state.on_revised();
+            }
+        }
+
+        if state == ReviewState::Rejected {
+            bail!("Solution {} rejected during interactive review", solution.solution_id);
+        }
+        Ok((solution, validation))
     }
 }
 
+/// Synthetic validation turning json_schema violations into failing checks for the RevisionAgent
+fn schema_violation_feedback(solution: &SolutionV1) -> ValidationV1 {
+    ValidationV1 {
+        schema_version: "validation_v1".to_string(),
+        task_id: solution.task_id.clone(),
+        solution_id: solution.solution_id.clone(),
+        verdict: Verdict::Fail,
+        score: 0.0,
+        checks: solution
+            .evidence
+            .schema_violations
+            .iter()
+            .map(|violation| CheckResult {
+                criterion: "deliverable.json conforms to task_spec.json_schema".to_string(),
+                pass_: false,
+                reason: violation.clone(),
+                severity: Severity::Major,
+                suggested_fix: None,
+                partial_score: None,
+                unmatched: false,
+                evidence_lines: None,
+            })
+            .collect(),
+        suggested_rewrite: None,
+        model_used: ModelUsed {
+            name: "jsonschema".to_string(),
+            temperature: 0.0,
+        },
+        created_at: Utc::now().to_rfc3339(),
+        reasoning: None,
+    }
+}
+
+/// Order of pipeline attempts for `--retry-pipeline`: any non-failing verdict beats a
+/// failing one, then the higher score wins
+fn rank(result: &PipelineResult) -> (bool, f32) {
+    (
+        !matches!(result.validation.verdict, Verdict::Fail),
+        result.validation.score,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        completion_body, mock_config, sample_solution_json, sample_task, sample_validation_json,
+    };
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Mock server answering the producer (chat) and auditor (reasoner) models
+    async fn pipeline_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-reasoner" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion_body(&sample_validation_json())),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion_body(&sample_solution_json())),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
 
+    fn temp_out_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("pipeline_{}", uuid::Uuid::new_v4()))
+    }
+
+    fn config() -> Config {
+        Config {
+            api_key: "k".to_string(),
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn run_dirs_are_unique_and_filesystem_safe() {
+        let base = temp_out_dir();
+        std::fs::create_dir_all(&base).unwrap();
+        let started_at = DateTime::parse_from_rfc3339("2024-05-06T07:08:09.010Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let first = create_run_dir(&base, started_at).await.unwrap();
+        let second = create_run_dir(&base, started_at).await.unwrap();
+        assert_eq!(first, base.join("2024-05-06T07-08-09.010Z"));
+        assert_eq!(second, base.join("2024-05-06T07-08-09.010Z-1"));
+        assert!(first.is_dir() && second.is_dir());
+        assert!(!first.file_name().unwrap().to_string_lossy().contains(':'));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn auditor_gets_low_temperature_by_default() {
+        let orchestrator = Orchestrator::new(config()).unwrap();
+        assert_eq!(orchestrator.reasoner_client.config().temperature, 0.0);
+        assert_eq!(
+            orchestrator.chat_client.config().temperature,
+            Config::default().temperature
+        );
+    }
+
+    #[test]
+    fn auditor_temperature_is_overridable() {
+        let cfg = Config {
+            auditor_temperature: Some(0.4),
+            ..config()
+        };
+        let orchestrator = Orchestrator::new(cfg).unwrap();
+        assert_eq!(orchestrator.reasoner_client.config().temperature, 0.4);
+    }
+
+    #[test]
+    fn resolved_config_shows_both_clients_without_the_key() {
+        let cfg = Config {
+            api_key: "sk-very-secret-key-1234".to_string(),
+            base_url: "https://gateway.example.com/v1".to_string(),
+            ..config()
+        };
+        let printed = Orchestrator::new(cfg).unwrap().resolved_config();
+
+        assert!(printed.contains("model: \"deepseek-chat\""), "{}", printed);
+        assert!(printed.contains("model: \"deepseek-reasoner\""), "{}", printed);
+        assert!(printed.contains("base_url: \"https://gateway.example.com/v1\""), "{}", printed);
+        assert!(!printed.contains("sk-very-secret-key-1234"), "{}", printed);
+        assert!(printed.contains("sk-...1234"), "{}", printed);
+        assert!(printed.starts_with("producer (Internal backend): Config {"), "{}", printed);
+    }
+
+    #[test]
+    fn quiet_skips_progress_but_keeps_final_output() {
+        let orchestrator = Orchestrator::new(config()).unwrap().with_options(PipelineOptions {
+            quiet: true,
+            ..PipelineOptions::default()
+        });
+        let (mut progress, mut fin) = (false, false);
+        orchestrator.show_progress(|| progress = true);
+        orchestrator.show(|| fin = true);
+        assert!(!progress);
+        assert!(fin);
+    }
+
+    #[tokio::test]
+    async fn pipeline_runs_entirely_off_replayed_fixtures() {
+        use crate::llm::{FixtureDir, FixtureKey};
+
+        let fixtures = temp_out_dir().join("fixtures");
+        // Record one run against the mock API...
+        let server = pipeline_server().await;
+        let recorder = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                fixtures: Some(FixtureMode::Record(Arc::new(FixtureDir::new(&fixtures, FixtureKey::Order)))),
+                ..PipelineOptions::default()
+            });
+        let recorded_dir = temp_out_dir();
+        let recorded = recorder.run_pipeline(sample_task(), &recorded_dir).await.unwrap().solution;
+        assert!(fixtures.join("0000.json").exists() && fixtures.join("0001.json").exists());
+        drop(server);
+
+        // ...then replay it with no server at all
+        let replayer = Orchestrator::new(config()).unwrap().with_options(PipelineOptions {
+            json_output: true,
+            fixtures: Some(FixtureMode::Replay(Arc::new(FixtureDir::new(&fixtures, FixtureKey::Order)))),
+            ..PipelineOptions::default()
+        });
+        let out_dir = temp_out_dir();
+        let PipelineResult { solution, validation, .. } = replayer.run_pipeline(sample_task(), &out_dir).await.unwrap();
+        assert_eq!(solution.solution_id, recorded.solution_id);
+        assert_eq!(solution.deliverable.text, recorded.deliverable.text);
+        assert!(matches!(validation.verdict, crate::types::Verdict::Pass));
+
+        for dir in [fixtures.parent().unwrap(), &recorded_dir, &out_dir] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_pipeline_reruns_after_a_fail_and_keeps_the_passing_attempt() {
+        let server = MockServer::start().await;
+        let mut failing: serde_json::Value = serde_json::from_str(&sample_validation_json()).unwrap();
+        failing["verdict"] = json!("fail");
+        failing["score"] = json!(0.2);
+        failing["checks"][0]["pass"] = json!(false);
+        let mut second: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        second["solution_id"] = json!("sol-2");
+        for (model, body) in [
+            ("deepseek-reasoner", failing.to_string()),
+            ("deepseek-chat", sample_solution_json()),
+        ] {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({ "model": model })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(&body)))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        for (model, body) in [
+            ("deepseek-reasoner", sample_validation_json()),
+            ("deepseek-chat", second.to_string()),
+        ] {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({ "model": model })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(&body)))
+                .mount(&server)
+                .await;
+        }
+
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                retry_pipeline: 3,
+                ..PipelineOptions::default()
+            });
+        let out_dir = temp_out_dir();
+        let result = orchestrator.run_pipeline(sample_task(), &out_dir).await.unwrap();
+
+        assert!(matches!(result.validation.verdict, Verdict::Pass));
+        assert_eq!(result.solution.solution_id, "sol-2");
+        // One failed attempt, one passing attempt, then no more
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+        let saved: SolutionV1 =
+            serde_json::from_str(&std::fs::read_to_string(out_dir.join(SOLUTION_KEY)).unwrap()).unwrap();
+        assert_eq!(saved.solution_id, "sol-2");
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[test]
+    fn pipeline_attempts_rank_non_failing_verdicts_first() {
+        let result = |verdict: &str, score: f32| {
+            let mut validation: serde_json::Value =
+                serde_json::from_str(&sample_validation_json()).unwrap();
+            validation["verdict"] = json!(verdict);
+            validation["score"] = json!(score);
+            PipelineResult {
+                solution: serde_json::from_str(&sample_solution_json()).unwrap(),
+                validation: serde_json::from_value(validation).unwrap(),
+                report: RunReport {
+                    started_at: String::new(),
+                    finished_at: String::new(),
+                    producer_ms: 0,
+                    auditor_ms: 0,
+                    total_ms: 0,
+                },
+            }
+        };
+        assert!(rank(&result("warn", 0.5)) > rank(&result("fail", 0.9)));
+        assert!(rank(&result("fail", 0.6)) > rank(&result("fail", 0.4)));
+    }
+
+    #[tokio::test]
+    async fn task_read_from_a_stream_runs_the_pipeline() {
+        let server = pipeline_server().await;
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                ..PipelineOptions::default()
+            });
+        let out_dir = temp_out_dir();
+        let json = serde_json::to_vec(&sample_task()).unwrap();
+
+        let task = TaskSpec::read_json(json.as_slice(), "stdin").await.unwrap();
+        let result = orchestrator.run_pipeline(task, &out_dir).await.unwrap();
+        assert_eq!(result.solution.solution_id, "sol-1");
+        assert!(out_dir.join(VALIDATION_KEY).exists());
+
+        let err = TaskSpec::read_json(&b" \n"[..], "stdin").await.unwrap_err();
+        assert_eq!(err.to_string(), "no TaskSpec JSON on stdin");
+        let err = TaskSpec::read_json(&b"{\"goal\": 1}"[..], "stdin").await.unwrap_err();
+        assert!(err.to_string().starts_with("invalid TaskSpec JSON on stdin"), "{}", err);
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn run_pipeline_reports_timings() {
+        let server = pipeline_server().await;
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                ..PipelineOptions::default()
+            });
+        let out_dir = temp_out_dir();
+
+        let PipelineResult { solution, validation, report } =
+            orchestrator.run_pipeline(sample_task(), &out_dir).await.unwrap();
+        assert_eq!(solution.solution_id, "sol-1");
+        assert!(matches!(validation.verdict, crate::types::Verdict::Pass));
+
+        let started = chrono::DateTime::parse_from_rfc3339(&report.started_at).unwrap();
+        let finished = chrono::DateTime::parse_from_rfc3339(&report.finished_at).unwrap();
+        assert!(finished >= started);
+        assert!(report.total_ms >= report.producer_ms + report.auditor_ms);
+        assert!(out_dir.join(SOLUTION_KEY).exists());
+        assert!(out_dir.join(VALIDATION_KEY).exists());
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn deadline_exceeded_by_a_slow_auditor_returns_the_unaudited_solution() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-reasoner" })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completion_body(&sample_validation_json()))
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion_body(&sample_solution_json())),
+            )
+            .mount(&server)
+            .await;
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                ..PipelineOptions::default()
+            });
+        let out_dir = temp_out_dir();
+        let task = TaskSpec { deadline_ms: Some(500), ..sample_task() };
+
+        let err = orchestrator.run_pipeline(task, &out_dir).await.unwrap_err();
+        let exceeded = err.downcast_ref::<DeadlineExceeded>().expect("deadline error");
+        assert_eq!(exceeded.deadline_ms, 500);
+        assert_eq!(exceeded.partial_solution.as_ref().unwrap().solution_id, "sol-1");
+        assert!(
+            err.to_string().contains("exceeded its deadline of 500 ms; unaudited solution sol-1"),
+            "{}",
+            err
+        );
+        assert!(out_dir.join(SOLUTION_KEY).exists());
+        assert!(!out_dir.join(VALIDATION_KEY).exists());
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn retry_budget_spent_by_producer_leaves_none_for_auditor() {
+        let server = MockServer::start().await;
+        // The producer's first attempt fails, its retry succeeds; the auditor always fails
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion_body(&sample_solution_json())),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-reasoner" })))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                retry_budget: Some(1),
+                ..PipelineOptions::default()
+            });
+        let out_dir = temp_out_dir();
+
+        let err = orchestrator.run_pipeline(sample_task(), &out_dir).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("busy"), "unexpected error: {:#}", err);
+        assert!(out_dir.join(SOLUTION_KEY).exists());
+        server.verify().await;
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn missing_out_dir_is_created_unless_required() {
+        let server = pipeline_server().await;
+        let out_dir = temp_out_dir();
+
+        let strict = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                require_existing_out_dir: true,
+                ..PipelineOptions::default()
+            });
+        let err = strict.run_pipeline(sample_task(), &out_dir).await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<crate::agents::AgentError>(),
+                Some(crate::agents::AgentError::Io(_))
+            ),
+            "unexpected error: {:#}",
+            err
+        );
+        assert!(!out_dir.exists());
+        assert!(server.received_requests().await.unwrap().is_empty(), "no request may be paid for");
+
+        let lenient = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                ..PipelineOptions::default()
+            });
+        lenient.run_pipeline(sample_task(), &out_dir).await.unwrap();
+        assert!(out_dir.join(SOLUTION_KEY).exists());
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn pii_findings_are_noted_and_can_fail_the_run() {
+        let server = MockServer::start().await;
+        let mut leaky: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        leaky["deliverable"] = json!({ "text": "- mail jane@example.com\n- b\n- c" });
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(&leaky.to_string())))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-reasoner" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion_body(&sample_validation_json())),
+            )
+            .mount(&server)
+            .await;
+        let options = PipelineOptions {
+            json_output: true,
+            pii_scanner: Some(Arc::new(PiiScanner::default())),
+            ..PipelineOptions::default()
+        };
+
+        let out_dir = temp_out_dir();
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(options.clone());
+        let solution = orchestrator.run_pipeline(sample_task(), &out_dir).await.unwrap().solution;
+        assert_eq!(solution.evidence.usage_note.as_deref(), Some("PII detected: email ×1"));
+        let saved = std::fs::read_to_string(out_dir.join(SOLUTION_KEY)).unwrap();
+        assert!(saved.contains("PII detected: email ×1"));
+
+        let strict = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions { fail_on_pii: true, ..options });
+        let err = strict.run_pipeline(sample_task(), &out_dir).await.unwrap_err();
+        assert!(err.to_string().contains("PII detected: email ×1"), "unexpected error: {}", err);
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+
+    #[tokio::test]
+    async fn schema_violation_triggers_one_revision() {
+        let server = MockServer::start().await;
+        let mut invalid: serde_json::Value = serde_json::from_str(&sample_solution_json()).unwrap();
+        invalid["deliverable_type"] = json!("json");
+        invalid["deliverable"] = json!({ "json": { "count": "three" } });
+        let mut revised = invalid.clone();
+        revised["solution_id"] = json!("sol-2");
+        revised["deliverable"] = json!({ "json": { "count": 3 } });
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-reasoner" })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion_body(&sample_validation_json())),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(&invalid.to_string())))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(&revised.to_string())))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut task = sample_task();
+        task.deliverable_type = crate::types::DeliverableType::Json;
+        task.json_schema = Some(json!({
+            "type": "object",
+            "properties": { "count": { "type": "integer" } }
+        }));
+        let orchestrator = Orchestrator::new(mock_config(&server))
+            .unwrap()
+            .with_options(PipelineOptions {
+                json_output: true,
+                revise_on_schema_violation: true,
+                ..PipelineOptions::default()
+            });
+        let out_dir = temp_out_dir();
+
+        let solution = orchestrator.run_pipeline(task, &out_dir).await.unwrap().solution;
+        assert_eq!(solution.solution_id, "sol-2");
+        assert!(solution.evidence.schema_violations.is_empty());
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+}
@@ -0,0 +1,155 @@
+use regex::Regex;
+
+use crate::types::Deliverable;
+
+/// Built-in patterns: `(name, regex)`
+const DEFAULT_PATTERNS: &[(&str, &str)] = &[
+    ("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+    ("phone", r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]\d{3}[ .-]\d{4}\b"),
+    ("ssn", r"\b\d{3}-\d{2}-\d{4}\b"),
+];
+
+/// Named PII pattern
+#[derive(Debug, Clone)]
+pub struct PiiPattern {
+    pub name: String,
+    regex: Regex,
+}
+
+impl PiiPattern {
+    pub fn new(name: impl Into<String>, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            name: name.into(),
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// Parse a `NAME=REGEX` pattern definition (e.g. from the command line)
+    pub fn parse(definition: &str) -> Result<Self, String> {
+        let (name, pattern) = definition
+            .split_once('=')
+            .filter(|(name, pattern)| !name.trim().is_empty() && !pattern.is_empty())
+            .ok_or_else(|| format!("expected NAME=REGEX, got '{}'", definition))?;
+        Self::new(name.trim(), pattern).map_err(|e| format!("invalid regex for '{}': {}", name.trim(), e))
+    }
+}
+
+/// Matches of one pattern in a deliverable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiFinding {
+    pub kind: String,
+    pub count: usize,
+}
+
+/// Regex-based scan of text/code deliverables for obvious PII (no API call)
+#[derive(Debug, Clone)]
+pub struct PiiScanner {
+    patterns: Vec<PiiPattern>,
+}
+
+impl Default for PiiScanner {
+    fn default() -> Self {
+        let patterns = DEFAULT_PATTERNS
+            .iter()
+            .map(|(name, pattern)| PiiPattern::new(*name, pattern).expect("valid built-in PII pattern"))
+            .collect();
+        Self { patterns }
+    }
+}
+
+impl PiiScanner {
+    /// Built-in patterns (email, phone, ssn) plus `extra`
+    pub fn with_extra_patterns(extra: Vec<PiiPattern>) -> Self {
+        let mut scanner = Self::default();
+        scanner.patterns.extend(extra);
+        scanner
+    }
+
+    /// Count matches per pattern in `text`; patterns without matches are omitted
+    pub fn scan(&self, text: &str) -> Vec<PiiFinding> {
+        self.patterns
+            .iter()
+            .filter_map(|p| {
+                let count = p.regex.find_iter(text).count();
+                (count > 0).then(|| PiiFinding { kind: p.name.clone(), count })
+            })
+            .collect()
+    }
+
+    /// Scan the text and code parts of a deliverable
+    pub fn scan_deliverable(&self, deliverable: &Deliverable) -> Vec<PiiFinding> {
+        let mut content = String::new();
+        if let Some(text) = &deliverable.text {
+            content.push_str(text);
+            content.push('\n');
+        }
+        if let Some(code) = &deliverable.code {
+            content.push_str(&code.content);
+        }
+        self.scan(&content)
+    }
+}
+
+/// One-line description of findings, without the matched values: `PII detected: email ×2, ssn ×1`
+pub fn describe_findings(findings: &[PiiFinding]) -> String {
+    let parts: Vec<String> = findings
+        .iter()
+        .map(|f| format!("{} ×{}", f.kind, f.count))
+        .collect();
+    format!("PII detected: {}", parts.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CodeArtifact;
+
+    fn text(s: &str) -> Deliverable {
+        Deliverable { text: Some(s.to_string()), json: None, code: None }
+    }
+
+    #[test]
+    fn detects_builtin_pii_kinds() {
+        let scanner = PiiScanner::default();
+        let findings = scanner.scan_deliverable(&text(
+            "Contact jane.doe@example.com or bob@corp.io, call (555) 123-4567; SSN 123-45-6789.",
+        ));
+        assert_eq!(
+            findings,
+            vec![
+                PiiFinding { kind: "email".to_string(), count: 2 },
+                PiiFinding { kind: "phone".to_string(), count: 1 },
+                PiiFinding { kind: "ssn".to_string(), count: 1 },
+            ]
+        );
+        assert_eq!(describe_findings(&findings), "PII detected: email ×2, phone ×1, ssn ×1");
+    }
+
+    #[test]
+    fn clean_deliverables_have_no_findings() {
+        let scanner = PiiScanner::default();
+        assert!(scanner
+            .scan_deliverable(&text("- 3 bullets\n- released 2024-01-01\n- version 1.2.3"))
+            .is_empty());
+        let code = Deliverable {
+            text: None,
+            json: None,
+            code: Some(CodeArtifact {
+                language: "rs".to_string(),
+                content: "let x = 12345678;".to_string(),
+            }),
+        };
+        assert!(scanner.scan_deliverable(&code).is_empty());
+    }
+
+    #[test]
+    fn extra_patterns_are_configurable() {
+        let pattern = PiiPattern::parse(r"api_key=sk-[A-Za-z0-9]{8,}").unwrap();
+        let scanner = PiiScanner::with_extra_patterns(vec![pattern]);
+        let findings = scanner.scan("token sk-abcdef123456");
+        assert_eq!(findings, vec![PiiFinding { kind: "api_key".to_string(), count: 1 }]);
+
+        assert!(PiiPattern::parse("no-equals-sign").is_err());
+        assert!(PiiPattern::parse("bad=(").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! `--strict` / `--lenient`: presets bundling the flags that decide how forgiving a run is.
+//! Flags given alongside a preset still apply on top of it.
+
+use crate::scoring::ScoringKind;
+
+/// The settings a strictness preset decides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Preset {
+    /// `--deterministic-verdict`: grade from the checks instead of trusting the model
+    pub deterministic_verdict: Option<ScoringKind>,
+    /// `--revise-on-schema-violation`: hold `json` deliverables to the task's JSON Schema
+    pub revise_on_schema_violation: bool,
+    /// `--continue-on-parse-error`: keep unparseable responses for inspection
+    pub continue_on_parse_error: bool,
+    /// `--strict-model`: an unknown model is an error rather than a warning
+    pub strict_model: bool,
+}
+
+impl Preset {
+    /// `--strict`: verdicts computed from the checks with the strict strategy, schema
+    /// violations revised, and an unknown model fails the run
+    pub fn strict() -> Self {
+        Self {
+            deterministic_verdict: Some(ScoringKind::Strict),
+            revise_on_schema_violation: true,
+            continue_on_parse_error: false,
+            strict_model: true,
+        }
+    }
+
+    /// `--lenient`: verdicts computed from the checks with the lenient strategy, no schema
+    /// revision, unparseable responses saved, and an unknown model only warns
+    pub fn lenient() -> Self {
+        Self {
+            deterministic_verdict: Some(ScoringKind::Lenient),
+            revise_on_schema_violation: false,
+            continue_on_parse_error: true,
+            strict_model: false,
+        }
+    }
+}
+
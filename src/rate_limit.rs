@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Sliding-window limiter: at most `max_requests` acquisitions per `window`.
+/// Wrap in an `Arc` to share it between clients.
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_requests: usize,
+    window: Duration,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            max_requests: max_requests.max(1) as usize,
+            window,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Limiter allowing `requests_per_minute` requests in any rolling minute
+    pub fn per_minute(requests_per_minute: u32) -> Self {
+        Self::new(requests_per_minute, Duration::from_secs(60))
+    }
+
+    /// Wait until a request may be sent, then record it
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut sent = self.sent.lock().await;
+                let now = Instant::now();
+                while sent
+                    .front()
+                    .is_some_and(|t| now.duration_since(*t) >= self.window)
+                {
+                    sent.pop_front();
+                }
+                if sent.len() < self.max_requests {
+                    sent.push_back(now);
+                    return;
+                }
+                // Oldest entry leaves the window first
+                self.window - now.duration_since(sent[0])
+            };
+            tracing::debug!("Rate limit reached, waiting {:?} before sending", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Shared "cooldown until" instant: once the server signals overload, every
+/// request sent through the owning clients waits until it has passed
+#[derive(Debug)]
+pub struct Cooldown {
+    origin: Instant,
+    /// Milliseconds after `origin` until which requests must wait
+    until_ms: AtomicU64,
+}
+
+impl Default for Cooldown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cooldown {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            until_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.origin.elapsed().as_millis() as u64
+    }
+
+    /// Extend the cooldown to at least `duration` from now
+    pub fn trigger(&self, duration: Duration) {
+        let until = self.now_ms() + duration.as_millis() as u64;
+        self.until_ms.fetch_max(until, Ordering::SeqCst);
+    }
+
+    /// Time left before requests may be sent again
+    pub fn remaining(&self) -> Option<Duration> {
+        let until = self.until_ms.load(Ordering::SeqCst);
+        let now = self.now_ms();
+        (until > now).then(|| Duration::from_millis(until - now))
+    }
+
+    /// Wait until the cooldown (possibly extended meanwhile) has passed
+    pub async fn wait(&self) {
+        while let Some(remaining) = self.remaining() {
+            tracing::debug!("Server asked to back off, waiting {:?}", remaining);
+            tokio::time::sleep(remaining).await;
+        }
+    }
+}
+
+/// Cap on retries across a whole run, shared by every client it is attached to so that
+/// retries in one stage leave fewer for the next
+#[derive(Debug)]
+pub struct RetryBudget {
+    remaining: AtomicU32,
+}
+
+impl RetryBudget {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            remaining: AtomicU32::new(max_retries),
+        }
+    }
+
+    /// Take one retry from the budget; false once it is exhausted
+    pub fn try_consume(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Retries still available
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_acquires_are_paced_to_the_rate() {
+        let limiter = Arc::new(RateLimiter::per_minute(2));
+        let start = Instant::now();
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire().await;
+                    start.elapsed()
+                })
+            })
+            .collect();
+
+        let mut elapsed = Vec::new();
+        for h in handles {
+            elapsed.push(h.await.unwrap().as_secs());
+        }
+        elapsed.sort();
+        assert_eq!(elapsed, vec![0, 0, 60, 60, 120]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cooldown_delays_until_the_latest_trigger() {
+        let cooldown = Cooldown::new();
+        assert!(cooldown.remaining().is_none());
+
+        cooldown.trigger(Duration::from_secs(5));
+        cooldown.trigger(Duration::from_secs(2));
+        let start = Instant::now();
+        cooldown.wait().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert!(cooldown.remaining().is_none());
+    }
+
+    #[test]
+    fn retry_budget_stops_at_zero() {
+        let budget = RetryBudget::new(2);
+        assert!(budget.try_consume());
+        assert!(budget.try_consume());
+        assert!(!budget.try_consume());
+        assert_eq!(budget.remaining(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acquires_within_limit_do_not_wait() {
+        let limiter = RateLimiter::new(3, Duration::from_secs(1));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,435 @@
+use std::io::{Seek, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config::Config;
+use crate::types::{Severity, SolutionV1, TaskSpec, ValidationV1, Verdict};
+
+/// Name of the manifest entry of a `--bundle` archive
+pub const BUNDLE_MANIFEST: &str = "manifest.json";
+/// Artifact entries of a `--bundle` archive, in archive order
+pub const BUNDLE_ENTRIES: &[&str] = &["task.json", "solution.json", "validation.json", "summary.json"];
+
+/// Compact outcome of a pipeline run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub verdict: Verdict,
+    pub score: f32,
+    pub failed_checks: usize,
+    pub total_checks: usize,
+    pub solution_path: String,
+    pub validation_path: String,
+    /// Billing tags of the run (`Config::organization`/`project`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+impl RunSummary {
+    pub fn new(validation: &ValidationV1, solution_path: &Path, validation_path: &Path) -> Self {
+        Self {
+            verdict: validation.verdict.clone(),
+            score: validation.score,
+            failed_checks: validation.checks.iter().filter(|c| !c.pass_).count(),
+            total_checks: validation.checks.len(),
+            solution_path: solution_path.display().to_string(),
+            validation_path: validation_path.display().to_string(),
+            organization: None,
+            project: None,
+        }
+    }
+
+    /// Record the organization/project `config` bills the run to
+    pub fn with_billing_tags(mut self, config: &Config) -> Self {
+        self.organization = config.organization.clone();
+        self.project = config.project.clone();
+        self
+    }
+}
+
+/// Timing of a pipeline run, for latency/SLO tracking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    /// RFC3339
+    pub started_at: String,
+    /// RFC3339
+    pub finished_at: String,
+    pub producer_ms: u64,
+    pub auditor_ms: u64,
+    pub total_ms: u64,
+}
+
+/// Outcome of a pipeline run: the final artifacts and the run's timings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineResult {
+    pub solution: SolutionV1,
+    pub validation: ValidationV1,
+    pub report: RunReport,
+}
+
+impl PipelineResult {
+    pub fn solution(&self) -> &SolutionV1 {
+        &self.solution
+    }
+
+    pub fn validation(&self) -> &ValidationV1 {
+        &self.validation
+    }
+
+    pub fn report(&self) -> &RunReport {
+        &self.report
+    }
+
+    pub fn verdict(&self) -> &Verdict {
+        &self.validation.verdict
+    }
+}
+
+/// Single JSON document printed to stdout by `--json`
+#[derive(Debug, Serialize)]
+pub struct RunOutput<'a> {
+    #[serde(flatten)]
+    pub result: &'a PipelineResult,
+    pub summary: &'a RunSummary,
+}
+
+/// Write `output` as one pretty JSON document followed by a newline
+pub fn write_json_output(writer: &mut impl Write, output: &RunOutput<'_>) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, output)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write just the lowercased verdict and a newline (`--verdict-only`)
+pub fn write_verdict(writer: &mut impl Write, validation: &ValidationV1) -> anyhow::Result<()> {
+    writeln!(writer, "{}", validation.verdict)?;
+    Ok(())
+}
+
+/// Number of equal-width score ranges of a `ScoreHistogram`: 0.0-0.2, ..., 0.8-1.0
+pub const HISTOGRAM_BUCKETS: usize = 5;
+
+/// Distribution of the validation scores of a batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreHistogram {
+    /// Scores per range; a score on a boundary counts in the higher range, 1.0 in the last
+    pub counts: [usize; HISTOGRAM_BUCKETS],
+    pub mean: f32,
+    pub median: f32,
+}
+
+impl ScoreHistogram {
+    /// Histogram of `scores`, each clamped into [0.0, 1.0]; `None` when there are none
+    pub fn from_scores(scores: &[f32]) -> Option<Self> {
+        if scores.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f32> = scores
+            .iter()
+            .map(|s| if s.is_finite() { s.clamp(0.0, 1.0) } else { 0.0 })
+            .collect();
+        sorted.sort_by(f32::total_cmp);
+
+        let mut counts = [0; HISTOGRAM_BUCKETS];
+        for score in &sorted {
+            let bucket = (score * HISTOGRAM_BUCKETS as f32).floor() as usize;
+            counts[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+        }
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        };
+        Some(Self {
+            counts,
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            median,
+        })
+    }
+
+    /// One `0.0-0.2 | ### 3` line per range, then the mean and median
+    pub fn render(&self) -> String {
+        let width = 1.0 / HISTOGRAM_BUCKETS as f32;
+        let mut lines: Vec<String> = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                format!(
+                    "{:.1}-{:.1} | {:<bar$} {}",
+                    i as f32 * width,
+                    (i + 1) as f32 * width,
+                    "#".repeat(*count),
+                    count,
+                    bar = self.counts.iter().copied().max().unwrap_or(0)
+                )
+            })
+            .collect();
+        lines.push(format!("mean {:.2}, median {:.2}", self.mean, self.median));
+        lines.join("\n")
+    }
+}
+
+/// `manifest.json` of a `--bundle` archive: what the run was and which entries it holds
+#[derive(Debug, Serialize)]
+struct BundleManifest<'a> {
+    task_id: &'a str,
+    solution_id: &'a str,
+    verdict: &'a Verdict,
+    score: f32,
+    /// RFC3339
+    created_at: String,
+    entries: &'a [&'a str],
+}
+
+/// `summary.json` of a `--bundle` archive
+#[derive(Debug, Serialize)]
+struct BundleSummary<'a> {
+    summary: &'a RunSummary,
+    report: &'a RunReport,
+}
+
+/// Package a whole run (task, solution, validation, summary and a manifest) as a zip archive
+pub fn write_bundle(
+    writer: impl Write + Seek,
+    task: &TaskSpec,
+    output: &RunOutput<'_>,
+) -> anyhow::Result<()> {
+    let manifest = BundleManifest {
+        task_id: &task.task_id,
+        solution_id: &output.result.solution.solution_id,
+        verdict: output.result.verdict(),
+        score: output.result.validation.score,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        entries: BUNDLE_ENTRIES,
+    };
+    let summary = BundleSummary { summary: output.summary, report: output.result.report() };
+    let contents = [
+        serde_json::to_vec_pretty(task)?,
+        serde_json::to_vec_pretty(output.result.solution())?,
+        serde_json::to_vec_pretty(output.result.validation())?,
+        serde_json::to_vec_pretty(&summary)?,
+    ];
+
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default();
+    zip.start_file(BUNDLE_MANIFEST, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    for (name, bytes) in BUNDLE_ENTRIES.iter().zip(&contents) {
+        zip.start_file(*name, options)?;
+        zip.write_all(bytes)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// One row of the `--checks-csv` report
+#[derive(Debug, Serialize)]
+struct CheckRow<'a> {
+    criterion: &'a str,
+    pass: bool,
+    severity: &'a Severity,
+    reason: &'a str,
+    suggested_fix: Option<&'a str>,
+}
+
+/// Write one CSV row per check (with a header row); fields containing commas,
+/// quotes or newlines are quoted
+pub fn write_checks_csv(writer: impl Write, validation: &ValidationV1) -> anyhow::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    for check in &validation.checks {
+        csv.serialize(CheckRow {
+            criterion: &check.criterion,
+            pass: check.pass_,
+            severity: &check.severity,
+            reason: &check.reason,
+            suggested_fix: check.suggested_fix.as_deref(),
+        })?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample_solution_json, sample_task, sample_validation_json};
+    use crate::types::CheckResult;
+
+    #[test]
+    fn histogram_buckets_scores_and_reports_mean_and_median() {
+        let histogram =
+            ScoreHistogram::from_scores(&[0.1, 0.2, 0.45, 0.9, 1.0, 0.95, 0.0, 0.5]).unwrap();
+        assert_eq!(histogram.counts, [2, 1, 2, 0, 3]);
+        assert!((histogram.mean - 0.5125).abs() < 1e-6);
+        assert!((histogram.median - 0.475).abs() < 1e-6);
+        assert_eq!(
+            histogram.render(),
+            "0.0-0.2 | ##  2\n0.2-0.4 | #   1\n0.4-0.6 | ##  2\n0.6-0.8 |     0\n0.8-1.0 | ### 3\nmean 0.51, median 0.47"
+        );
+
+        assert_eq!(ScoreHistogram::from_scores(&[0.7]).unwrap().median, 0.7);
+        assert!(ScoreHistogram::from_scores(&[]).is_none());
+    }
+
+    #[test]
+    fn verdict_only_output_is_exactly_the_verdict() {
+        let mut validation: ValidationV1 = serde_json::from_str(&sample_validation_json()).unwrap();
+        for (verdict, expected, code) in [
+            (Verdict::Pass, "pass\n", 0),
+            (Verdict::Warn, "warn\n", 2),
+            (Verdict::Fail, "fail\n", 3),
+        ] {
+            validation.verdict = verdict;
+            let mut buf = Vec::new();
+            write_verdict(&mut buf, &validation).unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), expected);
+            assert_eq!(validation.verdict.exit_code(), code);
+        }
+    }
+
+    #[test]
+    fn checks_csv_round_trips() {
+        let mut validation: ValidationV1 = serde_json::from_str(&sample_validation_json()).unwrap();
+        validation.checks.push(CheckResult {
+            criterion: "<= 80 words, total".to_string(),
+            pass_: false,
+            reason: "has 95 words,\nand a \"quoted\" phrase".to_string(),
+            severity: Severity::Major,
+            suggested_fix: Some("cut the intro".to_string()),
+            partial_score: None,
+            unmatched: false,
+            evidence_lines: None,
+        });
+
+        let mut buf = Vec::new();
+        write_checks_csv(&mut buf, &validation).unwrap();
+
+        let mut reader = csv::Reader::from_reader(buf.as_slice());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["criterion", "pass", "severity", "reason", "suggested_fix"]
+        );
+        let rows: Vec<Vec<String>> = reader
+            .records()
+            .map(|r| r.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["exactly 3 bullets", "true", "minor", "three bullets present", ""],
+                vec![
+                    "<= 80 words, total",
+                    "false",
+                    "major",
+                    "has 95 words,\nand a \"quoted\" phrase",
+                    "cut the intro",
+                ],
+            ]
+        );
+    }
+
+    fn sample_report() -> RunReport {
+        RunReport {
+            started_at: "2024-01-01T00:00:00Z".to_string(),
+            finished_at: "2024-01-01T00:00:02Z".to_string(),
+            producer_ms: 1200,
+            auditor_ms: 800,
+            total_ms: 2000,
+        }
+    }
+
+    #[test]
+    fn bundle_holds_manifest_and_every_artifact() {
+        let task = sample_task();
+        let solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        let validation: ValidationV1 = serde_json::from_str(&sample_validation_json()).unwrap();
+        let summary = RunSummary::new(
+            &validation,
+            Path::new("out/solution.json"),
+            Path::new("out/validation.json"),
+        );
+        let result = PipelineResult { solution, validation, report: sample_report() };
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        write_bundle(
+            &mut buf,
+            &task,
+            &RunOutput { result: &result, summary: &summary },
+        )
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(buf).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["manifest.json", "solution.json", "summary.json", "task.json", "validation.json"]
+        );
+
+        let entry_json = |archive: &mut zip::ZipArchive<_>, name: &str| -> serde_json::Value {
+            serde_json::from_reader(archive.by_name(name).unwrap()).unwrap()
+        };
+        let manifest = entry_json(&mut archive, "manifest.json");
+        assert_eq!(manifest["task_id"], task.task_id.as_str());
+        assert_eq!(manifest["solution_id"], "sol-1");
+        assert_eq!(manifest["entries"].as_array().unwrap().len(), BUNDLE_ENTRIES.len());
+        assert_eq!(entry_json(&mut archive, "task.json")["goal"], task.goal.as_str());
+        assert_eq!(entry_json(&mut archive, "summary.json")["report"]["total_ms"], 2000);
+    }
+
+    #[test]
+    fn json_output_is_a_single_parseable_document() {
+        let solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        let validation: ValidationV1 = serde_json::from_str(&sample_validation_json()).unwrap();
+        let config = Config {
+            organization: Some("org-billing".to_string()),
+            project: Some("search".to_string()),
+            ..Config::default()
+        };
+        let summary = RunSummary::new(
+            &validation,
+            Path::new("out/solution.json"),
+            Path::new("out/validation.json"),
+        )
+        .with_billing_tags(&config);
+
+        let result = PipelineResult { solution, validation, report: sample_report() };
+
+        let mut buf = Vec::new();
+        write_json_output(
+            &mut buf,
+            &RunOutput { result: &result, summary: &summary },
+        )
+        .unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed["solution"]["solution_id"], "sol-1");
+        assert_eq!(parsed["validation"]["verdict"], "pass");
+        assert_eq!(parsed["summary"]["failed_checks"], 0);
+        assert_eq!(parsed["summary"]["solution_path"], "out/solution.json");
+        assert_eq!(parsed["summary"]["organization"], "org-billing");
+        assert_eq!(parsed["summary"]["project"], "search");
+        assert_eq!(parsed["report"]["producer_ms"], 1200);
+    }
+
+    #[test]
+    fn pipeline_result_round_trips_through_json() {
+        let solution: SolutionV1 = serde_json::from_str(&sample_solution_json()).unwrap();
+        let validation: ValidationV1 = serde_json::from_str(&sample_validation_json()).unwrap();
+        let result = PipelineResult { solution, validation, report: sample_report() };
+
+        let json = serde_json::to_string(&result).unwrap();
+        let back: PipelineResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.solution().solution_id, "sol-1");
+        assert!(matches!(back.verdict(), Verdict::Pass));
+        assert_eq!(back.validation().checks.len(), result.validation().checks.len());
+        assert_eq!(back.report().total_ms, 2000);
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+}
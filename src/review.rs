@@ -0,0 +1,160 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+
+use crate::console::Console;
+
+/// Reviewer choice after the auditor has graded a solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewDecision {
+    Accept,
+    Revise,
+    Reject,
+}
+
+impl ReviewDecision {
+    /// Parse a console answer: number, full word or first letter
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "1" | "a" | "accept" => Some(ReviewDecision::Accept),
+            "2" | "r" | "revise" | "revision" | "request revision" => Some(ReviewDecision::Revise),
+            "3" | "x" | "reject" => Some(ReviewDecision::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// State of the interactive review loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewState {
+    /// Waiting for a decision on the solution of `round` (0 = original solution)
+    Awaiting { round: u32 },
+    /// The reviewer asked for revision number `round`
+    Revising { round: u32 },
+    Accepted,
+    Rejected,
+}
+
+impl ReviewState {
+    pub fn start() -> Self {
+        ReviewState::Awaiting { round: 0 }
+    }
+
+    /// Transition on a reviewer decision; ignored unless awaiting one
+    pub fn on_decision(self, decision: ReviewDecision) -> Self {
+        match (self, decision) {
+            (ReviewState::Awaiting { .. }, ReviewDecision::Accept) => ReviewState::Accepted,
+            (ReviewState::Awaiting { .. }, ReviewDecision::Reject) => ReviewState::Rejected,
+            (ReviewState::Awaiting { round }, ReviewDecision::Revise) => {
+                ReviewState::Revising { round: round + 1 }
+            }
+            (other, _) => other,
+        }
+    }
+
+    /// Transition once the requested revision has been produced and audited
+    pub fn on_revised(self) -> Self {
+        match self {
+            ReviewState::Revising { round } => ReviewState::Awaiting { round },
+            other => other,
+        }
+    }
+
+    pub fn is_final(self) -> bool {
+        matches!(self, ReviewState::Accepted | ReviewState::Rejected)
+    }
+}
+
+/// Source of reviewer answers (the console, or a script in tests)
+#[async_trait]
+pub trait ChoiceSource: Send {
+    async fn next_line(&mut self) -> Result<String>;
+}
+
+/// Reads answers from stdin
+pub struct ConsoleChoices;
+
+#[async_trait]
+impl ChoiceSource for ConsoleChoices {
+    async fn next_line(&mut self) -> Result<String> {
+        Console::prompt("👤 Review: [1] Accept  [2] Request revision  [3] Reject: ").await
+    }
+}
+
+/// Invalid answers tolerated before giving up (guards against a closed stdin)
+const MAX_INVALID_ANSWERS: usize = 5;
+
+/// Ask until a valid decision is entered
+pub async fn ask_decision(source: &mut dyn ChoiceSource) -> Result<ReviewDecision> {
+    for _ in 0..MAX_INVALID_ANSWERS {
+        let answer = source.next_line().await?;
+        match ReviewDecision::parse(&answer) {
+            Some(decision) => return Ok(decision),
+            None => tracing::warn!("Unrecognized review answer '{}'", answer),
+        }
+    }
+    bail!("No valid review decision entered")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    struct Scripted(VecDeque<&'static str>);
+
+    #[async_trait]
+    impl ChoiceSource for Scripted {
+        async fn next_line(&mut self) -> Result<String> {
+            Ok(self.0.pop_front().unwrap_or_default().to_string())
+        }
+    }
+
+    /// Drive the state machine like the orchestrator does, recording visited states
+    async fn drive(script: &[&'static str]) -> Result<Vec<ReviewState>> {
+        let mut source = Scripted(script.iter().copied().collect());
+        let mut state = ReviewState::start();
+        let mut visited = vec![state];
+        while !state.is_final() {
+            state = state.on_decision(ask_decision(&mut source).await?);
+            visited.push(state);
+            if let ReviewState::Revising { .. } = state {
+                state = state.on_revised();
+                visited.push(state);
+            }
+        }
+        Ok(visited)
+    }
+
+    #[tokio::test]
+    async fn accept_finishes_immediately() {
+        let states = drive(&["a"]).await.unwrap();
+        assert_eq!(states, vec![ReviewState::start(), ReviewState::Accepted]);
+    }
+
+    #[tokio::test]
+    async fn reject_finishes_rejected() {
+        let states = drive(&["Reject"]).await.unwrap();
+        assert_eq!(states.last(), Some(&ReviewState::Rejected));
+    }
+
+    #[tokio::test]
+    async fn revisions_loop_back_until_accepted() {
+        let states = drive(&["2", "hmm", "revise", "1"]).await.unwrap();
+        assert_eq!(
+            states,
+            vec![
+                ReviewState::Awaiting { round: 0 },
+                ReviewState::Revising { round: 1 },
+                ReviewState::Awaiting { round: 1 },
+                ReviewState::Revising { round: 2 },
+                ReviewState::Awaiting { round: 2 },
+                ReviewState::Accepted,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_repeated_invalid_answers() {
+        assert!(drive(&[]).await.is_err());
+    }
+}
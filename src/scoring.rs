@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use crate::types::{CheckResult, Severity, Verdict};
+
+/// Grading policy turning audit checks into a score in [0.0, 1.0] and a verdict
+pub trait ScoringStrategy: Send + Sync {
+    fn score(&self, checks: &[CheckResult]) -> (f32, Verdict);
+}
+
+/// Mean credit of the checks (`partial_score`, or 1.0/0.0 from `pass`)
+fn passed_fraction(checks: &[CheckResult]) -> f32 {
+    checks.iter().map(CheckResult::credit).sum::<f32>() / checks.len() as f32
+}
+
+fn any_failed(checks: &[CheckResult], severity: fn(&Severity) -> bool) -> bool {
+    checks.iter().any(|c| !c.pass_ && severity(&c.severity))
+}
+
+/// Pass only when every check passes; any failure fails the solution
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrictScoring;
+
+impl ScoringStrategy for StrictScoring {
+    fn score(&self, checks: &[CheckResult]) -> (f32, Verdict) {
+        if checks.is_empty() {
+            return (0.0, Verdict::Fail);
+        }
+        let score = passed_fraction(checks);
+        let verdict = if checks.iter().all(|c| c.pass_) { Verdict::Pass } else { Verdict::Fail };
+        (score, verdict)
+    }
+}
+
+/// Minor failures do not affect the verdict; a failed major check warns, a failed critical check fails
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LenientScoring;
+
+impl ScoringStrategy for LenientScoring {
+    fn score(&self, checks: &[CheckResult]) -> (f32, Verdict) {
+        if checks.is_empty() {
+            return (0.0, Verdict::Fail);
+        }
+        let verdict = if any_failed(checks, |s| matches!(s, Severity::Critical)) {
+            Verdict::Fail
+        } else if any_failed(checks, |s| matches!(s, Severity::Major)) {
+            Verdict::Warn
+        } else {
+            Verdict::Pass
+        };
+        (passed_fraction(checks), verdict)
+    }
+}
+
+/// Severity-weighted share of credit, mapped to a verdict by thresholds.
+/// A failed critical check always fails.
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedScoring {
+    pub minor: f32,
+    pub major: f32,
+    pub critical: f32,
+    /// Minimum score for `Pass`
+    pub pass_threshold: f32,
+    /// Minimum score for `Warn`
+    pub warn_threshold: f32,
+}
+
+impl Default for WeightedScoring {
+    fn default() -> Self {
+        Self {
+            minor: 1.0,
+            major: 2.0,
+            critical: 4.0,
+            pass_threshold: 0.8,
+            warn_threshold: 0.5,
+        }
+    }
+}
+
+impl WeightedScoring {
+    fn weight(&self, severity: &Severity) -> f32 {
+        match severity {
+            Severity::Minor => self.minor,
+            Severity::Major => self.major,
+            Severity::Critical => self.critical,
+        }
+    }
+}
+
+impl ScoringStrategy for WeightedScoring {
+    fn score(&self, checks: &[CheckResult]) -> (f32, Verdict) {
+        let total: f32 = checks.iter().map(|c| self.weight(&c.severity)).sum();
+        if total <= 0.0 {
+            return (0.0, Verdict::Fail);
+        }
+        let passed: f32 = checks
+            .iter()
+            .map(|c| c.credit() * self.weight(&c.severity))
+            .sum();
+        let score = passed / total;
+        let verdict = if any_failed(checks, |s| matches!(s, Severity::Critical)) {
+            Verdict::Fail
+        } else if score >= self.pass_threshold {
+            Verdict::Pass
+        } else if score >= self.warn_threshold {
+            Verdict::Warn
+        } else {
+            Verdict::Fail
+        };
+        (score, verdict)
+    }
+}
+
+/// Built-in strategies selectable with `--deterministic-verdict`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScoringKind {
+    Strict,
+    Lenient,
+    Weighted,
+}
+
+impl ScoringKind {
+    pub fn strategy(self) -> Arc<dyn ScoringStrategy> {
+        match self {
+            ScoringKind::Strict => Arc::new(StrictScoring),
+            ScoringKind::Lenient => Arc::new(LenientScoring),
+            ScoringKind::Weighted => Arc::new(WeightedScoring::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(pass: bool, severity: Severity) -> CheckResult {
+        CheckResult {
+            criterion: "c".to_string(),
+            pass_: pass,
+            reason: String::new(),
+            severity,
+            suggested_fix: None,
+            partial_score: None,
+            unmatched: false,
+            evidence_lines: None,
+        }
+    }
+
+    /// 3 of 4 checks pass; the failing one is major (weighted: 4 of 6)
+    fn checks() -> Vec<CheckResult> {
+        vec![
+            check(true, Severity::Minor),
+            check(true, Severity::Minor),
+            check(true, Severity::Major),
+            check(false, Severity::Major),
+        ]
+    }
+
+    #[test]
+    fn strict_fails_on_any_failure() {
+        let (score, verdict) = StrictScoring.score(&checks());
+        assert_eq!(score, 0.75);
+        assert!(matches!(verdict, Verdict::Fail));
+
+        let all_pass: Vec<_> = checks().into_iter().map(|c| CheckResult { pass_: true, ..c }).collect();
+        assert!(matches!(StrictScoring.score(&all_pass), (1.0, Verdict::Pass)));
+    }
+
+    #[test]
+    fn lenient_warns_on_major_failure() {
+        let (score, verdict) = LenientScoring.score(&checks());
+        assert_eq!(score, 0.75);
+        assert!(matches!(verdict, Verdict::Warn));
+
+        let mut minor_only = checks();
+        minor_only[3].severity = Severity::Minor;
+        assert!(matches!(LenientScoring.score(&minor_only).1, Verdict::Pass));
+    }
+
+    #[test]
+    fn weighted_uses_severity_weights_and_thresholds() {
+        let (score, verdict) = WeightedScoring::default().score(&checks());
+        assert!((score - 4.0 / 6.0).abs() < 1e-6);
+        assert!(matches!(verdict, Verdict::Warn));
+
+        let mut critical = checks();
+        critical[0] = check(false, Severity::Critical);
+        critical[3].pass_ = true;
+        let (score, verdict) = WeightedScoring::default().score(&critical);
+        assert!((score - 5.0 / 9.0).abs() < 1e-6);
+        assert!(matches!(verdict, Verdict::Fail));
+    }
+
+    #[test]
+    fn partial_scores_are_aggregated() {
+        let mut partial = checks();
+        partial[3].partial_score = Some(0.5);
+        partial[0].partial_score = Some(0.5);
+
+        let (score, verdict) = StrictScoring.score(&partial);
+        assert!((score - 3.0 / 4.0).abs() < 1e-6);
+        assert!(matches!(verdict, Verdict::Fail));
+
+        assert!((LenientScoring.score(&partial).0 - 0.75).abs() < 1e-6);
+
+        // (0.5*1 + 1*1 + 1*2 + 0.5*2) / 6
+        let (score, verdict) = WeightedScoring::default().score(&partial);
+        assert!((score - 4.5 / 6.0).abs() < 1e-6);
+        assert!(matches!(verdict, Verdict::Warn));
+    }
+
+    #[test]
+    fn no_checks_fail_for_every_strategy() {
+        for kind in [ScoringKind::Strict, ScoringKind::Lenient, ScoringKind::Weighted] {
+            assert!(matches!(kind.strategy().score(&[]), (0.0, Verdict::Fail)));
+        }
+    }
+}
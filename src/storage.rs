@@ -0,0 +1,318 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Device names Windows reserves regardless of extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// `name` as a file name valid on every platform: characters Windows rejects (`<>:"/\|?*`
+/// and control characters) become `-`, trailing dots and spaces are dropped, and reserved
+/// device names such as `CON` or `nul.txt` get a leading `_`
+pub fn sanitize_filename(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_control() || "<>:\"/\\|?*".contains(c) { '-' } else { c })
+        .collect();
+    sanitized.truncate(sanitized.trim_end_matches(['.', ' ']).len());
+    if sanitized.is_empty() {
+        return "_".to_string();
+    }
+    let stem = sanitized.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Backend used by the agents to persist and read back their artifacts
+#[async_trait]
+pub trait ArtifactStore: Send + Sync {
+    /// Persist `contents` under `key`, replacing any previous value
+    async fn save(&self, key: &str, contents: &str) -> std::io::Result<()>;
+
+    /// Append `contents` to the value under `key`, creating it when missing
+    async fn append(&self, key: &str, contents: &str) -> std::io::Result<()>;
+
+    /// Delete the value under `key`; a missing key is not an error
+    async fn remove(&self, key: &str) -> std::io::Result<()>;
+
+    /// Load the contents stored under `key`, if any
+    async fn load(&self, key: &str) -> Option<String>;
+
+    /// Human-readable location of `key`, used in logs and console output
+    fn locate(&self, key: &str) -> String {
+        key.to_string()
+    }
+}
+
+/// Filesystem-backed store rooted at a directory; keys are relative file paths
+#[derive(Debug, Clone)]
+pub struct FsStore {
+    root: PathBuf,
+    require_existing_root: bool,
+    mode: Option<u32>,
+}
+
+impl FsStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            require_existing_root: false,
+            mode: None,
+        }
+    }
+
+    /// Unix permission bits (e.g. `0o600`) applied to every file after it is written;
+    /// ignored on other platforms. `None` leaves the umask-derived default.
+    pub fn with_mode(mut self, mode: Option<u32>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// When enabled, saving fails with `NotFound` instead of creating a missing root directory
+    pub fn with_require_existing_root(mut self, required: bool) -> Self {
+        self.require_existing_root = required;
+        self
+    }
+
+    /// Fail with `NotFound` when the root must exist (`with_require_existing_root`) and does not
+    pub async fn check_root(&self) -> std::io::Result<()> {
+        if self.require_existing_root && !fs::metadata(&self.root).await.is_ok_and(|m| m.is_dir()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("output directory {} does not exist", self.root.display()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Resolve a key to its path on disk, each `/`-separated component sanitized
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        key.split('/')
+            .filter(|component| !component.is_empty())
+            .fold(self.root.clone(), |path, component| path.join(sanitize_filename(component)))
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for FsStore {
+    async fn save(&self, key: &str, contents: &str) -> std::io::Result<()> {
+        self.check_root().await?;
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, contents).await?;
+        if let Some(mode) = self.mode {
+            set_mode(&path, mode).await?;
+        }
+        Ok(())
+    }
+
+    async fn append(&self, key: &str, contents: &str) -> std::io::Result<()> {
+        self.check_root().await?;
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&path).await?;
+        file.write_all(contents.as_bytes()).await?;
+        file.flush().await?;
+        if let Some(mode) = self.mode {
+            set_mode(&path, mode).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> std::io::Result<()> {
+        match fs::remove_file(self.path_for(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    async fn load(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.path_for(key)).await.ok()
+    }
+
+    fn locate(&self, key: &str) -> String {
+        self.path_for(key).display().to_string()
+    }
+}
+
+#[cfg(unix)]
+async fn set_mode(path: &std::path::Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).await
+}
+
+#[cfg(not(unix))]
+async fn set_mode(_path: &std::path::Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// In-memory store for tests
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: std::sync::Mutex<std::collections::HashMap<String, String>>,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys currently held by the store, sorted
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .entries
+            .lock()
+            .expect("memory store mutex poisoned")
+            .keys()
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl ArtifactStore for MemoryStore {
+    async fn save(&self, key: &str, contents: &str) -> std::io::Result<()> {
+        self.entries
+            .lock()
+            .expect("memory store mutex poisoned")
+            .insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    async fn append(&self, key: &str, contents: &str) -> std::io::Result<()> {
+        self.entries
+            .lock()
+            .expect("memory store mutex poisoned")
+            .entry(key.to_string())
+            .or_default()
+            .push_str(contents);
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> std::io::Result<()> {
+        self.entries.lock().expect("memory store mutex poisoned").remove(key);
+        Ok(())
+    }
+
+    async fn load(&self, key: &str) -> Option<String> {
+        self.entries
+            .lock()
+            .expect("memory store mutex poisoned")
+            .get(key)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory_store_round_trip() {
+        let store = MemoryStore::new();
+        assert!(store.load("solution.json").await.is_none());
+
+        store.save("solution.json", "{}").await.unwrap();
+        store.save("solution.json", "{\"a\":1}").await.unwrap();
+        assert_eq!(store.load("solution.json").await.as_deref(), Some("{\"a\":1}"));
+        assert_eq!(store.keys(), vec!["solution.json".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn fs_store_creates_directories_and_round_trips() {
+        let root = std::env::temp_dir().join(format!("fs_store_{}", uuid::Uuid::new_v4()));
+        let store = FsStore::new(&root);
+
+        store.save("nested/validation.json", "{}").await.unwrap();
+        assert_eq!(store.load("nested/validation.json").await.as_deref(), Some("{}"));
+        assert!(store.load("missing.json").await.is_none());
+        assert!(store.locate("nested/validation.json").ends_with("validation.json"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn sanitized_filenames_are_valid_on_windows() {
+        assert_eq!(sanitize_filename("2024-05-06T07:08:09.010Z"), "2024-05-06T07-08-09.010Z");
+        assert_eq!(sanitize_filename("2024-05-06T07:08:09+02:00"), "2024-05-06T07-08-09+02-00");
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("nul.txt"), "_nul.txt");
+        assert_eq!(sanitize_filename("com1.raw.txt"), "_com1.raw.txt");
+        assert_eq!(sanitize_filename("console.json"), "console.json");
+        assert_eq!(sanitize_filename("a/b\\c?*\"d\n"), "a-b-c---d-");
+        assert_eq!(sanitize_filename("task. "), "task");
+        assert_eq!(sanitize_filename(".."), "_");
+    }
+
+    #[test]
+    fn fs_store_sanitizes_each_key_component() {
+        let store = FsStore::new("/out");
+        assert_eq!(store.path_for("aux/solution.json"), PathBuf::from("/out/_aux/solution.json"));
+        assert_eq!(store.path_for("run:1/prn.raw.txt"), PathBuf::from("/out/run-1/_prn.raw.txt"));
+    }
+
+    #[tokio::test]
+    async fn fs_store_requiring_existing_root_refuses_missing_directory() {
+        let root = std::env::temp_dir().join(format!("fs_store_{}", uuid::Uuid::new_v4()));
+        let store = FsStore::new(&root).with_require_existing_root(true);
+
+        let err = store.save("solution.json", "{}").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(!root.exists());
+
+        std::fs::create_dir_all(&root).unwrap();
+        store.save("nested/solution.json", "{}").await.unwrap();
+        assert_eq!(store.load("nested/solution.json").await.as_deref(), Some("{}"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn fs_store_appends_and_removes() {
+        let root = std::env::temp_dir().join(format!("fs_store_{}", uuid::Uuid::new_v4()));
+        let store = FsStore::new(&root);
+
+        store.append("nested/deliverable.txt", "a").await.unwrap();
+        store.append("nested/deliverable.txt", "b").await.unwrap();
+        assert_eq!(store.load("nested/deliverable.txt").await.as_deref(), Some("ab"));
+        store.remove("nested/deliverable.txt").await.unwrap();
+        assert!(store.load("nested/deliverable.txt").await.is_none());
+        store.remove("nested/deliverable.txt").await.unwrap();
+
+        let missing = FsStore::new(root.join("missing")).with_require_existing_root(true);
+        assert_eq!(missing.append("d.txt", "a").await.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn fs_store_applies_configured_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("fs_store_{}", uuid::Uuid::new_v4()));
+        let store = FsStore::new(&root).with_mode(Some(0o600));
+        store.save("solution.json", "{}").await.unwrap();
+        // Rewriting an existing file keeps the mode
+        store.save("solution.json", "{\"a\":1}").await.unwrap();
+
+        let mode = std::fs::metadata(store.path_for("solution.json")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o600);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
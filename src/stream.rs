@@ -0,0 +1,150 @@
+//! Incremental parsing of Server-Sent Events (SSE) chat-completion streams.
+
+/// Decodes UTF-8 text arriving in arbitrary byte chunks. Multi-byte sequences
+/// split across chunk boundaries are held back until the next chunk instead
+/// of being turned into replacement characters.
+#[derive(Debug, Default)]
+pub struct Utf8ChunkDecoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8ChunkDecoder {
+    /// Feed a chunk and return all text that is complete so far
+    pub fn push(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+        let mut out = String::new();
+        loop {
+            match std::str::from_utf8(&self.pending) {
+                Ok(text) => {
+                    out.push_str(text);
+                    self.pending.clear();
+                    return out;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    // Safe: validated by from_utf8 above
+                    out.push_str(std::str::from_utf8(&self.pending[..valid]).unwrap_or_default());
+                    match e.error_len() {
+                        // Incomplete sequence at the end: keep it for the next chunk
+                        None => {
+                            self.pending.drain(..valid);
+                            return out;
+                        }
+                        // Genuinely invalid bytes: replace them and continue
+                        Some(len) => {
+                            out.push(char::REPLACEMENT_CHARACTER);
+                            self.pending.drain(..valid + len);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Flush at end of stream; a dangling partial sequence becomes a replacement character
+    pub fn finish(&mut self) -> String {
+        let rest = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        rest
+    }
+}
+
+/// Splits a byte stream into SSE `data:` payloads
+#[derive(Debug, Default)]
+pub struct SseParser {
+    decoder: Utf8ChunkDecoder,
+    line: String,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk and return the `data:` payloads of all lines completed by it.
+    /// The terminal `[DONE]` marker is returned like any other payload.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let text = self.decoder.push(chunk);
+        self.take_lines(&text)
+    }
+
+    /// Flush at end of stream, returning a final unterminated payload if any
+    pub fn finish(&mut self) -> Vec<String> {
+        let text = self.decoder.finish();
+        let mut out = self.take_lines(&text);
+        let last = std::mem::take(&mut self.line);
+        out.extend(data_payload(&last));
+        out
+    }
+
+    fn take_lines(&mut self, text: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        for ch in text.chars() {
+            if ch == '\n' {
+                let line = std::mem::take(&mut self.line);
+                out.extend(data_payload(&line));
+            } else {
+                self.line.push(ch);
+            }
+        }
+        out
+    }
+}
+
+fn data_payload(line: &str) -> Option<String> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    line.strip_prefix("data:")
+        .map(|data| data.strip_prefix(' ').unwrap_or(data).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassembles_multibyte_text_split_across_chunks() {
+        let text = "héllo 世界 🦀!";
+        let bytes = text.as_bytes();
+        // Split at every possible position, including inside each multi-byte char
+        for split in 0..=bytes.len() {
+            let mut decoder = Utf8ChunkDecoder::default();
+            let mut out = decoder.push(&bytes[..split]);
+            out.push_str(&decoder.push(&bytes[split..]));
+            out.push_str(&decoder.finish());
+            assert_eq!(out, text, "split at {}", split);
+            assert!(!out.contains(char::REPLACEMENT_CHARACTER));
+        }
+    }
+
+    #[test]
+    fn byte_by_byte_feeding_round_trips() {
+        let text = "🦀 streaming 中文";
+        let mut decoder = Utf8ChunkDecoder::default();
+        let out: String = text.as_bytes().iter().map(|b| decoder.push(&[*b])).collect();
+        assert_eq!(out, text);
+    }
+
+    #[test]
+    fn invalid_bytes_become_replacement_characters() {
+        let mut decoder = Utf8ChunkDecoder::default();
+        assert_eq!(decoder.push(b"a\xffb"), "a\u{fffd}b");
+        assert_eq!(decoder.push(b"\xe4\xb8"), "");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+
+    #[test]
+    fn sse_parser_handles_events_split_inside_characters() {
+        let stream = "data: {\"content\":\"世\"}\n\ndata: {\"content\":\"界\"}\r\n\ndata: [DONE]\n";
+        let bytes = stream.as_bytes();
+        let mut parser = SseParser::new();
+        let mut events = Vec::new();
+        for chunk in bytes.chunks(5) {
+            events.extend(parser.push(chunk));
+        }
+        events.extend(parser.finish());
+        assert_eq!(
+            events,
+            vec!["{\"content\":\"世\"}", "{\"content\":\"界\"}", "[DONE]"]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! `--with-summary`: a small structured summary of a text deliverable (its key points and
+//! word count), stored in the solution's `evidence.summary`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::SolutionV1;
+
+/// Upper bound on the key points taken from a deliverable
+pub const MAX_KEY_POINTS: usize = 5;
+/// Key points longer than this many characters are cut and end with "..."
+const MAX_KEY_POINT_CHARS: usize = 120;
+
+/// Structured handles on a free-text deliverable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextSummary {
+    pub key_points: Vec<String>,
+    pub word_count: usize,
+}
+
+/// `line` without a leading bullet (`-`, `*`, `•`) or list number (`1.`, `2)`); `None` when
+/// it is not a list item
+fn list_item(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        return Some(rest.trim());
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..].strip_prefix(['.', ')']).map(str::trim)
+}
+
+/// First sentence of `paragraph`, up to and including its terminating punctuation
+fn first_sentence(paragraph: &str) -> &str {
+    let paragraph = paragraph.trim();
+    paragraph
+        .char_indices()
+        .find(|&(i, c)| {
+            matches!(c, '.' | '!' | '?')
+                && paragraph[i + c.len_utf8()..].chars().next().is_none_or(char::is_whitespace)
+        })
+        .map(|(i, c)| &paragraph[..i + c.len_utf8()])
+        .unwrap_or(paragraph)
+}
+
+fn shorten(point: &str) -> String {
+    if point.chars().count() <= MAX_KEY_POINT_CHARS {
+        return point.to_string();
+    }
+    let cut: String = point.chars().take(MAX_KEY_POINT_CHARS).collect();
+    format!("{}...", cut.trim_end())
+}
+
+impl TextSummary {
+    /// Summary of `text`: its list items when it has any, otherwise the first sentence of each
+    /// paragraph; at most `MAX_KEY_POINTS` of them
+    pub fn of(text: &str) -> Self {
+        let items: Vec<&str> = text
+            .lines()
+            .filter_map(list_item)
+            .filter(|item| !item.is_empty())
+            .collect();
+        let points: Vec<&str> = if items.is_empty() {
+            text.split("\n\n")
+                .map(first_sentence)
+                .filter(|sentence| !sentence.is_empty())
+                .collect()
+        } else {
+            items
+        };
+        Self {
+            key_points: points.into_iter().take(MAX_KEY_POINTS).map(shorten).collect(),
+            word_count: text
+                .split_whitespace()
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .count(),
+        }
+    }
+}
+
+/// Set the solution's `evidence.summary` from its text deliverable. Returns false, changing
+/// nothing, when there is no text.
+pub fn attach_text_summary(solution: &mut SolutionV1) -> bool {
+    let Some(text) = solution.deliverable.text.as_deref().filter(|t| !t.trim().is_empty()) else {
+        return false;
+    };
+    solution.evidence.summary = Some(TextSummary::of(text));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bullets_become_key_points_and_prose_uses_first_sentences() {
+        let summary = TextSummary::of("Intro line\n- Fast setup\n* Low cost\n2) Works offline\n");
+        assert_eq!(summary.key_points, vec!["Fast setup", "Low cost", "Works offline"]);
+        // Bullet markers are not words
+        assert_eq!(summary.word_count, 9);
+
+        let summary = TextSummary::of("Rust is fast. It is safe.\n\nCargo builds v1.2 crates! Really.");
+        assert_eq!(summary.key_points, vec!["Rust is fast.", "Cargo builds v1.2 crates!"]);
+        assert_eq!(summary.word_count, 11);
+    }
+}
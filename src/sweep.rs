@@ -0,0 +1,127 @@
+//! `--temperature-sweep`: run the pipeline on one task at each producer temperature of a
+//! range and report the temperature whose solution the auditor scored best.
+
+/// Most temperatures a sweep may cover, so a tiny step cannot start hundreds of runs
+pub const MAX_SWEEP_POINTS: usize = 21;
+
+/// Inclusive range of producer temperatures, given as `start,end,step`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureSweep {
+    pub start: f32,
+    pub end: f32,
+    pub step: f32,
+}
+
+/// Round to the two decimals temperatures are reported with
+fn round_temperature(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+impl TemperatureSweep {
+    /// `--temperature-sweep` value: `start,end,step` with 0.0 <= start <= end <= 2.0 and
+    /// step >= 0.01, covering at most `MAX_SWEEP_POINTS` temperatures
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = spec.split(',').map(str::trim).collect();
+        let [start, end, step] = parts.as_slice() else {
+            return Err(format!("expected start,end,step, got '{}'", spec));
+        };
+        let number = |value: &str| {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("'{}' is not a number", value))
+        };
+        let sweep = Self { start: number(start)?, end: number(end)?, step: number(step)? };
+        if !(0.0..=2.0).contains(&sweep.start) || !(sweep.start..=2.0).contains(&sweep.end) {
+            return Err(format!(
+                "expected 0.0 <= start <= end <= 2.0, got {} to {}",
+                sweep.start, sweep.end
+            ));
+        }
+        if sweep.step < 0.01 {
+            return Err(format!("step must be at least 0.01, got {}", sweep.step));
+        }
+        let points = sweep.values().len();
+        if points > MAX_SWEEP_POINTS {
+            return Err(format!(
+                "the sweep covers {} temperatures, more than {}; use a larger step",
+                points, MAX_SWEEP_POINTS
+            ));
+        }
+        Ok(sweep)
+    }
+
+    /// Temperatures from `start` up to and including `end` (when a whole number of steps
+    /// away), `step` apart, rounded to two decimals
+    pub fn values(&self) -> Vec<f32> {
+        // The tolerance keeps `end` in the range despite float error in (end - start) / step
+        let steps = ((self.end - self.start) / self.step + 1e-4).floor() as usize;
+        (0..=steps)
+            .map(|i| round_temperature(self.start + i as f32 * self.step))
+            .collect()
+    }
+}
+
+/// Id of the sweep run at `temperature`, also the name of its output directory
+pub fn run_id(task_id: &str, temperature: f32) -> String {
+    format!("{}-t{:.2}", task_id, temperature)
+}
+
+/// Table of temperature → score, one row per run in sweep order (`None` for a failed run),
+/// with the best-scoring temperature marked and named below
+pub fn render_table(rows: &[(f32, Option<f32>)]) -> String {
+    let best = rows
+        .iter()
+        .filter_map(|&(temperature, score)| score.map(|score| (temperature, score)))
+        // The first of equal scores wins: the lowest temperature is the most deterministic
+        .fold(None::<(f32, f32)>, |best, run| match best {
+            Some(best) if best.1 >= run.1 => Some(best),
+            _ => Some(run),
+        });
+    let mut table = String::from("temperature | score\n");
+    for &(temperature, score) in rows {
+        let score = score.map_or_else(|| "error".to_string(), |score| format!("{:.2}", score));
+        let marker = if best.is_some_and(|(t, _)| t == temperature) { "  <- best" } else { "" };
+        table.push_str(&format!("{:>11.2} | {}{}\n", temperature, score, marker));
+    }
+    match best {
+        Some((temperature, score)) => table.push_str(&format!(
+            "Best: temperature {:.2} (score {:.2})",
+            temperature, score
+        )),
+        None => table.push_str("Best: none (every run failed)"),
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_spec_yields_inclusive_rounded_values() {
+        let sweep = TemperatureSweep::parse("0.2, 1.0, 0.2").unwrap();
+        assert_eq!(sweep.values(), vec![0.2, 0.4, 0.6, 0.8, 1.0]);
+        assert_eq!(TemperatureSweep::parse("0,0.25,0.1").unwrap().values(), vec![0.0, 0.1, 0.2]);
+        assert_eq!(TemperatureSweep::parse("0.7,0.7,0.1").unwrap().values(), vec![0.7]);
+
+        assert!(TemperatureSweep::parse("0.2,1.0").is_err());
+        assert!(TemperatureSweep::parse("1.0,0.2,0.1").is_err());
+        assert!(TemperatureSweep::parse("0,2.5,0.5").is_err());
+        assert!(TemperatureSweep::parse("0,1,0").is_err());
+        assert!(TemperatureSweep::parse("0,2,0.05").is_err());
+    }
+
+    #[test]
+    fn table_marks_the_best_temperature() {
+        let table = render_table(&[(0.2, Some(0.8)), (0.5, Some(0.9)), (0.8, None), (1.0, Some(0.9))]);
+        assert_eq!(
+            table,
+            "temperature | score\n\
+             \x20      0.20 | 0.80\n\
+             \x20      0.50 | 0.90  <- best\n\
+             \x20      0.80 | error\n\
+             \x20      1.00 | 0.90\n\
+             Best: temperature 0.50 (score 0.90)"
+        );
+    }
+}
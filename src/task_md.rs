@@ -0,0 +1,210 @@
+use thiserror::Error;
+
+use crate::types::{DeliverableType, TaskSpec};
+
+/// Errors raised while importing a `TaskSpec` from markdown
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TaskMarkdownError {
+    #[error("Missing required section '## {0}'")]
+    MissingSection(&'static str),
+
+    #[error("Section '## {0}' is empty")]
+    EmptySection(&'static str),
+
+    #[error("Unknown deliverable type '{0}' (expected text, json or code)")]
+    InvalidDeliverableType(String),
+
+    #[error("Section '## JSON Schema' is not valid JSON: {0}")]
+    InvalidJsonSchema(String),
+}
+
+/// Split the document into `(heading, body)` pairs for every `## ` heading.
+/// Headings are lowercased; bodies are trimmed.
+fn sections(md: &str) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = Vec::new();
+    for line in md.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            out.push((heading.trim().to_lowercase(), String::new()));
+        } else if let Some((_, body)) = out.last_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    for (_, body) in out.iter_mut() {
+        *body = body.trim().to_string();
+    }
+    out
+}
+
+fn section<'a>(sections: &'a [(String, String)], name: &'static str) -> Option<&'a str> {
+    sections
+        .iter()
+        .find(|(heading, _)| heading == &name.to_lowercase())
+        .map(|(_, body)| body.as_str())
+}
+
+fn required<'a>(
+    sections: &'a [(String, String)],
+    name: &'static str,
+) -> Result<&'a str, TaskMarkdownError> {
+    let body = section(sections, name).ok_or(TaskMarkdownError::MissingSection(name))?;
+    if body.is_empty() {
+        return Err(TaskMarkdownError::EmptySection(name));
+    }
+    Ok(body)
+}
+
+/// Build a `TaskSpec` from a markdown document with `## Goal`, `## Input`,
+/// `## Acceptance Criteria` (bullet list) and `## Deliverable Type` sections.
+/// `## Hints`, `## Task ID` and `## JSON Schema` (optionally fenced) are optional; a fresh
+/// UUID is used when the id is absent.
+pub fn parse_task_markdown(md: &str) -> Result<TaskSpec, TaskMarkdownError> {
+    let sections = sections(md);
+
+    let goal = required(&sections, "Goal")?.to_string();
+    let input = required(&sections, "Input")?.to_string();
+
+    let acceptance_criteria: Vec<String> = required(&sections, "Acceptance Criteria")?
+        .lines()
+        .map(str::trim)
+        .filter_map(|l| {
+            l.strip_prefix("- ")
+                .or_else(|| l.strip_prefix("* "))
+                .or_else(|| l.strip_prefix("+ "))
+        })
+        .map(|l| l.strip_prefix("[ ] ").or_else(|| l.strip_prefix("[x] ")).unwrap_or(l))
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if acceptance_criteria.is_empty() {
+        return Err(TaskMarkdownError::EmptySection("Acceptance Criteria"));
+    }
+
+    let deliverable_raw = required(&sections, "Deliverable Type")?;
+    let deliverable_type: DeliverableType = deliverable_raw
+        .trim_matches('`')
+        .parse()
+        .map_err(|_| TaskMarkdownError::InvalidDeliverableType(deliverable_raw.to_string()))?;
+
+    let hints = section(&sections, "Hints")
+        .filter(|h| !h.is_empty())
+        .map(str::to_string);
+    let task_id = section(&sections, "Task ID")
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let json_schema = section(&sections, "JSON Schema")
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let body = s
+                .strip_prefix("```json")
+                .or_else(|| s.strip_prefix("```"))
+                .and_then(|b| b.trim_end().strip_suffix("```"))
+                .unwrap_or(s);
+            serde_json::from_str(body.trim())
+                .map_err(|e| TaskMarkdownError::InvalidJsonSchema(e.to_string()))
+        })
+        .transpose()?;
+
+    Ok(TaskSpec {
+        task_id,
+        goal,
+        input,
+        acceptance_criteria,
+        deliverable_type,
+        hints,
+        json_schema,
+        deadline_ms: None,
+        scoring_guidance: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# Summarize release notes
+
+## Task ID
+issue-42
+
+## Goal
+Summarize the input into exactly 3 bullet points
+
+## Input
+Release 1.2 adds streaming.
+It also fixes retries.
+
+## Acceptance Criteria
+- exactly 3 bullets
+* <= 80 words total
+- [ ] no marketing fluff
+
+## Deliverable Type
+`text`
+
+## Hints
+Be concise
+";
+
+    #[test]
+    fn parses_sample_markdown() {
+        let task = parse_task_markdown(SAMPLE).unwrap();
+        assert_eq!(task.task_id, "issue-42");
+        assert_eq!(task.goal, "Summarize the input into exactly 3 bullet points");
+        assert_eq!(task.input, "Release 1.2 adds streaming.\nIt also fixes retries.");
+        assert_eq!(
+            task.acceptance_criteria,
+            vec!["exactly 3 bullets", "<= 80 words total", "no marketing fluff"]
+        );
+        assert!(matches!(task.deliverable_type, DeliverableType::Text));
+        assert_eq!(task.hints.as_deref(), Some("Be concise"));
+    }
+
+    #[test]
+    fn optional_sections_default() {
+        let md = "## Goal\ng\n## Input\ni\n## Acceptance Criteria\n- a\n## Deliverable Type\ncode\n";
+        let task = parse_task_markdown(md).unwrap();
+        assert!(uuid::Uuid::parse_str(&task.task_id).is_ok());
+        assert!(task.hints.is_none());
+        assert!(task.json_schema.is_none());
+        assert!(matches!(task.deliverable_type, DeliverableType::Code));
+    }
+
+    #[test]
+    fn parses_fenced_json_schema() {
+        let md = "## Goal\ng\n## Input\ni\n## Acceptance Criteria\n- a\n## Deliverable Type\njson\n\
+                  ## JSON Schema\n```json\n{ \"type\": \"object\" }\n```\n";
+        let task = parse_task_markdown(md).unwrap();
+        assert_eq!(task.json_schema, Some(serde_json::json!({ "type": "object" })));
+
+        let md = "## Goal\ng\n## Input\ni\n## Acceptance Criteria\n- a\n## Deliverable Type\njson\n\
+                  ## JSON Schema\n{ type: object }\n";
+        assert!(matches!(
+            parse_task_markdown(md).unwrap_err(),
+            TaskMarkdownError::InvalidJsonSchema(_)
+        ));
+    }
+
+    #[test]
+    fn missing_and_invalid_sections_error() {
+        let md = "## Goal\ng\n## Acceptance Criteria\n- a\n## Deliverable Type\ntext\n";
+        assert_eq!(
+            parse_task_markdown(md).unwrap_err(),
+            TaskMarkdownError::MissingSection("Input")
+        );
+
+        let md = "## Goal\ng\n## Input\ni\n## Acceptance Criteria\nnot a list\n## Deliverable Type\ntext\n";
+        assert_eq!(
+            parse_task_markdown(md).unwrap_err(),
+            TaskMarkdownError::EmptySection("Acceptance Criteria")
+        );
+
+        let md = "## Goal\ng\n## Input\ni\n## Acceptance Criteria\n- a\n## Deliverable Type\nmarkdown\n";
+        assert_eq!(
+            parse_task_markdown(md).unwrap_err(),
+            TaskMarkdownError::InvalidDeliverableType("markdown".to_string())
+        );
+    }
+}
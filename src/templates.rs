@@ -0,0 +1,110 @@
+use crate::types::{DeliverableType, TaskSpec};
+
+/// Names accepted by `--template`
+pub const TEMPLATE_NAMES: &[&str] = &["summarize", "code-review", "translate"];
+
+fn criteria(items: &[&str]) -> Vec<String> {
+    items.iter().map(|c| c.to_string()).collect()
+}
+
+/// Built-in `TaskSpec` for a common workflow, with `goal`, `acceptance_criteria` and
+/// `deliverable_type` filled in and an empty `input` for the caller to supply.
+/// `None` for an unknown name.
+pub fn template(name: &str) -> Option<TaskSpec> {
+    let (goal, acceptance_criteria, deliverable_type, hints) = match name {
+        "summarize" => (
+            "Summarize the input into a short list of key points",
+            criteria(&[
+                "3 to 5 bullet points",
+                "<= 100 words total",
+                "covers the main points of the input",
+                "no information that is not in the input",
+            ]),
+            DeliverableType::Text,
+            Some("Lead with the most important point"),
+        ),
+        "code-review" => (
+            "Review the code in the input and report concrete problems with fixes",
+            criteria(&[
+                "every finding names the affected line or construct",
+                "bugs and security issues are listed before style issues",
+                "each finding includes a suggested fix",
+                "no findings about code that is not in the input",
+            ]),
+            DeliverableType::Json,
+            Some("Return {\"findings\": [{\"location\", \"severity\", \"issue\", \"fix\"}]}"),
+        ),
+        "translate" => (
+            "Translate the input into English, preserving meaning and tone",
+            criteria(&[
+                "the full input is translated, nothing omitted",
+                "meaning and tone match the original",
+                "names, numbers and code are kept unchanged",
+                "reads naturally to a native speaker",
+            ]),
+            DeliverableType::Text,
+            Some("Name the target language in hints to translate into something other than English"),
+        ),
+        _ => return None,
+    };
+    Some(TaskSpec {
+        task_id: uuid::Uuid::new_v4().to_string(),
+        goal: goal.to_string(),
+        input: String::new(),
+        acceptance_criteria,
+        deliverable_type,
+        hints: hints.map(str::to_string),
+        json_schema: None,
+        deadline_ms: None,
+        scoring_guidance: None,
+    })
+}
+
+/// `template` for the `--template` value parser, listing the known names on error
+pub fn parse_template(name: &str) -> Result<TaskSpec, String> {
+    template(name).ok_or_else(|| {
+        format!("unknown template '{}' (expected one of: {})", name, TEMPLATE_NAMES.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_template_is_a_text_task() {
+        let task = template("summarize").unwrap();
+        assert!(matches!(task.deliverable_type, DeliverableType::Text));
+        assert!(task.goal.starts_with("Summarize"));
+        assert_eq!(task.acceptance_criteria.len(), 4);
+        assert!(task.input.is_empty());
+    }
+
+    #[test]
+    fn code_review_template_is_a_json_task() {
+        let task = template("code-review").unwrap();
+        assert!(matches!(task.deliverable_type, DeliverableType::Json));
+        assert!(task.goal.starts_with("Review the code"));
+        assert!(task.acceptance_criteria.iter().any(|c| c.contains("suggested fix")));
+        assert!(task.input.is_empty());
+    }
+
+    #[test]
+    fn translate_template_is_a_text_task() {
+        let task = template("translate").unwrap();
+        assert!(matches!(task.deliverable_type, DeliverableType::Text));
+        assert!(task.goal.starts_with("Translate"));
+        assert!(task.acceptance_criteria.iter().any(|c| c.contains("unchanged")));
+        assert!(task.input.is_empty());
+    }
+
+    #[test]
+    fn every_listed_name_resolves_and_unknown_names_do_not() {
+        for name in TEMPLATE_NAMES {
+            assert!(template(name).is_some(), "{}", name);
+        }
+        assert!(template("poem").is_none());
+        let err = parse_template("poem").unwrap_err();
+        assert!(err.contains("summarize, code-review, translate"), "{}", err);
+    }
+}
@@ -0,0 +1,105 @@
+//! Shared helpers for unit tests that talk to a mocked DeepSeek endpoint.
+
+use serde_json::{Value as JsonValue, json};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::config::Config;
+use crate::deepseek::DeepSeekClient;
+use crate::types::{DeliverableType, TaskSpec};
+
+/// Config pointing at the given mock server
+pub fn mock_config(server: &MockServer) -> Config {
+    Config {
+        api_key: "test_key".to_string(),
+        base_url: server.uri(),
+        timeout: 5,
+        ..Config::default()
+    }
+}
+
+/// Client pointing at the given mock server
+pub fn mock_client(server: &MockServer) -> DeepSeekClient {
+    DeepSeekClient::new(mock_config(server)).expect("valid mock config")
+}
+
+/// Chat Completions body whose single choice carries `content`
+pub fn completion_body(content: &str) -> JsonValue {
+    json!({
+        "choices": [
+            { "message": { "role": "assistant", "content": content } }
+        ]
+    })
+}
+
+/// Mount a mock answering every chat completion with `content`
+pub async fn mount_completion(server: &MockServer, content: &str) {
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion_body(content)))
+        .mount(server)
+        .await;
+}
+
+/// Server-Sent Events body of a streamed completion delivering `deltas`, then `[DONE]`
+pub fn sse_body(deltas: &[&str]) -> String {
+    let mut body: String = deltas
+        .iter()
+        .map(|d| format!("data: {}\n\n", json!({ "choices": [{ "delta": { "content": d } }] })))
+        .collect();
+    body.push_str("data: [DONE]\n\n");
+    body
+}
+
+/// Minimal TaskSpec used across tests
+pub fn sample_task() -> TaskSpec {
+    TaskSpec {
+        task_id: "task-1".to_string(),
+        goal: "Summarize".to_string(),
+        input: "Some input".to_string(),
+        acceptance_criteria: vec!["exactly 3 bullets".to_string(), "no fluff".to_string()],
+        deliverable_type: DeliverableType::Text,
+        hints: None,
+        json_schema: None,
+        deadline_ms: None,
+        scoring_guidance: None,
+    }
+}
+
+/// A SolutionV1 JSON document as a model would return it
+pub fn sample_solution_json() -> String {
+    json!({
+        "schema_version": "solution_v1",
+        "task_id": "task-1",
+        "solution_id": "sol-1",
+        "model_used": { "name": "deepseek-chat", "temperature": 0.7 },
+        "deliverable_type": "text",
+        "deliverable": { "text": "- a\n- b\n- c" },
+        "evidence": { "system_prompt": "..." },
+        "usage": { "prompt_tokens": 1, "completion_tokens": 2 },
+        "created_at": "2024-01-01T00:00:00Z"
+    })
+    .to_string()
+}
+
+/// A ValidationV1 JSON document as a model would return it
+pub fn sample_validation_json() -> String {
+    json!({
+        "schema_version": "validation_v1",
+        "task_id": "task-1",
+        "solution_id": "sol-1",
+        "verdict": "pass",
+        "score": 0.9,
+        "checks": [
+            {
+                "criterion": "exactly 3 bullets",
+                "pass": true,
+                "reason": "three bullets present",
+                "severity": "minor"
+            }
+        ],
+        "model_used": { "name": "deepseek-reasoner", "temperature": 0.7 },
+        "created_at": "2024-01-01T00:00:00Z"
+    })
+    .to_string()
+}
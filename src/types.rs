@@ -1,7 +1,25 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::summary::TextSummary;
+
+/// Deserialize an enum from its wire name through `FromStr`. A name the model made up (e.g.
+/// `deliverable_type: "markdown"`) maps to `fallback` with a warning instead of failing the
+/// whole document.
+fn deserialize_lenient<'de, D, T>(deserializer: D, fallback: T, what: &str) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr + std::fmt::Debug,
+{
+    let name = String::deserialize(deserializer)?;
+    Ok(name.parse().unwrap_or_else(|_| {
+        tracing::warn!("Unknown {} '{}'; using {:?}", what, name, fallback);
+        fallback
+    }))
+}
+
+/// Unknown names deserialize as `Text`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "snake_case")] 
 pub enum DeliverableType {
     Text,
@@ -9,6 +27,36 @@ pub enum DeliverableType {
     Code,
 }
 
+impl DeliverableType {
+    /// Wire name, as used in TaskSpec/SolutionV1 JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliverableType::Text => "text",
+            DeliverableType::Json => "json",
+            DeliverableType::Code => "code",
+        }
+    }
+}
+
+impl std::str::FromStr for DeliverableType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(DeliverableType::Text),
+            "json" => Ok(DeliverableType::Json),
+            "code" => Ok(DeliverableType::Code),
+            other => Err(format!("unknown deliverable type '{}'", other)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DeliverableType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_lenient(deserializer, DeliverableType::Text, "deliverable_type")
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskSpec {
     pub task_id: String,
@@ -18,6 +66,196 @@ pub struct TaskSpec {
     pub deliverable_type: DeliverableType,
     #[serde(skip_serializing_if = "Option::is_none")] 
     pub hints: Option<String>,
+    /// JSON Schema a `Json` deliverable must conform to
+    #[serde(default, skip_serializing_if = "Option::is_none")] 
+    pub json_schema: Option<JsonValue>,
+    /// Longest the whole pipeline may take for this task, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_ms: Option<u64>,
+    /// How the auditor should weigh the criteria (e.g. "correctness counts twice as much as
+    /// style"), added to its instructions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoring_guidance: Option<String>,
+}
+
+impl TaskSpec {
+    /// Check a `Json` deliverable against `json_schema`, one message per violation.
+    /// Empty when the task has no schema or is not a `Json` task.
+    pub fn json_schema_violations(&self, deliverable: &Deliverable) -> Vec<String> {
+        let Some(schema) = &self.json_schema else {
+            return Vec::new();
+        };
+        if !matches!(self.deliverable_type, DeliverableType::Json) {
+            return Vec::new();
+        }
+        let Some(instance) = &deliverable.json else {
+            return vec!["deliverable.json is missing".to_string()];
+        };
+        let validator = match jsonschema::validator_for(schema) {
+            Ok(validator) => validator,
+            Err(e) => return vec![format!("invalid json_schema: {}", e)],
+        };
+        validator
+            .iter_errors(instance)
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                if path.is_empty() {
+                    e.to_string()
+                } else {
+                    format!("{}: {}", path, e)
+                }
+            })
+            .collect()
+    }
+}
+
+impl TaskSpec {
+    /// Drop acceptance criteria repeating an earlier one (trimmed, case-insensitive), keeping
+    /// the first occurrence in place. Returns the number removed.
+    pub fn dedupe_criteria(&mut self) -> usize {
+        let before = self.acceptance_criteria.len();
+        let mut seen = std::collections::HashSet::new();
+        self.acceptance_criteria
+            .retain(|criterion| seen.insert(criterion.trim().to_lowercase()));
+        before - self.acceptance_criteria.len()
+    }
+}
+
+/// Default of `--max-input-bytes`, well below the model's context window
+pub const DEFAULT_MAX_INPUT_BYTES: usize = 200_000;
+
+/// Appended to an input cut short by `--truncate`
+pub const TRUNCATION_MARKER: &str = "[truncated]";
+
+/// `TaskSpec.input` is over the `--max-input-bytes` limit
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("task input is {size} bytes, over the {limit}-byte limit (--max-input-bytes); pass --truncate to trim it")]
+pub struct InputTooLarge {
+    pub size: usize,
+    pub limit: usize,
+}
+
+impl TaskSpec {
+    /// Enforce `max_bytes` on `input`. An oversized input is an error, or with `truncate` is cut
+    /// at the last character boundary within the limit and marked with `TRUNCATION_MARKER`;
+    /// the returned note describes the cut for the solution's evidence.
+    pub fn limit_input(&mut self, max_bytes: usize, truncate: bool) -> Result<Option<String>, InputTooLarge> {
+        let size = self.input.len();
+        if size <= max_bytes {
+            return Ok(None);
+        }
+        if !truncate {
+            return Err(InputTooLarge { size, limit: max_bytes });
+        }
+        let mut cut = max_bytes;
+        while !self.input.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        self.input.truncate(cut);
+        self.input.push('\n');
+        self.input.push_str(TRUNCATION_MARKER);
+        Ok(Some(format!("Input truncated from {} to {} bytes", size, cut)))
+    }
+}
+
+/// Default of `--max-criteria`: far more than a real task needs, but it bounds the audit prompt
+pub const DEFAULT_MAX_CRITERIA: usize = 100;
+
+/// `TaskSpec.acceptance_criteria` is over the `--max-criteria` limit
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("task has {count} acceptance criteria, over the limit of {limit} (--max-criteria); pass --truncate-criteria to keep only the first {limit}")]
+pub struct TooManyCriteria {
+    pub count: usize,
+    pub limit: usize,
+}
+
+impl TaskSpec {
+    /// Enforce `max` on `acceptance_criteria`. Too many criteria is an error, or with
+    /// `truncate` only the first `max` are kept; the returned note describes the cut for the
+    /// solution's evidence.
+    pub fn limit_criteria(&mut self, max: usize, truncate: bool) -> Result<Option<String>, TooManyCriteria> {
+        let count = self.acceptance_criteria.len();
+        if count <= max {
+            return Ok(None);
+        }
+        if !truncate {
+            return Err(TooManyCriteria { count, limit: max });
+        }
+        self.acceptance_criteria.truncate(max);
+        Ok(Some(format!("Acceptance criteria truncated from {} to {}", count, max)))
+    }
+}
+
+/// A `TaskSpec` could not be read from a stream (`--stdin-task-json`)
+#[derive(Debug, thiserror::Error)]
+pub enum TaskJsonError {
+    #[error("no TaskSpec JSON on {0}")]
+    Empty(&'static str),
+    #[error("invalid TaskSpec JSON on {source_name}: {error}")]
+    Invalid {
+        source_name: &'static str,
+        error: serde_json::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl TaskSpec {
+    /// Read a whole `TaskSpec` JSON document from `reader`, named `source_name` in errors
+    pub async fn read_json(
+        mut reader: impl tokio::io::AsyncRead + Unpin,
+        source_name: &'static str,
+    ) -> Result<Self, TaskJsonError> {
+        use tokio::io::AsyncReadExt;
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            return Err(TaskJsonError::Empty(source_name));
+        }
+        serde_json::from_slice(&bytes).map_err(|error| TaskJsonError::Invalid { source_name, error })
+    }
+}
+
+/// Criterion of the deterministic deliverable-type check added to every audit
+pub const DELIVERABLE_TYPE_CRITERION: &str = "deliverable_type matches the task";
+
+impl TaskSpec {
+    /// Grade the most basic contract without the model: the solution declares the requested
+    /// `deliverable_type` and fills the matching `deliverable` field. A failure is critical.
+    pub fn deliverable_type_check(&self, solution: &SolutionV1) -> CheckResult {
+        let expected = self.deliverable_type.as_str();
+        let present = match self.deliverable_type {
+            DeliverableType::Text => solution.deliverable.text.is_some(),
+            DeliverableType::Json => solution.deliverable.json.is_some(),
+            DeliverableType::Code => solution.deliverable.code.is_some(),
+        };
+        let (pass_, reason) = if solution.deliverable_type != self.deliverable_type {
+            (
+                false,
+                format!(
+                    "task requests '{}' but the solution is '{}'",
+                    expected,
+                    solution.deliverable_type.as_str()
+                ),
+            )
+        } else if !present {
+            (false, format!("deliverable.{} is missing", expected))
+        } else {
+            (true, format!("solution is a '{}' deliverable as requested", expected))
+        };
+        CheckResult {
+            criterion: DELIVERABLE_TYPE_CRITERION.to_string(),
+            pass_,
+            reason,
+            severity: if pass_ { Severity::Minor } else { Severity::Critical },
+            suggested_fix: (!pass_)
+                .then(|| format!("Return the deliverable as deliverable.{}", expected)),
+            partial_score: None,
+            unmatched: false,
+            evidence_lines: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +285,22 @@ pub struct Evidence {
     pub system_prompt: String,
     #[serde(skip_serializing_if = "Option::is_none")] 
     pub usage_note: Option<String>,
+    /// Violations of `TaskSpec::json_schema`, filled in after generation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")] 
+    pub schema_violations: Vec<String>,
+    /// Key points and word count of a text deliverable (`--with-summary`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<TextSummary>,
+}
+
+impl Evidence {
+    /// Put `note` in front of any existing `usage_note`, separated by "; "
+    pub fn prepend_note(&mut self, note: &str) {
+        self.usage_note = Some(match self.usage_note.take() {
+            Some(existing) if !existing.trim().is_empty() => format!("{}; {}", note, existing),
+            _ => note.to_string(),
+        });
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -66,9 +320,13 @@ pub struct SolutionV1 {
     pub evidence: Evidence,
     pub usage: Usage,
     pub created_at: String, // RFC3339
+    /// Originating task, embedded for self-contained artifacts (`--embed-task`)
+    #[serde(default, skip_serializing_if = "Option::is_none")] 
+    pub task_spec: Option<TaskSpec>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Unknown names deserialize as `Fail`, so a verdict the model made up never passes
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")] 
 pub enum Verdict {
     Pass,
@@ -76,6 +334,17 @@ pub enum Verdict {
     Fail,
 }
 
+impl Verdict {
+    /// Process exit code for `--verdict-only`: 0 pass, 2 warn, 3 fail (1 stays reserved for errors)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Verdict::Pass => 0,
+            Verdict::Warn => 2,
+            Verdict::Fail => 3,
+        }
+    }
+}
+
 impl std::fmt::Display for Verdict {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self { Verdict::Pass => "pass", Verdict::Warn => "warn", Verdict::Fail => "fail" };
@@ -83,11 +352,52 @@ impl std::fmt::Display for Verdict {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::str::FromStr for Verdict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pass" => Ok(Verdict::Pass),
+            "warn" => Ok(Verdict::Warn),
+            "fail" => Ok(Verdict::Fail),
+            other => Err(format!("unknown verdict '{}'", other)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Verdict {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_lenient(deserializer, Verdict::Fail, "verdict")
+    }
+}
+
+/// Unknown names deserialize as `Major`
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")] 
 pub enum Severity {
     Minor,
     Major,
+    /// Must-not-ship violation (e.g. a security issue); a failing critical check forces `Verdict::Fail`
+    Critical,
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "minor" => Ok(Severity::Minor),
+            "major" => Ok(Severity::Major),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!("unknown severity '{}'", other)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_lenient(deserializer, Severity::Major, "severity")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +409,23 @@ pub struct CheckResult {
     pub severity: Severity,
     #[serde(skip_serializing_if = "Option::is_none")] 
     pub suggested_fix: Option<String>,
+    /// Degree to which the criterion is met, in [0.0, 1.0]; absent means 1.0 if passed, else 0.0
+    #[serde(default, skip_serializing_if = "Option::is_none")] 
+    pub partial_score: Option<f32>,
+    /// No acceptance criterion resembles `criterion` (set by `--align-criteria`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")] 
+    pub unmatched: bool,
+    /// 1-based deliverable lines supporting the outcome (set with `--cite-lines`)
+    #[serde(default, skip_serializing_if = "Option::is_none")] 
+    pub evidence_lines: Option<Vec<u32>>,
+}
+
+impl CheckResult {
+    /// Credit this check contributes to the overall score, in [0.0, 1.0]
+    pub fn credit(&self) -> f32 {
+        self.partial_score
+            .unwrap_or(if self.pass_ { 1.0 } else { 0.0 })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,6 +440,331 @@ pub struct ValidationV1 {
     pub suggested_rewrite: Option<JsonValue>,
     pub model_used: ModelUsed,
     pub created_at: String, // RFC3339
+    /// Inline `<think>` reasoning the auditor's response carried (`--keep-reasoning`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+}
+
+/// One entry of a `validations.json` audit trail (`--append-validations`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationRecord {
+    pub appended_at: String, // RFC3339
+    pub validation: ValidationV1,
+}
+
+impl ValidationV1 {
+    /// Failing checks with `Severity::Critical`
+    pub fn failed_critical_checks(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|c| !c.pass_ && matches!(c.severity, Severity::Critical))
+    }
+
+    /// Drop `partial_score` values outside [0.0, 1.0] (or NaN) so those checks fall back to
+    /// `pass`. Returns the number of values dropped.
+    pub fn discard_invalid_partial_scores(&mut self) -> usize {
+        let mut dropped = 0;
+        for check in &mut self.checks {
+            if check.partial_score.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+                check.partial_score = None;
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+
+    /// Clamp `score` into [0.0, 1.0], replacing NaN and infinities with 0.0.
+    /// Returns the original score if it was corrected.
+    pub fn normalize_score(&mut self) -> Option<f32> {
+        let original = self.score;
+        self.score = if original.is_finite() { original.clamp(0.0, 1.0) } else { 0.0 };
+        (self.score != original || original.is_nan()).then_some(original)
+    }
+
+    /// Force the verdict to `Fail` when a critical check failed, regardless of score.
+    /// Returns true if the verdict was changed.
+    pub fn enforce_critical_failures(&mut self) -> bool {
+        if self.failed_critical_checks().next().is_some() && !matches!(self.verdict, Verdict::Fail) {
+            self.verdict = Verdict::Fail;
+            return true;
+        }
+        false
+    }
 }
 
 // (Removed duplicate AuditInput; the canonical type lives in `crate::agents::AuditInput`)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validation(verdict: &str, checks: JsonValue) -> ValidationV1 {
+        serde_json::from_value(serde_json::json!({
+            "schema_version": "validation_v1",
+            "task_id": "t",
+            "solution_id": "s",
+            "verdict": verdict,
+            "score": 0.95,
+            "checks": checks,
+            "model_used": { "name": "deepseek-reasoner", "temperature": 0.0 },
+            "created_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn unknown_enum_values_map_to_safe_defaults() {
+        let validation = validation(
+            "mostly_pass",
+            serde_json::json!([
+                { "criterion": "c", "pass": false, "reason": "r", "severity": "blocker" },
+                { "criterion": "d", "pass": true, "reason": "r", "severity": "Critical" }
+            ]),
+        );
+        assert!(matches!(validation.verdict, Verdict::Fail));
+        assert!(matches!(validation.checks[0].severity, Severity::Major));
+        assert!(matches!(validation.checks[1].severity, Severity::Critical));
+
+        let mut solution: serde_json::Value =
+            serde_json::from_str(&crate::test_support::sample_solution_json()).unwrap();
+        solution["deliverable_type"] = serde_json::json!("markdown");
+        let solution: SolutionV1 = serde_json::from_value(solution).unwrap();
+        assert_eq!(solution.deliverable_type, DeliverableType::Text);
+
+        // Only unknown names are lenient; a wrong JSON type still fails
+        assert!(serde_json::from_str::<Verdict>("1").is_err());
+    }
+
+    fn json_task(schema: JsonValue) -> TaskSpec {
+        TaskSpec {
+            task_id: "t".to_string(),
+            goal: "g".to_string(),
+            input: "i".to_string(),
+            acceptance_criteria: vec![],
+            deliverable_type: DeliverableType::Json,
+            hints: None,
+            json_schema: Some(schema),
+            deadline_ms: None,
+            scoring_guidance: None,
+        }
+    }
+
+    #[test]
+    fn duplicate_criteria_collapse_to_their_first_occurrence() {
+        let mut task = json_task(serde_json::json!({}));
+        task.acceptance_criteria = ["exactly 3 bullets", "no fluff", " Exactly 3 Bullets ", "<= 80 words", "NO FLUFF"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+
+        assert_eq!(task.dedupe_criteria(), 2);
+        assert_eq!(task.acceptance_criteria, vec!["exactly 3 bullets", "no fluff", "<= 80 words"]);
+        assert_eq!(task.dedupe_criteria(), 0);
+    }
+
+    fn json_deliverable(json: JsonValue) -> Deliverable {
+        Deliverable { text: None, json: Some(json), code: None }
+    }
+
+    fn person_schema() -> JsonValue {
+        serde_json::json!({
+            "type": "object",
+            "required": ["name", "age"],
+            "properties": {
+                "name": { "type": "string" },
+                "age": { "type": "integer", "minimum": 0 }
+            }
+        })
+    }
+
+    #[test]
+    fn conforming_json_deliverable_has_no_violations() {
+        let task = json_task(person_schema());
+        let deliverable = json_deliverable(serde_json::json!({ "name": "Ada", "age": 36 }));
+        assert!(task.json_schema_violations(&deliverable).is_empty());
+    }
+
+    #[test]
+    fn non_conforming_json_deliverable_lists_violations() {
+        let task = json_task(person_schema());
+        let deliverable = json_deliverable(serde_json::json!({ "name": 7 }));
+        let violations = task.json_schema_violations(&deliverable);
+        assert_eq!(violations.len(), 2, "{:?}", violations);
+        assert!(violations.iter().any(|v| v.contains("age")));
+        assert!(violations.iter().any(|v| v.starts_with("/name:")));
+    }
+
+    #[test]
+    fn missing_json_deliverable_is_a_violation() {
+        let task = json_task(person_schema());
+        let deliverable = Deliverable { text: Some("{}".to_string()), json: None, code: None };
+        assert_eq!(
+            task.json_schema_violations(&deliverable),
+            vec!["deliverable.json is missing".to_string()]
+        );
+    }
+
+    #[test]
+    fn schema_is_ignored_for_non_json_tasks() {
+        let mut task = json_task(person_schema());
+        task.deliverable_type = DeliverableType::Text;
+        let deliverable = Deliverable { text: Some("hi".to_string()), json: None, code: None };
+        assert!(task.json_schema_violations(&deliverable).is_empty());
+    }
+
+    #[test]
+    fn solution_round_trips_with_and_without_embedded_task() {
+        let plain: SolutionV1 =
+            serde_json::from_str(&crate::test_support::sample_solution_json()).unwrap();
+        assert!(plain.task_spec.is_none());
+        let json = serde_json::to_value(&plain).unwrap();
+        assert!(json.get("task_spec").is_none());
+        let back: SolutionV1 = serde_json::from_value(json).unwrap();
+        assert!(back.task_spec.is_none());
+
+        let task = crate::test_support::sample_task();
+        let embedded = SolutionV1 { task_spec: Some(task.clone()), ..plain };
+        let json = serde_json::to_value(&embedded).unwrap();
+        assert_eq!(json["task_spec"]["task_id"], task.task_id.as_str());
+        let back: SolutionV1 = serde_json::from_value(json).unwrap();
+        let back_task = back.task_spec.unwrap();
+        assert_eq!(back_task.goal, task.goal);
+        assert_eq!(back_task.acceptance_criteria, task.acceptance_criteria);
+    }
+
+    #[test]
+    fn deliverable_type_mismatch_is_a_critical_failure() {
+        let task = json_task(person_schema());
+        let mut solution: SolutionV1 =
+            serde_json::from_str(&crate::test_support::sample_solution_json()).unwrap();
+
+        let check = task.deliverable_type_check(&solution);
+        assert!(!check.pass_);
+        assert!(matches!(check.severity, Severity::Critical));
+        assert_eq!(check.reason, "task requests 'json' but the solution is 'text'");
+
+        solution.deliverable_type = DeliverableType::Json;
+        let check = task.deliverable_type_check(&solution);
+        assert!(!check.pass_);
+        assert_eq!(check.reason, "deliverable.json is missing");
+
+        solution.deliverable = json_deliverable(serde_json::json!({ "name": "Ada", "age": 36 }));
+        assert!(task.deliverable_type_check(&solution).pass_);
+    }
+
+    #[test]
+    fn partial_score_defaults_from_pass_and_rejects_out_of_range() {
+        let mut v = validation(
+            "warn",
+            serde_json::json!([
+                { "criterion": "a", "pass": true, "reason": "", "severity": "minor" },
+                { "criterion": "b", "pass": false, "reason": "", "severity": "minor" },
+                { "criterion": "c", "pass": false, "reason": "", "severity": "minor", "partial_score": 0.6 },
+                { "criterion": "d", "pass": true, "reason": "", "severity": "minor", "partial_score": 1.5 }
+            ]),
+        );
+        let credits: Vec<f32> = v.checks.iter().map(CheckResult::credit).collect();
+        assert_eq!(credits, vec![1.0, 0.0, 0.6, 1.5]);
+
+        assert_eq!(v.discard_invalid_partial_scores(), 1);
+        assert_eq!(v.checks[3].credit(), 1.0);
+        assert_eq!(v.checks[2].partial_score, Some(0.6));
+
+        let json = serde_json::to_value(&v.checks[0]).unwrap();
+        assert!(json.get("partial_score").is_none());
+    }
+
+    #[test]
+    fn oversized_input_is_an_error_without_truncate() {
+        let mut task = json_task(serde_json::json!({}));
+        task.input = "x".repeat(11);
+        assert_eq!(task.limit_input(11, false), Ok(None));
+        assert_eq!(task.limit_input(10, false), Err(InputTooLarge { size: 11, limit: 10 }));
+        assert_eq!(task.input.len(), 11);
+    }
+
+    #[test]
+    fn truncate_cuts_at_a_char_boundary_and_marks_the_input() {
+        let mut task = json_task(serde_json::json!({}));
+        // "é" is two bytes: a 4-byte limit would split the second one
+        task.input = "aéé".to_string();
+        let note = task.limit_input(4, true).unwrap();
+        assert_eq!(task.input, format!("aé\n{}", TRUNCATION_MARKER));
+        assert_eq!(note.as_deref(), Some("Input truncated from 5 to 3 bytes"));
+    }
+
+    #[test]
+    fn too_many_criteria_is_an_error_unless_truncated() {
+        let mut task = json_task(serde_json::json!({}));
+        task.acceptance_criteria = (1..=5).map(|i| format!("criterion {}", i)).collect();
+        assert_eq!(task.limit_criteria(5, false), Ok(None));
+        assert_eq!(task.limit_criteria(3, false), Err(TooManyCriteria { count: 5, limit: 3 }));
+        assert_eq!(task.acceptance_criteria.len(), 5);
+
+        let note = task.limit_criteria(3, true).unwrap();
+        assert_eq!(task.acceptance_criteria, vec!["criterion 1", "criterion 2", "criterion 3"]);
+        assert_eq!(note.as_deref(), Some("Acceptance criteria truncated from 5 to 3"));
+    }
+
+    #[test]
+    fn normalize_score_clamps_out_of_range_values() {
+        let mut v = validation("pass", serde_json::json!([]));
+        for (raw, expected) in [(1.5, 1.0), (-0.2, 0.0)] {
+            v.score = raw;
+            assert_eq!(v.normalize_score(), Some(raw));
+            assert_eq!(v.score, expected);
+        }
+        v.score = 0.7;
+        assert_eq!(v.normalize_score(), None);
+        assert_eq!(v.score, 0.7);
+    }
+
+    #[test]
+    fn normalize_score_replaces_nan_and_infinity_with_zero() {
+        let mut v = validation("pass", serde_json::json!([]));
+        for raw in [f32::INFINITY, f32::NEG_INFINITY] {
+            v.score = raw;
+            assert_eq!(v.normalize_score(), Some(raw));
+            assert_eq!(v.score, 0.0);
+        }
+        v.score = f32::NAN;
+        assert!(v.normalize_score().is_some_and(f32::is_nan));
+        assert_eq!(v.score, 0.0);
+    }
+
+    #[test]
+    fn severity_serde_accepts_all_levels() {
+        for (raw, expected) in [("minor", "Minor"), ("major", "Major"), ("critical", "Critical")] {
+            let sev: Severity = serde_json::from_value(JsonValue::from(raw)).unwrap();
+            assert_eq!(format!("{:?}", sev), expected);
+            assert_eq!(serde_json::to_value(&sev).unwrap(), JsonValue::from(raw));
+        }
+    }
+
+    #[test]
+    fn failing_critical_check_forces_fail() {
+        let mut v = validation(
+            "pass",
+            serde_json::json!([
+                { "criterion": "a", "pass": true, "reason": "", "severity": "minor" },
+                { "criterion": "no secrets", "pass": false, "reason": "", "severity": "critical" }
+            ]),
+        );
+        assert!(v.enforce_critical_failures());
+        assert!(matches!(v.verdict, Verdict::Fail));
+        assert_eq!(v.failed_critical_checks().count(), 1);
+    }
+
+    #[test]
+    fn passing_critical_or_failing_major_keeps_verdict() {
+        let mut v = validation(
+            "warn",
+            serde_json::json!([
+                { "criterion": "no secrets", "pass": true, "reason": "", "severity": "critical" },
+                { "criterion": "b", "pass": false, "reason": "", "severity": "major" }
+            ]),
+        );
+        assert!(!v.enforce_critical_failures());
+        assert!(matches!(v.verdict, Verdict::Warn));
+    }
+}
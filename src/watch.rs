@@ -0,0 +1,181 @@
+//! `--watch`: re-run the pipeline whenever the task file changes. Changes are detected by
+//! polling the file's modification time and size, and debounced so an editor's burst of
+//! writes triggers a single run.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use futures::stream::{self, Stream, StreamExt};
+
+/// How often the task file is checked
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the file must stay unchanged before a change is reported
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Modification time and size of a file; `None` while it does not exist
+type Fingerprint = Option<(SystemTime, u64)>;
+
+async fn fingerprint(path: PathBuf) -> Fingerprint {
+    let metadata = tokio::fs::metadata(&path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Polls one file and reports each settled change to it
+#[derive(Debug)]
+pub struct TaskWatcher {
+    path: PathBuf,
+    poll_interval: Duration,
+    debounce: Duration,
+}
+
+impl TaskWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), poll_interval: DEFAULT_POLL_INTERVAL, debounce: DEFAULT_DEBOUNCE }
+    }
+
+    #[cfg(test)]
+    pub fn with_intervals(mut self, poll_interval: Duration, debounce: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.debounce = debounce;
+        self
+    }
+
+    /// One item per change to the file, relative to its state when this is called.
+    /// A deleted file is waited for; its re-creation counts as a change.
+    pub async fn changes(self) -> impl Stream<Item = ()> {
+        let path = self.path.clone();
+        self.changes_of(move || fingerprint(path.clone())).await
+    }
+
+    /// `changes`, with the file's state read by `probe`
+    async fn changes_of<P, Fut>(self, probe: P) -> impl Stream<Item = ()>
+    where
+        P: Fn() -> Fut,
+        Fut: Future<Output = Fingerprint>,
+    {
+        let seen = probe().await;
+        stream::unfold((self, probe, seen), |(watcher, probe, mut seen)| async move {
+            let mut reported_missing = false;
+            loop {
+                tokio::time::sleep(watcher.poll_interval).await;
+                let mut current = probe().await;
+                if current.is_none() {
+                    if !reported_missing {
+                        tracing::warn!(
+                            "{} is missing; waiting for it to reappear",
+                            watcher.path.display()
+                        );
+                        reported_missing = true;
+                    }
+                    seen = None;
+                    continue;
+                }
+                if current == seen {
+                    continue;
+                }
+                // Wait for the edits to settle before reporting
+                loop {
+                    tokio::time::sleep(watcher.debounce).await;
+                    let settled = probe().await;
+                    if settled == current {
+                        break;
+                    }
+                    current = settled;
+                }
+                if current.is_none() {
+                    continue;
+                }
+                tracing::info!("{} changed", watcher.path.display());
+                return Some(((), (watcher, probe, current)));
+            }
+        })
+    }
+}
+
+/// Call `run` once, then again after each item of `changes`. A failed run is reported
+/// and the loop carries on; it ends when `changes` does.
+pub async fn run_on_changes<F, Fut>(changes: impl Stream<Item = ()>, mut run: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut changes = std::pin::pin!(changes);
+    loop {
+        if let Err(e) = run().await {
+            tracing::error!("Run failed: {:#}", e);
+        }
+        println!("Watching for changes (Ctrl+C to stop)...");
+        if changes.next().await.is_none() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn each_change_event_triggers_a_rerun_even_after_failures() {
+        let runs = AtomicUsize::new(0);
+        run_on_changes(stream::iter([(), ()]), || async {
+            let n = runs.fetch_add(1, Ordering::SeqCst);
+            if n == 1 {
+                anyhow::bail!("bad task");
+            }
+            Ok(())
+        })
+        .await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn modifying_or_recreating_the_file_is_reported_once_per_burst() {
+        // The file's state, set by the test instead of read from disk
+        let state = Arc::new(Mutex::new(None::<(SystemTime, u64)>));
+        let write = |state: &Arc<Mutex<Fingerprint>>, version: u64| {
+            let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(version);
+            *state.lock().unwrap() = Some((modified, version));
+        };
+        write(&state, 0);
+        let probe = {
+            let state = state.clone();
+            move || std::future::ready(*state.lock().unwrap())
+        };
+        let watcher = TaskWatcher::new("task.json")
+            .with_intervals(Duration::from_millis(10), Duration::from_millis(50));
+        let mut changes = Box::pin(watcher.changes_of(probe).await);
+
+        let writer = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                for version in 1..=3 {
+                    tokio::time::sleep(Duration::from_millis(15)).await;
+                    write(&state, version);
+                }
+            })
+        };
+        tokio::time::timeout(Duration::from_secs(5), changes.next())
+            .await
+            .expect("change reported")
+            .unwrap();
+        writer.await.unwrap();
+        // The burst settled into one change
+        assert!(
+            tokio::time::timeout(Duration::from_millis(150), changes.next())
+                .await
+                .is_err()
+        );
+
+        *state.lock().unwrap() = None;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        write(&state, 4);
+        tokio::time::timeout(Duration::from_secs(5), changes.next())
+            .await
+            .expect("re-creation reported")
+            .unwrap();
+    }
+}